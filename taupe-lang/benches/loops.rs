@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use taupe::{
+    core::{
+        compilation::compile, environment::Environment, evaluation::evaluate, execution::execute,
+        options::Options, resolution::Resolver,
+    },
+    parser::Parser,
+    translator::Translator,
+};
+
+fn loop_source(bindings: usize) -> String {
+    let mut source = String::new();
+    for i in 0..bindings {
+        source.push_str(&format!("let unused{} = {}\n", i, i));
+    }
+    source.push_str("let mut total = 0\nfor i in [1..=10000] {\n  total += i\n}\n");
+    source
+}
+
+fn loops(c: &mut Criterion) {
+    for bindings in [0, 100] {
+        let tokens = Parser::new().parse(&loop_source(bindings));
+        let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+        let expression = resolved.expression.clone();
+        c.bench_function(&format!("loop with {} bindings in scope", bindings), |b| {
+            b.iter(|| {
+                let mut environment = Environment::new();
+                evaluate(
+                    black_box(&expression),
+                    &mut environment,
+                    &Options::default(),
+                )
+            })
+        });
+
+        let program = compile(&resolved);
+        c.bench_function(
+            &format!("compiled loop with {} bindings in scope", bindings),
+            |b| {
+                b.iter(|| {
+                    let mut environment = Environment::new();
+                    execute(black_box(&program), &mut environment, &Options::default())
+                })
+            },
+        );
+
+        #[cfg(feature = "jit")]
+        c.bench_function(
+            &format!("jit-compiled loop with {} bindings in scope", bindings),
+            |b| {
+                let options = Options {
+                    engine: taupe::core::options::Backend::Jit,
+                    ..Options::default()
+                };
+                b.iter(|| {
+                    let mut environment = Environment::new();
+                    evaluate(black_box(&expression), &mut environment, &options)
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(benches, loops);
+criterion_main!(benches);
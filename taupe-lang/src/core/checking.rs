@@ -0,0 +1,587 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::stdlib;
+
+use super::{
+    expression::{Declaration, Expression, Function, Operand, Pattern, Variable},
+    resolution::Resolved,
+    stack,
+    types::Type,
+    values::{Error, Value},
+};
+
+// The named fields of a constructor, with their types.
+type Fields = Vec<(String, Type)>;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Signature {
+    generics: Vec<String>,
+    parameters: Vec<Type>,
+    returns: Type,
+}
+
+// Computes the type of a resolved tree before it runs, failing with
+// TypeMismatch where an operation cannot succeed whatever the values. The
+// check is gradual: a variable takes its annotation, or else the type of its
+// value unless it is `mut`, and anything whose type is not evident is Unknown,
+// which never fails. Calls to the stdlib are checked against the signature
+// of their builtin, and globals read from a function body are Unknown.
+//
+// Type parameters are opaque within a generic function or type: a `T` only
+// accepts a `T`. Each call of a generic function or constructor instantiates
+// them anew from its arguments, left to right; a parameter no argument tells
+// is Unknown.
+//
+// Types of the current frame's variables are kept by slot; signatures by
+// definition number, and structs and enums by name. They all outlive a
+// single check, as the REPL needs.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Checker {
+    types: Vec<Type>,
+    signatures: HashMap<usize, Signature>,
+    declarations: HashMap<String, Rc<Declaration>>,
+    // The type parameters of the function being checked.
+    generics: Vec<String>,
+}
+
+impl Checker {
+    pub fn new() -> Checker {
+        Checker {
+            types: Vec::new(),
+            signatures: HashMap::new(),
+            declarations: HashMap::new(),
+            generics: Vec::new(),
+        }
+    }
+
+    pub fn check(&mut self, resolved: &Resolved) -> Result<Type, Error> {
+        for declaration in &resolved.declarations {
+            self.declarations
+                .insert(declaration.name.clone(), declaration.clone());
+        }
+        for declaration in &resolved.declarations {
+            for constructor in &declaration.constructors {
+                for (_, field) in &constructor.fields {
+                    Checker::known(field, &declaration.parameters, &self.declarations)?;
+                }
+            }
+        }
+        for function in &resolved.functions {
+            self.generics = function.generics.clone();
+            let mut parameters = Vec::new();
+            for parameter in &function.parameters {
+                parameters.push(self.annotation(&parameter.annotation)?);
+            }
+            let signature = Signature {
+                generics: function.generics.clone(),
+                parameters,
+                returns: self.annotation(&function.returns)?,
+            };
+            self.signatures
+                .insert(function.definition.unwrap_or_default(), signature);
+        }
+        for function in &resolved.functions {
+            self.function(function)?;
+        }
+        self.generics.clear();
+
+        self.expression(&resolved.expression)
+    }
+
+    fn function(&mut self, function: &Function) -> Result<(), Error> {
+        let signature = self.signatures[&function.definition.unwrap_or_default()].clone();
+
+        let caller = std::mem::take(&mut self.types);
+        self.generics = signature.generics;
+        for (parameter, parameter_type) in function.parameters.iter().zip(signature.parameters) {
+            self.declare(parameter, parameter_type);
+        }
+        let body = self.expression(&function.body);
+        self.types = caller;
+
+        let body = body?;
+        if signature.returns.accepts(&body) {
+            Ok(())
+        } else {
+            Err(Error::TypeMismatch(
+                function.name.clone(),
+                vec![signature.returns, body],
+            ))
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<Type, Error> {
+        stack::grow(|| self.node(expression))
+    }
+
+    fn node(&mut self, expression: &Expression) -> Result<Type, Error> {
+        match expression {
+            Expression::Primitive(primitive) => Ok(Type::of(primitive)),
+            Expression::Expression(inner) => self.expression(inner),
+            Expression::Sum { left, right } => {
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                match (&left, &right) {
+                    (Type::Str, Type::Str) => Ok(Type::Str),
+                    (Type::Str | Type::Unknown, Type::Str | Type::Unknown) => Ok(Type::Unknown),
+                    _ => Checker::numeric("Sum", left, right, Type::Int),
+                }
+            }
+            Expression::Product { left, right } => {
+                self.arithmetic("Product", left, right, Type::Int)
+            }
+            Expression::Subtract { left, right } => {
+                self.arithmetic("Subtract", left, right, Type::Int)
+            }
+            Expression::Divide { left, right } => {
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                if left.is_numeric() && right.is_numeric() {
+                    Ok(Type::Float)
+                } else {
+                    Err(Error::TypeMismatch("Divide".to_string(), vec![left, right]))
+                }
+            }
+            Expression::IntegerDivide { left, right } => {
+                self.arithmetic("IntegerDivide", left, right, Type::Int)
+            }
+            Expression::Modulo { left, right } => self.arithmetic("Modulo", left, right, Type::Int),
+            Expression::Power { left, right } => {
+                self.arithmetic("Power", left, right, Type::Unknown)
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expression(condition)?;
+                if !Type::Bool.accepts(&condition) {
+                    return Err(Error::TypeMismatch("If".to_string(), vec![condition]));
+                }
+                let then_type = self.expression(then_branch)?;
+                let else_type = self.expression(else_branch)?;
+                if then_type == else_type {
+                    Ok(then_type)
+                } else {
+                    Ok(Type::Unknown)
+                }
+            }
+            Expression::Compare { left, right, .. } => {
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                if left.is_numeric() && right.is_numeric() {
+                    Ok(Type::Bool)
+                } else {
+                    Err(Error::TypeMismatch(
+                        "Compare".to_string(),
+                        vec![left, right],
+                    ))
+                }
+            }
+            Expression::Bitwise { left, right, .. } => {
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                if Type::Int.accepts(&left) && Type::Int.accepts(&right) {
+                    Ok(Type::Int)
+                } else {
+                    Err(Error::TypeMismatch(
+                        "Bitwise".to_string(),
+                        vec![left, right],
+                    ))
+                }
+            }
+            Expression::Unary { operand, right } => {
+                let right = self.expression(right)?;
+                match (operand, &right) {
+                    (Operand::Negate, _) if right.is_numeric() => Ok(right),
+                    (Operand::Not, Type::Bool | Type::Int | Type::Unknown) => Ok(Type::Bool),
+                    (Operand::Complement, Type::Int | Type::Unknown) => Ok(Type::Int),
+                    _ => Err(Error::TypeMismatch("Unary".to_string(), vec![right])),
+                }
+            }
+            Expression::Let { variables, scope } => {
+                for (pattern, value) in variables {
+                    let value = self.expression(value)?;
+                    self.pattern(pattern, value)?;
+                }
+                self.expression(scope)
+            }
+            Expression::Use { variable } => Ok(match variable.slot {
+                Some(_) if variable.global => Type::Unknown,
+                Some(slot) => self.types.get(slot).cloned().unwrap_or(Type::Unknown),
+                None => match stdlib::constant(&variable.name) {
+                    Some(Value::Float(_)) => Type::Float,
+                    _ => Type::Unknown,
+                },
+            }),
+            Expression::Assign { variable, value } => {
+                let value = self.expression(value)?;
+                let declared = match variable.slot {
+                    Some(slot) => self.types.get(slot).cloned().unwrap_or(Type::Unknown),
+                    None => Type::Unknown,
+                };
+                if declared.accepts(&value) {
+                    Ok(Type::Unit)
+                } else {
+                    Err(Error::TypeMismatch(
+                        variable.name.clone(),
+                        vec![declared, value],
+                    ))
+                }
+            }
+            Expression::For {
+                variable,
+                from,
+                to,
+                body,
+            } => {
+                let (from, to) = (self.expression(from)?, self.expression(to)?);
+                if !Type::Int.accepts(&from) || !Type::Int.accepts(&to) {
+                    return Err(Error::TypeMismatch("For".to_string(), vec![from, to]));
+                }
+                self.declare(variable, Type::Int);
+                self.expression(body)?;
+                Ok(Type::Unit)
+            }
+            Expression::Print { expression } => {
+                self.expression(expression)?;
+                Ok(Type::Unit)
+            }
+            Expression::Chain { left, right } => {
+                self.expression(left)?;
+                self.expression(right)
+            }
+            Expression::Line { statement, .. } => self.expression(statement),
+            Expression::Block { body } => self.expression(body),
+            Expression::Function { scope, .. } => self.expression(scope),
+            Expression::Call {
+                function,
+                definition,
+                arguments,
+            } => {
+                let mut types = Vec::new();
+                for argument in arguments {
+                    types.push(self.expression(argument)?);
+                }
+                let signature = match definition {
+                    Some(definition) => match self.signatures.get(definition) {
+                        Some(signature) => signature.clone(),
+                        None => return Ok(Type::Unknown),
+                    },
+                    None => match stdlib::signature(function) {
+                        Some((parameters, returns)) => Signature {
+                            generics: Vec::new(),
+                            parameters,
+                            returns,
+                        },
+                        None => return Ok(Type::Unknown),
+                    },
+                };
+                let parameters = Type::Tuple(signature.parameters.clone());
+                let arguments = Type::Tuple(types);
+                let mut bindings = HashMap::new();
+                if Checker::bind(&parameters, &arguments, &signature.generics, &mut bindings) {
+                    Ok(Checker::substitute(
+                        &signature.returns,
+                        &signature.generics,
+                        &bindings,
+                    ))
+                } else {
+                    Err(Error::TypeMismatch(
+                        function.clone(),
+                        vec![parameters, arguments],
+                    ))
+                }
+            }
+            Expression::Declare { scope, .. } => self.expression(scope),
+            Expression::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => {
+                let constructor = &declaration.constructors[*constructor];
+                let mut types = Vec::new();
+                for argument in arguments {
+                    types.push(self.expression(argument)?);
+                }
+                let fields = Type::Tuple(constructor.fields.iter().map(|(_, t)| t.clone()).collect());
+                let arguments = Type::Tuple(types);
+                let mut bindings = HashMap::new();
+                if Checker::bind(&fields, &arguments, &declaration.parameters, &mut bindings) {
+                    let parameters = declaration
+                        .parameters
+                        .iter()
+                        .map(|parameter| Type::Named(parameter.clone(), Vec::new()))
+                        .collect();
+                    Ok(Checker::substitute(
+                        &Type::Named(declaration.name.clone(), parameters),
+                        &declaration.parameters,
+                        &bindings,
+                    ))
+                } else {
+                    Err(Error::TypeMismatch(
+                        constructor.name.clone(),
+                        vec![fields, arguments],
+                    ))
+                }
+            }
+            Expression::Field { value, field } => match self.expression(value)? {
+                Type::Unknown => Ok(Type::Unknown),
+                value => {
+                    let found = self.constructors(&value).and_then(|constructors| match constructors.as_slice() {
+                        [(_, fields)] => fields.iter().find(|(name, _)| name == field).cloned(),
+                        _ => None,
+                    });
+                    match found {
+                        Some((_, field)) => Ok(field),
+                        None => Err(Error::TypeMismatch(field.clone(), vec![value])),
+                    }
+                }
+            },
+            Expression::Match { value, arms } => {
+                let value = self.expression(value)?;
+                let mut types = Vec::new();
+                for (pattern, body) in arms {
+                    self.pattern(pattern, value.clone())?;
+                    types.push(self.expression(body)?);
+                }
+                match types.split_first() {
+                    Some((first, rest)) if rest.iter().all(|t| t == first) => Ok(first.clone()),
+                    _ => Ok(Type::Unknown),
+                }
+            }
+            Expression::Tuple { elements } => {
+                let mut types = Vec::new();
+                for element in elements {
+                    types.push(self.expression(element)?);
+                }
+                Ok(Type::Tuple(types))
+            }
+            Expression::Index { tuple, index } => match self.expression(tuple)? {
+                Type::Tuple(types) if *index < types.len() => Ok(types[*index].clone()),
+                Type::Unknown => Ok(Type::Unknown),
+                tuple => Err(Error::TypeMismatch("Index".to_string(), vec![tuple])),
+            },
+            Expression::None {} => Ok(Type::Unknown),
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        name: &str,
+        left: &Expression,
+        right: &Expression,
+        integers: Type,
+    ) -> Result<Type, Error> {
+        let (left, right) = (self.expression(left)?, self.expression(right)?);
+        Checker::numeric(name, left, right, integers)
+    }
+
+    fn numeric(name: &str, left: Type, right: Type, integers: Type) -> Result<Type, Error> {
+        match Type::arithmetic(&left, &right, integers) {
+            Some(result) => Ok(result),
+            None => Err(Error::TypeMismatch(name.to_string(), vec![left, right])),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern, value: Type) -> Result<(), Error> {
+        match (pattern, &value) {
+            (Pattern::Variable(variable), _) => {
+                let declared = match &variable.annotation {
+                    Some(_) => self.annotation(&variable.annotation)?,
+                    None if variable.mutable => Type::Unknown,
+                    None => value.clone(),
+                };
+                if !declared.accepts(&value) {
+                    return Err(Error::TypeMismatch(
+                        variable.name.clone(),
+                        vec![declared, value],
+                    ));
+                }
+                self.declare(variable, declared);
+            }
+            (Pattern::Literal(literal), _) if Type::of(literal).accepts(&value) => {}
+            (Pattern::Tuple(elements), Type::Tuple(types)) if elements.len() == types.len() => {
+                for (element, element_type) in elements.iter().zip(types) {
+                    self.pattern(element, element_type.clone())?;
+                }
+            }
+            (Pattern::List { elements, rest }, Type::List(element_type)) => {
+                for element in elements {
+                    self.pattern(element, element_type.as_ref().clone())?;
+                }
+                if let Some(rest) = rest {
+                    self.pattern(&Pattern::Variable(rest.clone()), value.clone())?;
+                }
+            }
+            (Pattern::Constructor { name, elements }, Type::Named(..)) => {
+                let fields = self.constructors(&value).and_then(|constructors| {
+                    constructors
+                        .into_iter()
+                        .find(|(constructor, _)| constructor == name)
+                });
+                match fields {
+                    Some((_, fields)) if fields.len() == elements.len() => {
+                        for (element, (_, field)) in elements.iter().zip(fields) {
+                            self.pattern(element, field)?;
+                        }
+                    }
+                    _ => return Err(Error::TypeMismatch(name.clone(), vec![value])),
+                }
+            }
+            (Pattern::Tuple(elements) | Pattern::Constructor { elements, .. }, Type::Unknown) => {
+                for element in elements {
+                    self.pattern(element, Type::Unknown)?;
+                }
+            }
+            (Pattern::List { elements, rest }, Type::Unknown) => {
+                for element in elements {
+                    self.pattern(element, Type::Unknown)?;
+                }
+                if let Some(rest) = rest {
+                    self.pattern(&Pattern::Variable(rest.clone()), Type::Unknown)?;
+                }
+            }
+            _ => return Err(Error::TypeMismatch("Let".to_string(), vec![value])),
+        }
+        Ok(())
+    }
+
+    // The constructors of a struct or enum type with their fields, typed for
+    // its type arguments.
+    fn constructors(&self, value: &Type) -> Option<Vec<(String, Fields)>> {
+        let (declaration, arguments) = match value {
+            Type::Named(name, arguments) => (self.declarations.get(name)?, arguments),
+            _ => return None,
+        };
+        let bindings: HashMap<String, Type> = declaration
+            .parameters
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect();
+        let constructors = declaration.constructors.iter().map(|constructor| {
+            let fields = constructor.fields.iter().map(|(field, field_type)| {
+                let field_type = Checker::substitute(field_type, &declaration.parameters, &bindings);
+                (field.clone(), field_type)
+            });
+            (constructor.name.clone(), fields.collect())
+        });
+        Some(constructors.collect())
+    }
+
+    // Matches the types of arguments against the types of parameters that
+    // may mention `generics`, binding each of those at its first mention.
+    fn bind(
+        expected: &Type,
+        actual: &Type,
+        generics: &[String],
+        bindings: &mut HashMap<String, Type>,
+    ) -> bool {
+        match (expected, actual) {
+            (Type::Named(name, arguments), _)
+                if arguments.is_empty() && generics.contains(name) =>
+            {
+                match bindings.get(name) {
+                    Some(bound) if *bound != Type::Unknown => bound.accepts(actual),
+                    _ => {
+                        bindings.insert(name.clone(), actual.clone());
+                        true
+                    }
+                }
+            }
+            (Type::List(expected), Type::List(actual)) => {
+                Checker::bind(expected, actual, generics, bindings)
+            }
+            (Type::Named(expected_name, expected), Type::Named(actual_name, actual))
+                if expected_name != actual_name =>
+            {
+                false
+            }
+            (Type::Tuple(expected), Type::Tuple(actual))
+            | (Type::Named(_, expected), Type::Named(_, actual)) => {
+                expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual)
+                        .all(|(expected, actual)| Checker::bind(expected, actual, generics, bindings))
+            }
+            _ => expected.accepts(actual),
+        }
+    }
+
+    // Replaces the `generics` within a type by what they are bound to, or
+    // Unknown.
+    fn substitute(substituted: &Type, generics: &[String], bindings: &HashMap<String, Type>) -> Type {
+        match substituted {
+            Type::Named(name, arguments) if arguments.is_empty() && generics.contains(name) => {
+                bindings.get(name).cloned().unwrap_or(Type::Unknown)
+            }
+            Type::Named(name, arguments) => Type::Named(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| Checker::substitute(argument, generics, bindings))
+                    .collect(),
+            ),
+            Type::List(element) => {
+                Type::List(Box::new(Checker::substitute(element, generics, bindings)))
+            }
+            Type::Tuple(elements) => Type::Tuple(
+                elements
+                    .iter()
+                    .map(|element| Checker::substitute(element, generics, bindings))
+                    .collect(),
+            ),
+            _ => substituted.clone(),
+        }
+    }
+
+    // Forgets the type of a slot a host filled, which is then Unknown.
+    pub fn define(&mut self, slot: usize) {
+        self.declare(&Variable::resolved(String::new(), slot), Type::Unknown);
+    }
+
+    fn declare(&mut self, variable: &Variable, declared: Type) {
+        if let Some(slot) = variable.slot {
+            if self.types.len() <= slot {
+                self.types.resize(slot + 1, Type::Unknown);
+            }
+            self.types[slot] = declared;
+        }
+    }
+
+    // The type an annotation stands for; no annotation stands for Unknown.
+    fn annotation(&self, annotation: &Option<Type>) -> Result<Type, Error> {
+        match annotation {
+            None => Ok(Type::Unknown),
+            Some(annotation) => Checker::known(annotation, &self.generics, &self.declarations)
+                .map(|_| annotation.clone()),
+        }
+    }
+
+    // Names must be type parameters in scope, or structs and enums given as
+    // many type arguments as they have parameters.
+    pub(crate) fn known(
+        annotation: &Type,
+        generics: &[String],
+        declarations: &HashMap<String, Rc<Declaration>>,
+    ) -> Result<(), Error> {
+        match annotation {
+            Type::Named(name, arguments) if arguments.is_empty() && generics.contains(name) => {
+                Ok(())
+            }
+            Type::Named(name, arguments)
+                if declarations
+                    .get(name)
+                    .is_some_and(|declaration| declaration.parameters.len() == arguments.len()) =>
+            {
+                arguments
+                    .iter()
+                    .try_for_each(|argument| Checker::known(argument, generics, declarations))
+            }
+            Type::Named(name, _) => Err(Error::UndefinedType(name.clone())),
+            Type::List(element) => Checker::known(element, generics, declarations),
+            Type::Tuple(elements) => elements
+                .iter()
+                .try_for_each(|element| Checker::known(element, generics, declarations)),
+            _ => Ok(()),
+        }
+    }
+}
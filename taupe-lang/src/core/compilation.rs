@@ -0,0 +1,375 @@
+use std::rc::Rc;
+
+use super::{
+    expression::{Declaration, Expression, Function, Operand, Pattern, Variable},
+    primitives::Primitive,
+    resolution::Resolved,
+    stack,
+};
+
+// The instructions of the stack machine. Most pop their operands off the
+// value stack and push their result; the numbers they carry index the pools
+// of the chunk they belong to, or the code itself for jumps.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instruction {
+    Constant(usize),
+    Unit,
+    Nil,
+    // A stdlib constant, such as math.pi, by name.
+    Global(usize),
+    Load {
+        slot: usize,
+        name: usize,
+    },
+    // A global read from a function body, by its slot from the bottom of
+    // the stack.
+    LoadGlobal {
+        slot: usize,
+        name: usize,
+    },
+    Assign {
+        slot: usize,
+        name: usize,
+    },
+    // Destructures a value into the slots of a pattern.
+    Bind(usize),
+    // Fails with an undefined variable, for a variable left unresolved.
+    Undefined(usize),
+    Pop,
+    Sum,
+    Product,
+    Divide,
+    Subtract,
+    IntegerDivide,
+    Modulo,
+    Power,
+    Compare(Operand),
+    Bitwise(Operand),
+    Unary(Operand),
+    Print,
+    Tuple(usize),
+    Index(usize),
+    Construct {
+        declaration: usize,
+        constructor: usize,
+        arguments: usize,
+    },
+    Field(usize),
+    Call {
+        definition: usize,
+        name: usize,
+        arguments: usize,
+    },
+    Builtin {
+        name: usize,
+        arguments: usize,
+    },
+    EnterScope,
+    ExitScope,
+    Jump(usize),
+    // Pops the condition of an `if`, jumping when it is false.
+    JumpUnless(usize),
+    // Pops the bounds of a `for` and pushes them back as its counter and
+    // last value, or jumps past the loop when there is nothing to count.
+    Range(usize),
+    // Declares the loop variable in a slot, with the counter's value.
+    Iterate(usize),
+    // Pops the counter and last value once they meet, and otherwise counts
+    // up and jumps back to the start of the loop.
+    Next(usize),
+    // Matches the value on top of the stack against a pattern. A match pops
+    // it and binds the pattern in a new scope; a mismatch jumps.
+    Try {
+        pattern: usize,
+        otherwise: usize,
+    },
+    // Pops a value no arm matched, and fails with it.
+    Unmatched,
+    Return,
+}
+
+// A sequence of instructions along with the pools they refer to.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub constants: Vec<Primitive>,
+    pub names: Vec<String>,
+    pub patterns: Vec<Pattern>,
+    pub declarations: Vec<Rc<Declaration>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Compiled {
+    pub definition: usize,
+    pub name: String,
+    pub parameters: Vec<Variable>,
+    pub chunk: Rc<Chunk>,
+}
+
+// The code of a resolved tree, along with every function it declares.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    pub main: Rc<Chunk>,
+    pub functions: Vec<Rc<Compiled>>,
+}
+
+pub fn compile(resolved: &Resolved) -> Program {
+    Program {
+        main: Rc::new(Compiler::chunk(&resolved.expression)),
+        functions: resolved
+            .functions
+            .iter()
+            .filter_map(|function| compile_function(function).map(Rc::new))
+            .collect(),
+    }
+}
+
+// Functions the resolver did not number cannot be called, and are left out.
+pub fn compile_function(function: &Function) -> Option<Compiled> {
+    Some(Compiled {
+        definition: function.definition?,
+        name: function.name.clone(),
+        parameters: function.parameters.clone(),
+        chunk: Rc::new(Compiler::chunk(&function.body)),
+    })
+}
+
+#[derive(Default)]
+struct Compiler {
+    chunk: Chunk,
+}
+
+impl Compiler {
+    fn chunk(expression: &Expression) -> Chunk {
+        let mut compiler = Compiler::default();
+        compiler.expression(expression);
+        compiler.emit(Instruction::Return);
+        compiler.chunk
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        stack::grow(|| self.node(expression))
+    }
+
+    fn node(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Primitive(primitive) => {
+                self.chunk.constants.push(primitive.clone());
+                self.emit(Instruction::Constant(self.chunk.constants.len() - 1));
+            }
+            Expression::None {} => {
+                self.emit(Instruction::Nil);
+            }
+            Expression::Expression(inner) => self.expression(inner),
+            Expression::Sum { left, right } => self.binary(left, right, Instruction::Sum),
+            Expression::Product { left, right } => self.binary(left, right, Instruction::Product),
+            Expression::Divide { left, right } => self.binary(left, right, Instruction::Divide),
+            Expression::Subtract { left, right } => self.binary(left, right, Instruction::Subtract),
+            Expression::IntegerDivide { left, right } => {
+                self.binary(left, right, Instruction::IntegerDivide)
+            }
+            Expression::Modulo { left, right } => self.binary(left, right, Instruction::Modulo),
+            Expression::Power { left, right } => self.binary(left, right, Instruction::Power),
+            Expression::Compare {
+                left,
+                operand,
+                right,
+            } => self.binary(left, right, Instruction::Compare(operand.clone())),
+            Expression::Bitwise {
+                left,
+                operand,
+                right,
+            } => self.binary(left, right, Instruction::Bitwise(operand.clone())),
+            Expression::Unary { operand, right } => {
+                self.expression(right);
+                self.emit(Instruction::Unary(operand.clone()));
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition);
+                let otherwise = self.emit(Instruction::JumpUnless(0));
+                self.scoped(then_branch);
+                let end = self.emit(Instruction::Jump(0));
+                self.patch(otherwise);
+                self.scoped(else_branch);
+                self.patch(end);
+            }
+            Expression::Let { variables, scope } => {
+                for (pattern, value) in variables {
+                    self.expression(value);
+                    self.chunk.patterns.push(pattern.clone());
+                    self.emit(Instruction::Bind(self.chunk.patterns.len() - 1));
+                }
+                self.expression(scope);
+            }
+            Expression::Use { variable } => {
+                let name = self.name(&variable.name);
+                match variable.slot {
+                    Some(slot) if variable.global => {
+                        self.emit(Instruction::LoadGlobal { slot, name })
+                    }
+                    Some(slot) => self.emit(Instruction::Load { slot, name }),
+                    None => self.emit(Instruction::Global(name)),
+                };
+            }
+            Expression::Assign { variable, value } => {
+                self.expression(value);
+                let name = self.name(&variable.name);
+                match variable.slot {
+                    Some(slot) => self.emit(Instruction::Assign { slot, name }),
+                    None => self.emit(Instruction::Undefined(name)),
+                };
+            }
+            Expression::For {
+                variable,
+                from,
+                to,
+                body,
+            } => {
+                self.expression(from);
+                self.expression(to);
+                let slot = match variable.slot {
+                    Some(slot) => slot,
+                    None => {
+                        let name = self.name(&variable.name);
+                        self.emit(Instruction::Undefined(name));
+                        return;
+                    }
+                };
+                let range = self.emit(Instruction::Range(0));
+                let start = self.emit(Instruction::EnterScope);
+                self.emit(Instruction::Iterate(slot));
+                self.expression(body);
+                self.emit(Instruction::Pop);
+                self.emit(Instruction::ExitScope);
+                self.emit(Instruction::Next(start));
+                self.patch(range);
+                self.emit(Instruction::Unit);
+            }
+            Expression::Print { expression } => {
+                self.expression(expression);
+                self.emit(Instruction::Print);
+            }
+            Expression::Chain { left, right } => {
+                self.expression(left);
+                self.emit(Instruction::Pop);
+                self.expression(right);
+            }
+            Expression::Line { statement, .. } => self.expression(statement),
+            Expression::Block { body } => self.scoped(body),
+            Expression::Function { scope, .. } => self.expression(scope),
+            Expression::Call {
+                function,
+                definition,
+                arguments,
+            } => {
+                for argument in arguments {
+                    self.expression(argument);
+                }
+                let name = self.name(function);
+                let arguments = arguments.len();
+                match definition {
+                    Some(definition) => self.emit(Instruction::Call {
+                        definition: *definition,
+                        name,
+                        arguments,
+                    }),
+                    None => self.emit(Instruction::Builtin { name, arguments }),
+                };
+            }
+            Expression::Tuple { elements } => {
+                for element in elements {
+                    self.expression(element);
+                }
+                self.emit(Instruction::Tuple(elements.len()));
+            }
+            Expression::Index { tuple, index } => {
+                self.expression(tuple);
+                self.emit(Instruction::Index(*index));
+            }
+            Expression::Declare { scope, .. } => self.expression(scope),
+            Expression::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => {
+                for argument in arguments {
+                    self.expression(argument);
+                }
+                self.chunk.declarations.push(declaration.clone());
+                self.emit(Instruction::Construct {
+                    declaration: self.chunk.declarations.len() - 1,
+                    constructor: *constructor,
+                    arguments: arguments.len(),
+                });
+            }
+            Expression::Field { value, field } => {
+                self.expression(value);
+                let field = self.name(field);
+                self.emit(Instruction::Field(field));
+            }
+            Expression::Match { value, arms } => {
+                self.expression(value);
+                let mut ends = Vec::new();
+                for (pattern, body) in arms {
+                    self.chunk.patterns.push(pattern.clone());
+                    let pattern = self.chunk.patterns.len() - 1;
+                    let attempt = self.emit(Instruction::Try {
+                        pattern,
+                        otherwise: 0,
+                    });
+                    self.expression(body);
+                    self.emit(Instruction::ExitScope);
+                    ends.push(self.emit(Instruction::Jump(0)));
+                    self.patch(attempt);
+                }
+                self.emit(Instruction::Unmatched);
+                for end in ends {
+                    self.patch(end);
+                }
+            }
+        }
+    }
+
+    fn binary(&mut self, left: &Expression, right: &Expression, operator: Instruction) {
+        self.expression(left);
+        self.expression(right);
+        self.emit(operator);
+    }
+
+    fn scoped(&mut self, body: &Expression) {
+        self.emit(Instruction::EnterScope);
+        self.expression(body);
+        self.emit(Instruction::ExitScope);
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        match self.chunk.names.iter().position(|known| known == name) {
+            Some(index) => index,
+            None => {
+                self.chunk.names.push(name.to_string());
+                self.chunk.names.len() - 1
+            }
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.code.len() - 1
+    }
+
+    // Points the jump at `at` to the next instruction to be emitted.
+    fn patch(&mut self, at: usize) {
+        let target = self.chunk.code.len();
+        match &mut self.chunk.code[at] {
+            Instruction::Jump(to)
+            | Instruction::JumpUnless(to)
+            | Instruction::Range(to)
+            | Instruction::Try { otherwise: to, .. } => *to = target,
+            instruction => unreachable!("{:?} does not jump", instruction),
+        }
+    }
+}
@@ -0,0 +1,685 @@
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+
+use super::{
+    compilation::{Chunk, Compiled, Instruction, Program},
+    expression::{Constructor, Declaration, Operand, Pattern, Variable},
+    options::Overflow,
+    primitives::Primitive,
+    types::Type,
+    values::Error,
+};
+
+// What a bytecode file starts with, before anything else.
+pub const MAGIC: &[u8; 4] = b"TAUB";
+
+// Bumped whenever the encoding or the meaning of an instruction changes; a
+// file of any other version is rejected rather than misread.
+pub const VERSION: u16 = 4;
+
+// A bytecode file is the magic bytes and the version, followed by the
+// overflow mode its constants were folded with, which it has to run with
+// too, then the main chunk and every function. Numbers are unsigned LEB128,
+// except for integer constants, which are zigzag encoded first, and floats,
+// which are their eight little-endian bytes. Lists are their length followed
+// by their items; strings are their UTF-8 bytes as a list.
+//
+// Each chunk holds its constant pool ahead of its code, and a chunk's
+// indices into its pools and its jumps are checked while decoding, so a
+// decoded program never reads outside of them.
+pub fn encode(program: &Program, overflow: Overflow) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.bytes.extend_from_slice(&VERSION.to_le_bytes());
+    writer.bytes.push(match overflow {
+        Overflow::Promote => 0,
+        Overflow::Checked => 1,
+        Overflow::Wrapping => 2,
+        Overflow::Saturating => 3,
+    });
+    writer.chunk(&program.main);
+    writer.number(program.functions.len());
+    for function in &program.functions {
+        writer.number(function.definition);
+        writer.string(&function.name);
+        writer.number(function.parameters.len());
+        for parameter in &function.parameters {
+            writer.variable(parameter);
+        }
+        writer.chunk(&function.chunk);
+    }
+    writer.bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<(Program, Overflow), Error> {
+    if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::InvalidBytecode(
+            "not a taupe bytecode file".to_string(),
+        ));
+    }
+    let version = u16::from_le_bytes([bytes[MAGIC.len()], bytes[MAGIC.len() + 1]]);
+    if version != VERSION {
+        return Err(Error::IncompatibleBytecode(version, VERSION));
+    }
+
+    let mut reader = Reader {
+        bytes,
+        at: MAGIC.len() + 2,
+    };
+    let overflow = match reader.byte()? {
+        0 => Overflow::Promote,
+        1 => Overflow::Checked,
+        2 => Overflow::Wrapping,
+        3 => Overflow::Saturating,
+        tag => return Err(Reader::unknown("overflow mode", tag)),
+    };
+    let main = Rc::new(reader.chunk()?);
+    let mut functions = Vec::new();
+    for _ in 0..reader.number()? {
+        let definition = reader.number()?;
+        let name = reader.string()?;
+        let mut parameters = Vec::new();
+        for _ in 0..reader.number()? {
+            parameters.push(reader.variable()?);
+        }
+        functions.push(Rc::new(Compiled {
+            definition,
+            name,
+            parameters,
+            chunk: Rc::new(reader.chunk()?),
+        }));
+    }
+    if reader.at != bytes.len() {
+        return Err(Error::InvalidBytecode(
+            "trailing bytes after the program".to_string(),
+        ));
+    }
+    Ok((Program { main, functions }, overflow))
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn chunk(&mut self, chunk: &Chunk) {
+        self.number(chunk.constants.len());
+        for constant in &chunk.constants {
+            self.primitive(constant);
+        }
+        self.number(chunk.names.len());
+        for name in &chunk.names {
+            self.string(name);
+        }
+        self.number(chunk.patterns.len());
+        for pattern in &chunk.patterns {
+            self.pattern(pattern);
+        }
+        self.number(chunk.declarations.len());
+        for declaration in &chunk.declarations {
+            self.declaration(declaration);
+        }
+        self.number(chunk.code.len());
+        for instruction in &chunk.code {
+            self.instruction(instruction);
+        }
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::Constant(constant) => self.operands(0, &[*constant]),
+            Instruction::Unit => self.operands(1, &[]),
+            Instruction::Nil => self.operands(2, &[]),
+            Instruction::Global(name) => self.operands(3, &[*name]),
+            Instruction::Load { slot, name } => self.operands(4, &[*slot, *name]),
+            Instruction::Assign { slot, name } => self.operands(5, &[*slot, *name]),
+            Instruction::Bind(pattern) => self.operands(6, &[*pattern]),
+            Instruction::Undefined(name) => self.operands(7, &[*name]),
+            Instruction::Pop => self.operands(8, &[]),
+            Instruction::Sum => self.operands(9, &[]),
+            Instruction::Product => self.operands(10, &[]),
+            Instruction::Divide => self.operands(11, &[]),
+            Instruction::Subtract => self.operands(12, &[]),
+            Instruction::IntegerDivide => self.operands(13, &[]),
+            Instruction::Modulo => self.operands(14, &[]),
+            Instruction::Power => self.operands(15, &[]),
+            Instruction::Compare(operand) => {
+                self.operands(16, &[]);
+                self.operand(operand);
+            }
+            Instruction::Bitwise(operand) => {
+                self.operands(17, &[]);
+                self.operand(operand);
+            }
+            Instruction::Unary(operand) => {
+                self.operands(18, &[]);
+                self.operand(operand);
+            }
+            Instruction::Print => self.operands(19, &[]),
+            Instruction::Tuple(elements) => self.operands(20, &[*elements]),
+            Instruction::Index(index) => self.operands(21, &[*index]),
+            Instruction::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => self.operands(22, &[*declaration, *constructor, *arguments]),
+            Instruction::Field(field) => self.operands(23, &[*field]),
+            Instruction::Call {
+                definition,
+                name,
+                arguments,
+            } => self.operands(24, &[*definition, *name, *arguments]),
+            Instruction::Builtin { name, arguments } => self.operands(25, &[*name, *arguments]),
+            Instruction::EnterScope => self.operands(26, &[]),
+            Instruction::ExitScope => self.operands(27, &[]),
+            Instruction::Jump(to) => self.operands(28, &[*to]),
+            Instruction::JumpUnless(to) => self.operands(29, &[*to]),
+            Instruction::Range(exit) => self.operands(30, &[*exit]),
+            Instruction::Iterate(slot) => self.operands(31, &[*slot]),
+            Instruction::Next(start) => self.operands(32, &[*start]),
+            Instruction::Try { pattern, otherwise } => self.operands(33, &[*pattern, *otherwise]),
+            Instruction::Unmatched => self.operands(34, &[]),
+            Instruction::Return => self.operands(35, &[]),
+            Instruction::LoadGlobal { slot, name } => self.operands(36, &[*slot, *name]),
+        }
+    }
+
+    fn operands(&mut self, tag: u8, operands: &[usize]) {
+        self.bytes.push(tag);
+        for operand in operands {
+            self.number(*operand);
+        }
+    }
+
+    fn operand(&mut self, operand: &Operand) {
+        self.bytes.push(match operand {
+            Operand::Equals => 0,
+            Operand::LessThan => 1,
+            Operand::GreaterThan => 2,
+            Operand::Not => 3,
+            Operand::Negate => 4,
+            Operand::BitAnd => 5,
+            Operand::BitOr => 6,
+            Operand::BitXor => 7,
+            Operand::Complement => 8,
+            Operand::ShiftLeft => 9,
+            Operand::ShiftRight => 10,
+        });
+    }
+
+    fn primitive(&mut self, primitive: &Primitive) {
+        match primitive {
+            Primitive::Integer(value) => {
+                self.bytes.push(0);
+                self.number(((value << 1) ^ (value >> 63)) as u64 as usize);
+            }
+            Primitive::BigInteger(value) => {
+                self.bytes.push(1);
+                let bytes = value.to_signed_bytes_le();
+                self.number(bytes.len());
+                self.bytes.extend_from_slice(&bytes);
+            }
+            Primitive::Float(value) => {
+                self.bytes.push(2);
+                self.bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Primitive::String(value) => {
+                self.bytes.push(3);
+                self.string(value);
+            }
+            Primitive::Boolean(value) => {
+                self.bytes.push(4);
+                self.bytes.push(*value as u8);
+            }
+            Primitive::ToReplace => self.bytes.push(5),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Variable(variable) => {
+                self.bytes.push(0);
+                self.variable(variable);
+            }
+            Pattern::Tuple(elements) => {
+                self.bytes.push(1);
+                self.patterns(elements);
+            }
+            Pattern::List { elements, rest } => {
+                self.bytes.push(2);
+                self.patterns(elements);
+                match rest {
+                    Some(rest) => {
+                        self.bytes.push(1);
+                        self.variable(rest);
+                    }
+                    None => self.bytes.push(0),
+                }
+            }
+            Pattern::Constructor { name, elements } => {
+                self.bytes.push(3);
+                self.string(name);
+                self.patterns(elements);
+            }
+            Pattern::Literal(literal) => {
+                self.bytes.push(4);
+                self.primitive(literal);
+            }
+        }
+    }
+
+    fn patterns(&mut self, patterns: &[Pattern]) {
+        self.number(patterns.len());
+        for pattern in patterns {
+            self.pattern(pattern);
+        }
+    }
+
+    fn variable(&mut self, variable: &Variable) {
+        self.string(&variable.name);
+        match variable.slot {
+            Some(slot) => {
+                self.bytes.push(1);
+                self.number(slot);
+            }
+            None => self.bytes.push(0),
+        }
+        self.bytes.push(variable.mutable as u8);
+        self.bytes.push(variable.global as u8);
+        match &variable.annotation {
+            Some(annotation) => {
+                self.bytes.push(1);
+                self.annotation(annotation);
+            }
+            None => self.bytes.push(0),
+        }
+    }
+
+    fn declaration(&mut self, declaration: &Declaration) {
+        self.string(&declaration.name);
+        self.number(declaration.parameters.len());
+        for parameter in &declaration.parameters {
+            self.string(parameter);
+        }
+        self.bytes.push(declaration.enumeration as u8);
+        self.number(declaration.constructors.len());
+        for constructor in &declaration.constructors {
+            self.string(&constructor.name);
+            self.number(constructor.fields.len());
+            for (field, annotation) in &constructor.fields {
+                self.string(field);
+                self.annotation(annotation);
+            }
+        }
+    }
+
+    fn annotation(&mut self, annotation: &Type) {
+        match annotation {
+            Type::Int => self.bytes.push(0),
+            Type::Float => self.bytes.push(1),
+            Type::Str => self.bytes.push(2),
+            Type::Bool => self.bytes.push(3),
+            Type::List(element) => {
+                self.bytes.push(4);
+                self.annotation(element);
+            }
+            Type::Tuple(elements) => {
+                self.bytes.push(5);
+                self.annotations(elements);
+            }
+            Type::Unit => self.bytes.push(6),
+            Type::Named(name, arguments) => {
+                self.bytes.push(7);
+                self.string(name);
+                self.annotations(arguments);
+            }
+            Type::Variable(variable) => {
+                self.bytes.push(8);
+                self.number(*variable);
+            }
+            Type::Unknown => self.bytes.push(9),
+        }
+    }
+
+    fn annotations(&mut self, annotations: &[Type]) {
+        self.number(annotations.len());
+        for annotation in annotations {
+            self.annotation(annotation);
+        }
+    }
+
+    fn string(&mut self, string: &str) {
+        self.number(string.len());
+        self.bytes.extend_from_slice(string.as_bytes());
+    }
+
+    fn number(&mut self, mut number: usize) {
+        loop {
+            let byte = (number & 0x7f) as u8;
+            number >>= 7;
+            if number == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn chunk(&mut self) -> Result<Chunk, Error> {
+        let mut chunk = Chunk::default();
+        for _ in 0..self.number()? {
+            chunk.constants.push(self.primitive()?);
+        }
+        for _ in 0..self.number()? {
+            chunk.names.push(self.string()?);
+        }
+        for _ in 0..self.number()? {
+            chunk.patterns.push(self.pattern()?);
+        }
+        for _ in 0..self.number()? {
+            chunk.declarations.push(Rc::new(self.declaration()?));
+        }
+        for _ in 0..self.number()? {
+            chunk.code.push(self.instruction()?);
+        }
+        Reader::check(&chunk)?;
+        Ok(chunk)
+    }
+
+    // Makes sure every index points inside the chunk, and that the code ends
+    // with a Return so running off its end is impossible. Whether the stack
+    // holds what each instruction takes is only known as it runs, where the
+    // stack machine fails with InvalidBytecode when it does not.
+    fn check(chunk: &Chunk) -> Result<(), Error> {
+        let within = |index: usize, length: usize| index < length;
+        let code = chunk.code.len();
+        let names = chunk.names.len();
+        let fits = chunk.code.iter().all(|instruction| match instruction {
+            Instruction::Constant(constant) => within(*constant, chunk.constants.len()),
+            Instruction::Global(name)
+            | Instruction::Load { name, .. }
+            | Instruction::LoadGlobal { name, .. }
+            | Instruction::Assign { name, .. }
+            | Instruction::Undefined(name)
+            | Instruction::Field(name)
+            | Instruction::Call { name, .. }
+            | Instruction::Builtin { name, .. } => within(*name, names),
+            Instruction::Bind(pattern) => within(*pattern, chunk.patterns.len()),
+            Instruction::Construct {
+                declaration,
+                constructor,
+                ..
+            } => chunk
+                .declarations
+                .get(*declaration)
+                .is_some_and(|declaration| within(*constructor, declaration.constructors.len())),
+            Instruction::Jump(to)
+            | Instruction::JumpUnless(to)
+            | Instruction::Range(to)
+            | Instruction::Next(to) => within(*to, code),
+            Instruction::Try { pattern, otherwise } => {
+                within(*pattern, chunk.patterns.len()) && within(*otherwise, code)
+            }
+            _ => true,
+        });
+        if !fits || chunk.code.last() != Some(&Instruction::Return) {
+            return Err(Error::InvalidBytecode(
+                "an instruction points outside of its chunk".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, Error> {
+        let instruction = match self.byte()? {
+            0 => Instruction::Constant(self.number()?),
+            1 => Instruction::Unit,
+            2 => Instruction::Nil,
+            3 => Instruction::Global(self.number()?),
+            4 => Instruction::Load {
+                slot: self.number()?,
+                name: self.number()?,
+            },
+            5 => Instruction::Assign {
+                slot: self.number()?,
+                name: self.number()?,
+            },
+            6 => Instruction::Bind(self.number()?),
+            7 => Instruction::Undefined(self.number()?),
+            8 => Instruction::Pop,
+            9 => Instruction::Sum,
+            10 => Instruction::Product,
+            11 => Instruction::Divide,
+            12 => Instruction::Subtract,
+            13 => Instruction::IntegerDivide,
+            14 => Instruction::Modulo,
+            15 => Instruction::Power,
+            16 => Instruction::Compare(self.operand()?),
+            17 => Instruction::Bitwise(self.operand()?),
+            18 => Instruction::Unary(self.operand()?),
+            19 => Instruction::Print,
+            20 => Instruction::Tuple(self.number()?),
+            21 => Instruction::Index(self.number()?),
+            22 => Instruction::Construct {
+                declaration: self.number()?,
+                constructor: self.number()?,
+                arguments: self.number()?,
+            },
+            23 => Instruction::Field(self.number()?),
+            24 => Instruction::Call {
+                definition: self.number()?,
+                name: self.number()?,
+                arguments: self.number()?,
+            },
+            25 => Instruction::Builtin {
+                name: self.number()?,
+                arguments: self.number()?,
+            },
+            26 => Instruction::EnterScope,
+            27 => Instruction::ExitScope,
+            28 => Instruction::Jump(self.number()?),
+            29 => Instruction::JumpUnless(self.number()?),
+            30 => Instruction::Range(self.number()?),
+            31 => Instruction::Iterate(self.number()?),
+            32 => Instruction::Next(self.number()?),
+            33 => Instruction::Try {
+                pattern: self.number()?,
+                otherwise: self.number()?,
+            },
+            34 => Instruction::Unmatched,
+            35 => Instruction::Return,
+            36 => Instruction::LoadGlobal {
+                slot: self.number()?,
+                name: self.number()?,
+            },
+            tag => return Err(Reader::unknown("instruction", tag)),
+        };
+        Ok(instruction)
+    }
+
+    fn operand(&mut self) -> Result<Operand, Error> {
+        let operand = match self.byte()? {
+            0 => Operand::Equals,
+            1 => Operand::LessThan,
+            2 => Operand::GreaterThan,
+            3 => Operand::Not,
+            4 => Operand::Negate,
+            5 => Operand::BitAnd,
+            6 => Operand::BitOr,
+            7 => Operand::BitXor,
+            8 => Operand::Complement,
+            9 => Operand::ShiftLeft,
+            10 => Operand::ShiftRight,
+            tag => return Err(Reader::unknown("operand", tag)),
+        };
+        Ok(operand)
+    }
+
+    fn primitive(&mut self) -> Result<Primitive, Error> {
+        let primitive = match self.byte()? {
+            0 => {
+                let zigzag = self.number()? as u64;
+                Primitive::Integer((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64))
+            }
+            1 => {
+                let length = self.number()?;
+                Primitive::BigInteger(BigInt::from_signed_bytes_le(self.take(length)?))
+            }
+            2 => {
+                let bytes = self.take(8)?;
+                Primitive::Float(f64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            3 => Primitive::String(self.string()?),
+            4 => Primitive::Boolean(self.flag()?),
+            5 => Primitive::ToReplace,
+            tag => return Err(Reader::unknown("constant", tag)),
+        };
+        Ok(primitive)
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Error> {
+        let pattern = match self.byte()? {
+            0 => Pattern::Variable(self.variable()?),
+            1 => Pattern::Tuple(self.patterns()?),
+            2 => Pattern::List {
+                elements: self.patterns()?,
+                rest: match self.flag()? {
+                    true => Some(self.variable()?),
+                    false => None,
+                },
+            },
+            3 => Pattern::Constructor {
+                name: self.string()?,
+                elements: self.patterns()?,
+            },
+            4 => Pattern::Literal(self.primitive()?),
+            tag => return Err(Reader::unknown("pattern", tag)),
+        };
+        Ok(pattern)
+    }
+
+    fn patterns(&mut self) -> Result<Vec<Pattern>, Error> {
+        (0..self.number()?).map(|_| self.pattern()).collect()
+    }
+
+    fn variable(&mut self) -> Result<Variable, Error> {
+        Ok(Variable {
+            name: self.string()?,
+            slot: match self.flag()? {
+                true => Some(self.number()?),
+                false => None,
+            },
+            mutable: self.flag()?,
+            global: self.flag()?,
+            annotation: match self.flag()? {
+                true => Some(self.annotation()?),
+                false => None,
+            },
+        })
+    }
+
+    fn declaration(&mut self) -> Result<Declaration, Error> {
+        let name = self.string()?;
+        let parameters = (0..self.number()?)
+            .map(|_| self.string())
+            .collect::<Result<_, _>>()?;
+        let enumeration = self.flag()?;
+        let mut constructors = Vec::new();
+        for _ in 0..self.number()? {
+            let name = self.string()?;
+            let mut fields = Vec::new();
+            for _ in 0..self.number()? {
+                fields.push((self.string()?, self.annotation()?));
+            }
+            constructors.push(Constructor { name, fields });
+        }
+        Ok(Declaration {
+            name,
+            parameters,
+            enumeration,
+            constructors,
+        })
+    }
+
+    fn annotation(&mut self) -> Result<Type, Error> {
+        let annotation = match self.byte()? {
+            0 => Type::Int,
+            1 => Type::Float,
+            2 => Type::Str,
+            3 => Type::Bool,
+            4 => Type::List(Box::new(self.annotation()?)),
+            5 => Type::Tuple(self.annotations()?),
+            6 => Type::Unit,
+            7 => Type::Named(self.string()?, self.annotations()?),
+            8 => Type::Variable(self.number()?),
+            9 => Type::Unknown,
+            tag => return Err(Reader::unknown("type", tag)),
+        };
+        Ok(annotation)
+    }
+
+    fn annotations(&mut self) -> Result<Vec<Type>, Error> {
+        (0..self.number()?).map(|_| self.annotation()).collect()
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self.number()?;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| Error::InvalidBytecode("a string is not valid UTF-8".to_string()))
+    }
+
+    fn number(&mut self) -> Result<usize, Error> {
+        let mut number: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= usize::BITS
+                || ((byte & 0x7f) as usize) << shift >> shift != (byte & 0x7f) as usize
+            {
+                return Err(Error::InvalidBytecode("a number is too large".to_string()));
+            }
+            number |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+            shift += 7;
+        }
+    }
+
+    fn flag(&mut self) -> Result<bool, Error> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(Reader::unknown("flag", tag)),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8], Error> {
+        match self.at.checked_add(length) {
+            Some(end) if end <= self.bytes.len() => {
+                let bytes = &self.bytes[self.at..end];
+                self.at = end;
+                Ok(bytes)
+            }
+            _ => Err(Error::InvalidBytecode("the file ends early".to_string())),
+        }
+    }
+
+    fn unknown(what: &str, tag: u8) -> Error {
+        Error::InvalidBytecode(format!("unknown {} {}", what, tag))
+    }
+}
@@ -0,0 +1,257 @@
+use std::rc::Rc;
+
+use super::{expression::Function, limits::Usage, values::Value};
+
+// Evaluated bindings, stored in the slots the resolver assigned to them.
+// Slots are handed out in declaration order and released when their scope
+// ends, so the bindings form a stack: a scope owns every slot above the
+// height the stack had when the scope began.
+//
+// - `declare` fills a slot. A declaration always targets either the next
+//   free slot or, when it redeclares a name of the same scope, the slot that
+//   name already holds.
+// - `assign` updates a slot that is already bound, wherever its scope is, so
+//   mutations inside a block are visible once the block ends.
+//
+// A function call runs in a frame of its own: slots are numbered from the
+// frame's base, which hides the caller's slots until the call returns.
+// Globals are the exception: they sit at the bottom of the stack, and
+// `global` reads them from any frame, but only once their declaration ran.
+// Until then their slot may hold another binding, or none.
+// Functions themselves live apart, by definition number, and so does what
+// the current run has used of its limits.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Environment {
+    slots: Vec<Value>,
+    base: usize,
+    // The number of slots at the bottom of the stack that hold globals.
+    globals: usize,
+    // The number of frames entered and not yet left.
+    depth: usize,
+    functions: Vec<Option<Rc<Function>>>,
+    usage: Usage,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment {
+            slots: Vec::new(),
+            base: 0,
+            globals: 0,
+            depth: 0,
+            functions: Vec::new(),
+            usage: Usage::default(),
+        }
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&Value> {
+        self.slots.get(self.base + slot)
+    }
+
+    // A global, whichever frame is current.
+    pub fn global(&self, slot: usize) -> Option<&Value> {
+        match slot < self.globals {
+            true => self.slots.get(slot),
+            false => None,
+        }
+    }
+
+    pub fn declare(&mut self, slot: usize, value: Value) {
+        let slot = self.base + slot;
+        if slot < self.slots.len() {
+            self.slots[slot] = value;
+        } else {
+            debug_assert_eq!(slot, self.slots.len());
+            self.slots.push(value);
+        }
+    }
+
+    // Declares a global, which must be done outside of any frame.
+    pub fn declare_global(&mut self, slot: usize, value: Value) {
+        debug_assert_eq!(self.base, 0);
+        self.declare(slot, value);
+        self.globals = self.globals.max(slot + 1);
+    }
+
+    // Whether `declare` may fill the slot: one already bound, or the next
+    // free one.
+    pub fn declarable(&self, slot: usize) -> bool {
+        self.base + slot <= self.slots.len()
+    }
+
+    // Returns false when the slot is not bound.
+    pub fn assign(&mut self, slot: usize, value: Value) -> bool {
+        match self.slots.get_mut(self.base + slot) {
+            Some(binding) => {
+                *binding = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The number of bound slots.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    // Defines a resolved function; functions without a definition number are
+    // ignored.
+    pub fn define(&mut self, function: Rc<Function>) {
+        if let Some(definition) = function.definition {
+            if self.functions.len() <= definition {
+                self.functions.resize(definition + 1, None);
+            }
+            self.functions[definition] = Some(function);
+        }
+    }
+
+    pub fn function(&self, definition: usize) -> Option<Rc<Function>> {
+        self.functions.get(definition).cloned().flatten()
+    }
+
+    // Runs `f` in a new frame, released afterwards even if `f` fails.
+    pub fn call<T>(&mut self, f: impl FnOnce(&mut Environment) -> T) -> T {
+        let base = self.enter();
+        let result = f(self);
+        self.leave(base);
+        result
+    }
+
+    // Runs `f` inside a new innermost scope, whose slots are released
+    // afterwards even if `f` fails.
+    pub fn scoped<T>(&mut self, f: impl FnOnce(&mut Environment) -> T) -> T {
+        let height = self.len();
+        let result = f(self);
+        self.truncate(height);
+        result
+    }
+
+    // Starts a new frame, returning the base of the caller's frame for
+    // `leave` to restore.
+    pub fn enter(&mut self) -> usize {
+        self.depth += 1;
+        std::mem::replace(&mut self.base, self.slots.len())
+    }
+
+    // Ends the current frame, releasing its slots.
+    pub fn leave(&mut self, base: usize) {
+        self.slots.truncate(self.base);
+        self.base = base;
+        self.depth -= 1;
+    }
+
+    // The number of calls in progress.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn usage(&mut self) -> &mut Usage {
+        &mut self.usage
+    }
+
+    // Starts a new run, with none of its limits used yet.
+    pub fn restart(&mut self) {
+        self.usage = Usage::default();
+    }
+
+    // Releases every slot above `height`, as a scope that began at that
+    // height ends.
+    pub fn truncate(&mut self, height: usize) {
+        self.slots.truncate(height);
+        self.globals = self.globals.min(height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_shadows_until_the_scope_ends() {
+        let mut environment = Environment::new();
+        environment.declare(0, Value::Integer(1));
+
+        environment.scoped(|environment| {
+            environment.declare(1, Value::Integer(2));
+            assert_eq!(environment.get(1), Some(&Value::Integer(2)));
+        });
+
+        assert_eq!(environment.get(0), Some(&Value::Integer(1)));
+    }
+
+    #[test]
+    fn it_makes_assignments_visible_to_enclosing_scopes() {
+        let mut environment = Environment::new();
+        environment.declare(0, Value::Integer(1));
+
+        environment.scoped(|environment| {
+            assert!(environment.assign(0, Value::Integer(2)));
+        });
+
+        assert_eq!(environment.get(0), Some(&Value::Integer(2)));
+    }
+
+    #[test]
+    fn it_forgets_declarations_of_a_finished_scope() {
+        let mut environment = Environment::new();
+
+        environment.scoped(|environment| environment.declare(0, Value::Integer(1)));
+
+        assert_eq!(environment.get(0), None);
+        assert!(!environment.assign(0, Value::Integer(2)));
+    }
+
+    #[test]
+    fn it_numbers_the_slots_of_a_call_from_zero() {
+        let mut environment = Environment::new();
+        environment.declare(0, Value::Integer(1));
+
+        environment.call(|environment| {
+            assert_eq!(environment.get(0), None);
+            environment.declare(0, Value::Integer(2));
+            assert_eq!(environment.get(0), Some(&Value::Integer(2)));
+        });
+
+        assert_eq!(environment.get(0), Some(&Value::Integer(1)));
+        assert_eq!(environment.len(), 1);
+    }
+
+    #[test]
+    fn it_counts_the_frames_entered() {
+        let mut environment = Environment::new();
+
+        environment.call(|environment| {
+            environment.call(|environment| assert_eq!(environment.depth(), 2));
+            assert_eq!(environment.depth(), 1);
+        });
+
+        assert_eq!(environment.depth(), 0);
+    }
+
+    #[test]
+    fn it_reads_globals_from_any_frame_once_declared() {
+        let mut environment = Environment::new();
+        environment.declare_global(0, Value::Integer(1));
+        environment.declare(1, Value::Integer(2));
+
+        environment.call(|environment| {
+            assert_eq!(environment.global(0), Some(&Value::Integer(1)));
+            assert_eq!(environment.global(1), None);
+        });
+    }
+
+    #[test]
+    fn it_reuses_the_slot_of_a_redeclaration() {
+        let mut environment = Environment::new();
+        environment.declare(0, Value::Integer(1));
+        environment.declare(0, Value::String("one".to_string()));
+
+        assert_eq!(environment.len(), 1);
+        assert_eq!(environment.get(0), Some(&Value::String("one".to_string())));
+    }
+}
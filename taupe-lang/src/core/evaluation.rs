@@ -1,220 +1,794 @@
-use std::{collections::HashMap, rc::Rc};
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::stdlib;
 
 use super::{
-    expression::{Expression, ExpressionRef, Operand},
+    environment::Environment,
+    expression::{Declaration, Expression, ExpressionRef, Function, Operand, Pattern, Variable},
+    limits,
+    options::{Options, Overflow},
     primitives::Primitive,
+    stack,
     values::{Error, Value},
 };
+#[cfg(feature = "jit")]
+use super::{jit, options::Backend};
 
+// Evaluates an expression to its value. Calls in tail position, such as the
+// last expression of a function body, replace the frame of the call they
+// are in rather than nesting another, so a function may call itself in tail
+// position any number of times. Any other call counts towards the maximum
+// depth of the options.
 pub fn evaluate(
     expr: &Expression,
-    state: &Option<HashMap<String, ExpressionRef>>,
+    environment: &mut Environment,
+    options: &Options,
 ) -> Result<Value, Error> {
-    match expr {
-        Expression::None {} => Ok(Value::None),
-        Expression::Sum { left, right } => {
-            let left_value = evaluate(&left, state)?;
-            let right_value = evaluate(&right, state)?;
-            match (left_value.clone(), right_value.clone()) {
-                (Value::Integer(left), Value::Integer(right)) => Ok(Value::Integer(left + right)),
-                _ => Err(Error::InvalidValues("Sum".to_string(), vec![left_value, right_value])),
-            }
-        }
-        Expression::Product { left, right } => {
-            let left_value = evaluate(&left, state)?;
-            let right_value = evaluate(&right, state)?;
-            match (left_value.clone(), right_value.clone()) {
-                (Value::Integer(left), Value::Integer(right)) => Ok(Value::Integer(left * right)),
-                _ => Err(Error::InvalidValues("Product".to_string(), vec![left_value, right_value])),
-            }
-        }
-        Expression::Divide { left, right } => {
-            let left_value = evaluate(&left, state)?;
-            let right_value = evaluate(&right, state)?;
-            match (left_value.clone(), right_value.clone()) {
-                (Value::Integer(left), Value::Integer(right)) => {
-                    if right == 0 {
-                        Err(Error::InvalidValues("Division with 0".to_string(), vec![right_value]))
-                    } else {
-                        Ok(Value::Float(left as f64 / right as f64))
-                    }
-                }
-                _ => Err(Error::InvalidValues("Divide".to_string(), vec![left_value, right_value])),
+    stack::grow(|| {
+        let mut tail = Tail {
+            height: None,
+            caller: None,
+        };
+        let result = trampoline(expr, environment, options, &mut tail);
+        tail.release(environment);
+        result
+    })
+}
+
+// What is left of an expression once `run` is done with it: either its
+// value, or a call whose value it is.
+enum Flow {
+    Value(Value),
+    Call(Rc<Function>, Vec<Value>),
+}
+
+// The height of the Environment where the first scope an evaluation began
+// starts, and the frame it made for its tail calls, if any. Both are
+// released once the evaluation is done.
+struct Tail {
+    height: Option<usize>,
+    caller: Option<usize>,
+}
+
+impl Tail {
+    fn scope(&mut self, environment: &Environment) {
+        self.height.get_or_insert(environment.len());
+    }
+
+    fn release(self, environment: &mut Environment) {
+        if let Some(caller) = self.caller {
+            environment.leave(caller);
+        }
+        if let Some(height) = self.height {
+            environment.truncate(height);
+        }
+    }
+}
+
+fn trampoline(expr: &Expression, environment: &mut Environment, options: &Options, tail: &mut Tail) -> Result<Value, Error> {
+    let mut flow = run(expr, environment, options, tail)?;
+    loop {
+        let (function, arguments) = match flow {
+            Flow::Value(value) => return Ok(value),
+            Flow::Call(function, arguments) => (function, arguments),
+        };
+        if arguments.len() != function.parameters.len() {
+            return Err(Error::InvalidValues(function.name.clone(), arguments));
+        }
+        // Native code nests its tail calls, so one that gave up for lack of
+        // depth would only do so again for each call of the same chain.
+        #[cfg(feature = "jit")]
+        if options.engine == Backend::Jit && tail.caller.is_none() {
+            if let Some(value) = jit::call(&function, &arguments, environment, options) {
+                return Ok(value);
             }
         }
-        Expression::If {
-            condition,
-            then_branch,
-            else_branch,
-        } => {
-            let condition_value = evaluate(&condition, state)?;
-            match condition_value {
-                Value::Boolean(true) => evaluate(&then_branch, state),
-                Value::Boolean(false) => evaluate(&else_branch, state),
-                _ => Err(Error::InvalidValues("If".to_string(), vec![condition_value])),
+        // The first call enters a frame, which every later one replaces.
+        match tail.caller {
+            Some(caller) => {
+                environment.leave(caller);
+                environment.enter();
             }
+            None if environment.depth() >= options.max_depth => return Err(Error::StackOverflow(options.max_depth)),
+            None => tail.caller = Some(environment.enter()),
         }
-        Expression::Compare {
-            left,
-            operand,
-            right,
-        } => {
-            let left_value = evaluate(&left, state)?;
-            let right_value = evaluate(&right, state)?;
-            match (left_value.clone(), right_value.clone()) {
-                (Value::Integer(left), Value::Integer(right)) => match operand {
-                    Operand::Equals => Ok(Value::Boolean(left == right)),
-                    Operand::LessThan => Ok(Value::Boolean(left < right)),
-                    Operand::GreaterThan => Ok(Value::Boolean(left > right)),
-                    _ => Err(Error::InvalidOperand(operand.clone())),
-                },
-                _ => Err(Error::InvalidValues("Compare".to_string(), vec![left_value, right_value])),
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            match parameter.slot {
+                Some(slot) => environment.declare(slot, argument),
+                None => return Err(Error::UndefinedVariable(parameter.name.clone())),
             }
         }
-        Expression::Primitive(primitive) => match primitive {
-            Primitive::Integer(value) => Ok(Value::Integer(*value)),
-            Primitive::String(value) => Ok(Value::String(value.clone())),
-            Primitive::Boolean(value) => Ok(Value::Boolean(*value)),
-            _ => Err(Error::InvalidValues(format!("Invalid primitive value, {:?}", primitive), vec![])),
-        },
-        Expression::Let { variables, scope } => {
-            let mut state = match state.clone() {
-                Some(state) => state,
-                None => HashMap::new(),
-            };
+        flow = run(&function.body, environment, options, tail)?;
+    }
+}
 
-            for (name, value) in variables {
-                state.insert(name.to_string(), value.clone());
+// Follows an expression down through the parts whose value is its own,
+// evaluating what comes before them, until it reaches a call or something
+// to compute. Scopes begun on the way stay bound until the evaluation is
+// done, since nothing comes after them but its end.
+fn run(mut expr: &Expression, environment: &mut Environment, options: &Options, tail: &mut Tail) -> Result<Flow, Error> {
+    loop {
+        environment.usage().step(options)?;
+        expr = match expr {
+            Expression::Expression(inner)
+            | Expression::Line { statement: inner, .. }
+            | Expression::Function { scope: inner, .. }
+            | Expression::Declare { scope: inner, .. } => inner,
+            Expression::Block { body } => {
+                tail.scope(environment);
+                body
+            }
+            Expression::Chain { left, right } => {
+                evaluate(left, environment, options)?;
+                right
+            }
+            Expression::Let { variables, scope } => {
+                for (pattern, value) in variables {
+                    let value = evaluate(value, environment, options)?;
+                    let mut bindings = Vec::new();
+                    destructure(pattern, value, &mut bindings)?;
+                    bind(bindings, environment);
+                }
+                scope
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition_value = evaluate(condition, environment, options)?;
+                tail.scope(environment);
+                match condition_value {
+                    Value::Boolean(true) => then_branch,
+                    Value::Boolean(false) => else_branch,
+                    _ => return Err(Error::InvalidValues("If".to_string(), vec![condition_value])),
+                }
+            }
+            Expression::Match { value, arms } => {
+                let value = evaluate(value, environment, options)?;
+                tail.scope(environment);
+                matching(value, arms, environment)?
+            }
+            Expression::Call {
+                function,
+                definition,
+                arguments,
+            } => {
+                let mut values = Vec::new();
+                for argument in arguments {
+                    values.push(evaluate(argument, environment, options)?);
+                }
+                return match definition {
+                    Some(definition) => match environment.function(*definition) {
+                        Some(function) => Ok(Flow::Call(function, values)),
+                        None => Err(Error::UndefinedFunction(function.to_string())),
+                    },
+                    None => match stdlib::lookup(function) {
+                        Some(builtin) => created(builtin(values, options)?, environment, options),
+                        None => Err(Error::UndefinedFunction(function.to_string())),
+                    },
+                };
             }
+            _ => return created(compute(expr, environment, options)?, environment, options),
+        };
+    }
+}
 
-            evaluate(&scope, &Some(state))
-        }
-        Expression::Use { variable } => match state {
-            Some(state) => match state.get(variable) {
-                Some(value) => evaluate(&value, &Some(state.clone())),
-                None => Err(Error::UndefinedVariable(variable.to_string())),
-            },
-            None => Err(Error::UndefinedVariable(variable.to_string())),
-        },
+// Counts a value against the limits of the options.
+fn created(value: Value, environment: &mut Environment, options: &Options) -> Result<Flow, Error> {
+    environment.usage().allocate(&value, options)?;
+    Ok(Flow::Value(value))
+}
+
+// Evaluates everything `run` does not: operators, loops and the other
+// expressions whose value is not that of one of their own parts.
+fn compute(expr: &Expression, environment: &mut Environment, options: &Options) -> Result<Value, Error> {
+    match expr {
+        Expression::None {} => Ok(Value::None),
+        Expression::Sum { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            sum(left_value, right_value, options)
+        }),
+        Expression::Product { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            product(left_value, right_value, options)
+        }),
+        Expression::Divide { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            divide(left_value, right_value)
+        }),
+        Expression::IntegerDivide { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            integer_divide(left_value, right_value, options)
+        }),
+        Expression::Modulo { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            modulo(left_value, right_value, options)
+        }),
+        Expression::Power { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            power(left_value, right_value, options)
+        }),
+        Expression::Compare {
+            left,
+            operand,
+            right,
+        } => binary(left, right, environment, options, |left_value, right_value| {
+            comparison(operand, left_value, right_value)
+        }),
+        Expression::Bitwise {
+            left,
+            operand,
+            right,
+        } => binary(left, right, environment, options, |left_value, right_value| {
+            bitwise(operand, left_value, right_value, options)
+        }),
+        Expression::Primitive(primitive) => primitive_value(primitive, options),
+        Expression::Use { variable } => lookup(variable, environment),
         Expression::For {
             variable,
             from,
             to,
             body,
         } => {
-            let from_value = evaluate(&from, state)?;
-            let to_value = evaluate(&to, state)?;
-            let mut previous_value = state.clone();
-            match (from_value.clone(), to_value.clone()) {
-                (Value::Integer(from), Value::Integer(to)) => {
-                    for i in from..=to {
-                        let current_value = Rc::new(Expression::Primitive(Primitive::Integer(i)));
-                        match state {
-                            Some(_) => {
-                                match previous_value.clone() {
-                                    Some(prev) => {
-                                        let mut new = prev.clone();
-                                        new.insert(variable.to_string(), current_value);
-                                        let state_value = evaluate(&body, &Some(new.clone()))?;
-                                        match state_value {
-                                            Value::State(new_value) => previous_value = new_value,
-                                            _ => (),
-                                        }
-                                    }
-                                    None => {
-                                        let mut state = HashMap::new();
-                                        state.insert(variable.to_string(), current_value);
-                                        evaluate(&body, &Some(state))?;
-                                    }
-                                };
-                            }
-                            None => {
-                                let mut state = HashMap::new();
-                                state.insert(variable.to_string(), current_value);
-                                evaluate(&body, &Some(state))?;
-                            }
-                        }
-                    }
-                    Ok(Value::Unit)
-                }
+            let from_value = evaluate(from, environment, options)?;
+            let to_value = evaluate(to, environment, options)?;
+            match (from_value.clone(), to_value.clone(), variable.slot) {
+                (Value::Integer(from), Value::Integer(to), Some(slot)) => repeat(slot, from, to, body, environment, options),
+                (_, _, None) => Err(Error::UndefinedVariable(variable.name.clone())),
                 _ => Err(Error::InvalidValues("For => bad [from..to]".to_string(), vec![from_value, to_value])),
             }
         }
         Expression::Print { expression } => {
-            let string = match evaluate(&expression, state)? {
-                Value::String(val) => val,
-                Value::Integer(val) => val.to_string(),
-                Value::Float(val) => val.to_string(),
-                Value::Boolean(val) => val.to_string(),
-                Value::State(val) => {
-                    let mut string = String::new();
-                    for (key, value) in val.unwrap().iter() {
-                        string.push_str(&format!(
-                            "{} = {}\n",
-                            key,
-                            evaluate(&value, state).unwrap()
-                        ));
-                    }
-                    string
-                }
-                Value::Unit => "".to_string(),
-                Value::None => "None".to_string(),
-            };
-            println!("{}", string);
+            let value = evaluate(expression, environment, options)?;
+            options.output.print(&format_value(value));
             Ok(Value::Unit)
         }
-        Expression::Chain { left, right } => {
-            let state_value = evaluate(&left, state)?;
-            let resul = match state_value {
-                Value::State(state) => evaluate(&right, &state),
-                _ => evaluate(&right, state),
+        Expression::Assign { variable, value } => {
+            let value = evaluate(value, environment, options)?;
+            match variable.slot {
+                Some(slot) if environment.assign(slot, value) => Ok(Value::Unit),
+                _ => Err(Error::UndefinedVariable(variable.name.clone())),
+            }
+        }
+        Expression::Unary { operand, right } => {
+            let right_value = evaluate(right, environment, options)?;
+            unary(operand, right_value, options)
+        }
+        Expression::Subtract { left, right } => binary(left, right, environment, options, |left_value, right_value| {
+            subtract(left_value, right_value, options)
+        }),
+        Expression::Tuple { elements } => {
+            let mut values = Vec::new();
+            for element in elements {
+                values.push(evaluate(element, environment, options)?);
+            }
+            Ok(Value::Tuple(values))
+        }
+        Expression::Index { tuple, index } => {
+            let value = evaluate(tuple, environment, options)?;
+            element(value, *index)
+        }
+        Expression::Construct {
+            declaration,
+            constructor,
+            arguments,
+        } => {
+            let mut values = Vec::new();
+            for argument in arguments {
+                values.push(evaluate(argument, environment, options)?);
+            }
+            construct(declaration, *constructor, values)
+        }
+        Expression::Field { value, field } => {
+            let value = evaluate(value, environment, options)?;
+            member(value, field)
+        }
+        _ => unreachable!("run follows every other expression"),
+    }
+}
+
+// Evaluates both operands, left first, then applies the operator to them.
+// Operators live out here rather than in `evaluate`, whose frame every level
+// of nesting pays for.
+fn binary(
+    left: &Expression,
+    right: &Expression,
+    environment: &mut Environment,
+    options: &Options,
+    operator: impl FnOnce(Value, Value) -> Result<Value, Error>,
+) -> Result<Value, Error> {
+    let left_value = evaluate(left, environment, options)?;
+    let right_value = evaluate(right, environment, options)?;
+    operator(left_value, right_value)
+}
+
+pub(crate) fn sum(left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    let sum = integers("Sum", &left_value, &right_value, options, i64::checked_add, |left, right| {
+        left + right
+    });
+    match (sum, left_value.clone(), right_value.clone()) {
+        (Some(result), _, _) => result,
+        (None, Value::String(left), Value::String(right)) => Ok(Value::String(left + &right)),
+        _ => match (left_value.as_float(), right_value.as_float()) {
+            (Some(left), Some(right)) => Ok(Value::Float(left + right)),
+            _ => Err(Error::InvalidValues("Sum".to_string(), vec![left_value, right_value])),
+        },
+    }
+}
+
+pub(crate) fn product(left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    let product = integers("Product", &left_value, &right_value, options, i64::checked_mul, |left, right| {
+        left * right
+    });
+    match product {
+        Some(result) => result,
+        None => match (left_value.as_float(), right_value.as_float()) {
+            (Some(left), Some(right)) => Ok(Value::Float(left * right)),
+            _ => Err(Error::InvalidValues("Product".to_string(), vec![left_value, right_value])),
+        },
+    }
+}
+
+pub(crate) fn divide(left_value: Value, right_value: Value) -> Result<Value, Error> {
+    match (left_value.as_float(), right_value.as_float()) {
+        (Some(left), Some(right)) => {
+            if right == 0.0 {
+                Err(Error::DivisionByZero("Divide".to_string(), vec![left_value, right_value]))
+            } else {
+                Ok(Value::Float(left / right))
+            }
+        }
+        _ => Err(Error::InvalidValues("Divide".to_string(), vec![left_value, right_value])),
+    }
+}
+
+pub(crate) fn integer_divide(left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    if right_value.as_float() == Some(0.0) {
+        return Err(Error::DivisionByZero("IntegerDivide".to_string(), vec![left_value, right_value]));
+    }
+    let quotient = integers("IntegerDivide", &left_value, &right_value, options, floored_divide, |left, right| {
+        left.div_floor(&right)
+    });
+    match quotient {
+        Some(result) => result,
+        None => match (left_value.as_float(), right_value.as_float()) {
+            (Some(left), Some(right)) => Ok(Value::Float((left / right).floor())),
+            _ => Err(Error::InvalidValues("IntegerDivide".to_string(), vec![left_value, right_value])),
+        },
+    }
+}
+
+pub(crate) fn modulo(left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    if right_value.as_float() == Some(0.0) {
+        return Err(Error::DivisionByZero("Modulo".to_string(), vec![left_value, right_value]));
+    }
+    let modulo = integers("Modulo", &left_value, &right_value, options, floored_modulo, |left, right| {
+        left.mod_floor(&right)
+    });
+    match modulo {
+        Some(result) => result,
+        None => match (left_value.as_float(), right_value.as_float()) {
+            (Some(left), Some(right)) => Ok(Value::Float(left - right * (left / right).floor())),
+            _ => Err(Error::InvalidValues("Modulo".to_string(), vec![left_value, right_value])),
+        },
+    }
+}
+
+pub(crate) fn power(left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    match (left_value.as_big_integer(), right_value.clone()) {
+        (Some(base), Value::Integer(exponent)) if exponent >= 0 => match integer_power(base, exponent as u64, options)? {
+            Some(value) => Ok(value),
+            None => Err(Error::IntegerOverflow("Power".to_string(), vec![left_value, right_value])),
+        },
+        _ => match (left_value.as_float(), right_value.as_float()) {
+            (Some(left), Some(right)) => Ok(Value::Float(left.powf(right))),
+            _ => Err(Error::InvalidValues("Power".to_string(), vec![left_value, right_value])),
+        },
+    }
+}
+
+// The most bits a power may take when promoted, past which it overflows.
+const POWER_BITS: u64 = u32::MAX as u64;
+
+// Raises an integer to a power without computing a result the overflow mode
+// would only throw away. A power past 64 bits is known to overflow as soon
+// as it does not fit, wraps by squaring in 64 bits, and is only computed in
+// full when promoted, once its size is known to be under POWER_BITS and
+// what limits::reserve_power allows. Yields None on overflow.
+fn integer_power(base: BigInt, exponent: u64, options: &Options) -> Result<Option<Value>, Error> {
+    if let (Some(base), Ok(exponent)) = (base.to_i64(), u32::try_from(exponent)) {
+        if let Some(value) = base.checked_pow(exponent) {
+            return Ok(Some(Value::Integer(value)));
+        }
+    }
+    if exponent == 0 {
+        return Ok(Some(Value::Integer(1)));
+    }
+    let negative = base.is_negative() && exponent.is_odd();
+    match base.to_i64() {
+        Some(small @ (0 | 1)) => return Ok(Some(Value::Integer(small))),
+        Some(-1) => return Ok(Some(Value::Integer(if negative { -1 } else { 1 }))),
+        _ => {}
+    }
+    match options.overflow {
+        Overflow::Promote if base.bits().saturating_mul(exponent) <= POWER_BITS => {
+            limits::reserve_power((base.bits() * exponent).div_ceil(8) as usize, options)?;
+            Ok(Some(Value::from(base.pow(exponent as u32))))
+        }
+        Overflow::Promote | Overflow::Checked => Ok(None),
+        Overflow::Saturating => Ok(Some(Value::Integer(if negative { i64::MIN } else { i64::MAX }))),
+        Overflow::Wrapping => {
+            let mut square = match Overflow::Wrapping.fit(base) {
+                Some(Value::Integer(base)) => base,
+                _ => unreachable!(),
             };
-            resul
-        }
-        Expression::Assign { variable, value } => match state.clone() {
-            Some(mut state) => {
-                let value = evaluate(&value, &Some(state.clone()))?;
-                let prim = match value {
-                    Value::Integer(value) => Primitive::Integer(value),
-                    Value::String(value) => Primitive::String(value),
-                    _ => panic!("unexpected value: {:?}", value),
-                };
-                state.insert(variable.clone(), Rc::new(Expression::Primitive(prim)));
-                Ok(Value::State(Some(state)))
+            let (mut result, mut exponent) = (1i64, exponent);
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    result = result.wrapping_mul(square);
+                }
+                square = square.wrapping_mul(square);
+                exponent >>= 1;
             }
-            None => Err(Error::UndefinedVariable(variable.to_string())),
+            Ok(Some(Value::Integer(result)))
+        }
+    }
+}
+
+pub(crate) fn comparison(operand: &Operand, left_value: Value, right_value: Value) -> Result<Value, Error> {
+    match (left_value.clone(), right_value.clone()) {
+        (Value::Integer(left), Value::Integer(right)) => compare(operand, left, right),
+        _ => match (left_value.as_big_integer(), right_value.as_big_integer()) {
+            (Some(left), Some(right)) => compare(operand, left, right),
+            _ => match (left_value.as_float(), right_value.as_float()) {
+                (Some(left), Some(right)) => compare(operand, left, right),
+                _ => Err(Error::InvalidValues("Compare".to_string(), vec![left_value, right_value])),
+            },
         },
-        Expression::Unary { operand, right } => {
-            let right_value = evaluate(&right, state)?;
-            match right_value {
-                Value::Integer(value) => match operand {
-                    Operand::Negate => Ok(Value::Integer(-value)),
-                    Operand::Not => Ok(Value::Boolean(value == 0)),
-                    _ => Err(Error::InvalidOperand(operand.clone())),
-                },
-                Value::Boolean(value) => match operand {
-                    Operand::Not => Ok(Value::Boolean(!value)),
-                    _ => Err(Error::InvalidOperand(operand.clone())),
+    }
+}
+
+pub(crate) fn subtract(left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    let difference = integers("Subtract", &left_value, &right_value, options, i64::checked_sub, |left, right| {
+        left - right
+    });
+    match difference {
+        Some(result) => result,
+        None => match (left_value.as_float(), right_value.as_float()) {
+            (Some(left), Some(right)) => Ok(Value::Float(left - right)),
+            _ => Err(Error::InvalidValues("Subtract".to_string(), vec![left_value, right_value])),
+        },
+    }
+}
+
+pub(crate) fn unary(operand: &Operand, right_value: Value, options: &Options) -> Result<Value, Error> {
+    match right_value {
+        Value::Integer(_) | Value::BigInteger(_) => {
+            let value = right_value.as_big_integer().unwrap();
+            match operand {
+                Operand::Negate => match options.overflow.fit(-value) {
+                    Some(value) => Ok(value),
+                    None => Err(Error::IntegerOverflow("Unary".to_string(), vec![right_value])),
                 },
-                _ => Err(Error::InvalidValues("Unary".to_string(), vec![right_value])),
+                Operand::Not => Ok(Value::Boolean(value == BigInt::from(0))),
+                Operand::Complement => Ok(Value::from(-value - 1)),
+                _ => Err(Error::InvalidOperand(operand.clone())),
+            }
+        }
+        Value::Float(value) => match operand {
+            Operand::Negate => Ok(Value::Float(-value)),
+            _ => Err(Error::InvalidOperand(operand.clone())),
+        },
+        Value::Boolean(value) => match operand {
+            Operand::Not => Ok(Value::Boolean(!value)),
+            _ => Err(Error::InvalidOperand(operand.clone())),
+        },
+        _ => Err(Error::InvalidValues("Unary".to_string(), vec![right_value])),
+    }
+}
+
+pub(crate) fn primitive_value(primitive: &Primitive, options: &Options) -> Result<Value, Error> {
+    match primitive {
+        Primitive::Integer(value) => Ok(Value::Integer(*value)),
+        Primitive::BigInteger(value) => match options.overflow.fit(value.clone()) {
+            Some(value) => Ok(value),
+            None => Err(Error::IntegerOverflow(
+                "Primitive".to_string(),
+                vec![Value::BigInteger(value.clone())],
+            )),
+        },
+        Primitive::Float(value) => Ok(Value::Float(*value)),
+        Primitive::String(value) => Ok(Value::String(value.clone())),
+        Primitive::Boolean(value) => Ok(Value::Boolean(*value)),
+        Primitive::ToReplace => Err(Error::InvalidValues(format!("Invalid primitive value, {:?}", primitive), vec![])),
+    }
+}
+
+fn lookup(variable: &Variable, environment: &Environment) -> Result<Value, Error> {
+    let value = match variable.slot {
+        Some(slot) if variable.global => environment.global(slot).cloned(),
+        Some(slot) => environment.get(slot).cloned(),
+        None => stdlib::constant(&variable.name),
+    };
+    value.ok_or_else(|| Error::UndefinedVariable(variable.name.clone()))
+}
+
+pub(crate) fn format_value(value: Value) -> String {
+    match value {
+        Value::String(val) => val,
+        Value::Integer(val) => val.to_string(),
+        Value::BigInteger(val) => val.to_string(),
+        Value::Float(val) => val.to_string(),
+        Value::Boolean(val) => val.to_string(),
+        Value::List(values) => format_list(&values),
+        Value::Tuple(values) => format_tuple(&values),
+        value @ (Value::Struct(..) | Value::Variant(..)) => format_element(&value),
+        Value::Unit => "".to_string(),
+        Value::None => "None".to_string(),
+    }
+}
+
+// Integer arithmetic is exact: the overflow option only decides what to do
+// with a result that does not fit in an i64. Yields None unless both values
+// are integers.
+fn integers(
+    name: &str,
+    left_value: &Value,
+    right_value: &Value,
+    options: &Options,
+    checked: fn(i64, i64) -> Option<i64>,
+    exact: fn(BigInt, BigInt) -> BigInt,
+) -> Option<Result<Value, Error>> {
+    if let (Value::Integer(left), Value::Integer(right)) = (left_value, right_value) {
+        if let Some(value) = checked(*left, *right) {
+            return Some(Ok(Value::Integer(value)));
+        }
+    }
+    let left = left_value.as_big_integer()?;
+    let right = right_value.as_big_integer()?;
+    Some(match options.overflow.fit(exact(left, right)) {
+        Some(value) => Ok(value),
+        None => Err(Error::IntegerOverflow(
+            name.to_string(),
+            vec![left_value.clone(), right_value.clone()],
+        )),
+    })
+}
+
+pub(crate) fn bitwise(operand: &Operand, left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    let name = format!("{:?}", operand);
+    let result = match operand {
+        Operand::BitAnd => integers(&name, &left_value, &right_value, options, |l, r| Some(l & r), |l, r| l & r),
+        Operand::BitOr => integers(&name, &left_value, &right_value, options, |l, r| Some(l | r), |l, r| l | r),
+        Operand::BitXor => integers(&name, &left_value, &right_value, options, |l, r| Some(l ^ r), |l, r| l ^ r),
+        Operand::ShiftLeft | Operand::ShiftRight => return shift(operand, left_value, right_value, options),
+        _ => return Err(Error::InvalidOperand(operand.clone())),
+    };
+    match result {
+        Some(result) => result,
+        None => Err(Error::InvalidValues(name, vec![left_value, right_value])),
+    }
+}
+
+// Shift amounts must lie within the 64 bits of an integer. Bits shifted out
+// on the left follow the overflow option like any other arithmetic.
+fn shift(operand: &Operand, left_value: Value, right_value: Value, options: &Options) -> Result<Value, Error> {
+    let name = format!("{:?}", operand);
+    let amount = match right_value {
+        Value::Integer(amount) if (0..64).contains(&amount) => amount as u32,
+        Value::Integer(_) | Value::BigInteger(_) => {
+            return Err(Error::InvalidShift(name, vec![left_value, right_value]));
+        }
+        _ => return Err(Error::InvalidValues(name, vec![left_value, right_value])),
+    };
+    let value = match left_value.as_big_integer() {
+        Some(value) => value,
+        None => return Err(Error::InvalidValues(name, vec![left_value, right_value])),
+    };
+    match operand {
+        Operand::ShiftLeft => match options.overflow.fit(value << amount) {
+            Some(value) => Ok(value),
+            None => Err(Error::IntegerOverflow(name, vec![left_value, right_value])),
+        },
+        _ => Ok(Value::from(value >> amount)),
+    }
+}
+
+// Runs the body of a `for` once for every integer from `from` to `to`, each
+// time in a scope of its own.
+fn repeat(slot: usize, from: i64, to: i64, body: &ExpressionRef, environment: &mut Environment, options: &Options) -> Result<Value, Error> {
+    #[cfg(feature = "jit")]
+    if options.engine == Backend::Jit {
+        if let Some(value) = jit::repeat(slot, from, to, body, environment, options) {
+            return Ok(value);
+        }
+    }
+    for i in from..=to {
+        environment.scoped(|environment| {
+            environment.declare(slot, Value::Integer(i));
+            evaluate(body, environment, options)
+        })?;
+    }
+    Ok(Value::Unit)
+}
+
+// Division and modulo round towards negative infinity, so the remainder
+// always has the sign of the divisor.
+fn floored_divide(left: i64, right: i64) -> Option<i64> {
+    let quotient = left.checked_div(right)?;
+    if left % right != 0 && (left < 0) != (right < 0) {
+        Some(quotient - 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+fn floored_modulo(left: i64, right: i64) -> Option<i64> {
+    let remainder = left.checked_rem(right)?;
+    if remainder != 0 && (remainder < 0) != (right < 0) {
+        Some(remainder + right)
+    } else {
+        Some(remainder)
+    }
+}
+
+fn compare<T: PartialOrd>(operand: &Operand, left: T, right: T) -> Result<Value, Error> {
+    match operand {
+        Operand::Equals => Ok(Value::Boolean(left == right)),
+        Operand::LessThan => Ok(Value::Boolean(left < right)),
+        Operand::GreaterThan => Ok(Value::Boolean(left > right)),
+        _ => Err(Error::InvalidOperand(operand.clone())),
+    }
+}
+
+pub(crate) fn element(value: Value, index: usize) -> Result<Value, Error> {
+    match value {
+        Value::Tuple(mut values) if index < values.len() => Ok(values.swap_remove(index)),
+        value => Err(Error::InvalidValues("Index".to_string(), vec![value, Value::Integer(index as i64)])),
+    }
+}
+
+pub(crate) fn member(value: Value, field: &str) -> Result<Value, Error> {
+    match value {
+        Value::Struct(name, fields) => match fields.into_iter().find(|(name, _)| name == field) {
+            Some((_, value)) => Ok(value),
+            None => Err(Error::InvalidValues(format!("{}.{}", name, field), vec![])),
+        },
+        value => Err(Error::InvalidValues("Field".to_string(), vec![value])),
+    }
+}
+
+pub(crate) fn construct(declaration: &Declaration, constructor: usize, values: Vec<Value>) -> Result<Value, Error> {
+    let constructor = &declaration.constructors[constructor];
+    if values.len() != constructor.fields.len() {
+        return Err(Error::InvalidValues(constructor.name.clone(), values));
+    }
+    if declaration.enumeration {
+        Ok(Value::Variant(constructor.name.clone(), values))
+    } else {
+        let fields = constructor.fields.iter().map(|(field, _)| field.clone());
+        Ok(Value::Struct(constructor.name.clone(), fields.zip(values).collect()))
+    }
+}
+
+// Binds the variables of the first arm whose pattern fits the value,
+// returning the body of that arm.
+fn matching<'a>(value: Value, arms: &'a [(Pattern, ExpressionRef)], environment: &mut Environment) -> Result<&'a Expression, Error> {
+    for (pattern, body) in arms {
+        let mut bindings = Vec::new();
+        match destructure(pattern, value.clone(), &mut bindings) {
+            Ok(()) => {}
+            Err(Error::PatternMismatch(..)) => continue,
+            Err(err) => return Err(err),
+        }
+        bind(bindings, environment);
+        return Ok(body);
+    }
+    Err(Error::UnmatchedValue(value))
+}
+
+// Declares what a pattern bound, globals among the globals.
+fn bind(bindings: Vec<(usize, bool, Value)>, environment: &mut Environment) {
+    for (slot, global, value) in bindings {
+        match global {
+            true => environment.declare_global(slot, value),
+            false => environment.declare(slot, value),
+        }
+    }
+}
+
+// Matches a value against a pattern, collecting the slot of every variable,
+// whether it is a global, and its value in the order the resolver declared
+// them. Nothing is collected for a value that does not fit.
+pub(crate) fn destructure(pattern: &Pattern, value: Value, bindings: &mut Vec<(usize, bool, Value)>) -> Result<(), Error> {
+    match (pattern, &value) {
+        (Pattern::Variable(variable), _) => match variable.slot {
+            Some(slot) => bindings.push((slot, variable.global, value)),
+            None => return Err(Error::UndefinedVariable(variable.name.clone())),
+        },
+        (Pattern::Literal(literal), _) if equals(literal, &value) => {}
+        (Pattern::Tuple(elements), Value::Tuple(values)) if elements.len() == values.len() => {
+            for (element, value) in elements.iter().zip(values) {
+                destructure(element, value.clone(), bindings)?;
             }
         }
-        Expression::DeriveState { expression } => {
-            evaluate(&expression, state)?;
-            Ok(Value::State(state.clone()))
+        (Pattern::List { elements, rest }, Value::List(values))
+            if values.len() == elements.len() || (rest.is_some() && values.len() > elements.len()) =>
+        {
+            for (element, value) in elements.iter().zip(values) {
+                destructure(element, value.clone(), bindings)?;
+            }
+            if let Some(rest) = rest {
+                let remaining = Value::List(values[elements.len()..].to_vec());
+                destructure(&Pattern::Variable(rest.clone()), remaining, bindings)?;
+            }
         }
-        Expression::Subtract { left, right } => {
-            let left_value = evaluate(&left, state)?;
-            let right_value = evaluate(&right, state)?;
-            match (left_value.clone(), right_value.clone()) {
-                (Value::Integer(left), Value::Integer(right)) => Ok(Value::Integer(left - right)),
-                _ => Err(Error::InvalidValues("Subtract".to_string(), vec![left_value, right_value])),
+        (Pattern::Constructor { name, elements }, Value::Variant(constructor, values))
+            if name == constructor && elements.len() == values.len() =>
+        {
+            for (element, value) in elements.iter().zip(values) {
+                destructure(element, value.clone(), bindings)?;
             }
         }
-        Expression::Expression(expr) => {
-            let state = state.clone();
-            let result = evaluate(&expr, &state)?;
-            Ok(result)
+        (Pattern::Constructor { name, elements }, Value::Struct(constructor, fields))
+            if name == constructor && elements.len() == fields.len() =>
+        {
+            for (element, (_, value)) in elements.iter().zip(fields) {
+                destructure(element, value.clone(), bindings)?;
+            }
         }
+        _ => return Err(Error::PatternMismatch(Box::new(pattern.clone()), value)),
+    }
+    Ok(())
+}
+
+// Whether a value is the one a literal pattern stands for.
+fn equals(literal: &Primitive, value: &Value) -> bool {
+    match (literal, value) {
+        (Primitive::Integer(literal), Value::Integer(value)) => literal == value,
+        (Primitive::BigInteger(literal), Value::BigInteger(value)) => literal == value,
+        (Primitive::Float(literal), Value::Float(value)) => literal == value,
+        (Primitive::String(literal), Value::String(value)) => literal == value,
+        (Primitive::Boolean(literal), Value::Boolean(value)) => literal == value,
+        _ => false,
+    }
+}
+
+fn format_element(value: &Value) -> String {
+    match value {
+        Value::String(val) => format!("{:?}", val),
+        Value::Integer(val) => val.to_string(),
+        Value::BigInteger(val) => val.to_string(),
+        Value::Float(val) => val.to_string(),
+        Value::Boolean(val) => val.to_string(),
+        Value::List(values) => format_list(values),
+        Value::Tuple(values) => format_tuple(values),
+        Value::Struct(name, fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(field, value)| format!("{}: {}", field, format_element(value)))
+                .collect();
+            format!("{} {{ {} }}", name, fields.join(", "))
+        }
+        Value::Variant(name, values) if values.is_empty() => name.clone(),
+        Value::Variant(name, values) => {
+            let items: Vec<String> = values.iter().map(format_element).collect();
+            format!("{}({})", name, items.join(", "))
+        }
+        Value::Unit => "()".to_string(),
+        Value::None => "None".to_string(),
+    }
+}
+
+fn format_list(values: &[Value]) -> String {
+    let items: Vec<String> = values.iter().map(format_element).collect();
+    format!("[{}]", items.join(", "))
+}
+
+// A one-element tuple keeps its trailing comma, as in the source.
+fn format_tuple(values: &[Value]) -> String {
+    let items: Vec<String> = values.iter().map(format_element).collect();
+    match items.as_slice() {
+        [item] => format!("({},)", item),
+        _ => format!("({})", items.join(", ")),
     }
 }
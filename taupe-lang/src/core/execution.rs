@@ -0,0 +1,419 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::stdlib;
+
+use super::{
+    compilation::{compile_function, Chunk, Compiled, Instruction, Program},
+    environment::Environment,
+    evaluation::{
+        bitwise, comparison, construct, destructure, divide, element, format_value, integer_divide,
+        member, modulo, power, primitive_value, product, subtract, sum, unary,
+    },
+    options::Options,
+    values::{Error, Value},
+};
+
+// Runs a compiled program, with the same results as evaluating the tree it
+// was compiled from. Calls push a frame onto a stack of their own rather
+// than recursing, and so do not grow the Rust stack. A call in tail position
+// replaces the frame it is made from.
+//
+// Functions of the program are known by definition number. A call to any
+// other function compiles the one the Environment defines, as the REPL
+// needs for functions declared by earlier lines.
+pub fn execute(
+    program: &Program,
+    environment: &mut Environment,
+    options: &Options,
+) -> Result<Value, Error> {
+    let mut machine = Machine {
+        functions: program
+            .functions
+            .iter()
+            .map(|function| (function.definition, function.clone()))
+            .collect(),
+        stack: Vec::new(),
+        frames: Vec::new(),
+        scopes: Vec::new(),
+    };
+    let result = machine.run(program.main.clone(), environment, options);
+    if result.is_err() {
+        machine.unwind(environment);
+    }
+    result
+}
+
+struct Frame {
+    chunk: Rc<Chunk>,
+    ip: usize,
+    // The base of the caller's frame in the Environment, for a call.
+    caller: Option<usize>,
+    // The number of scopes open when the frame began.
+    scopes: usize,
+}
+
+struct Machine {
+    functions: HashMap<usize, Rc<Compiled>>,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    // The height of the Environment where each open scope began.
+    scopes: Vec<usize>,
+}
+
+impl Machine {
+    fn run(
+        &mut self,
+        main: Rc<Chunk>,
+        environment: &mut Environment,
+        options: &Options,
+    ) -> Result<Value, Error> {
+        self.frames.push(Frame {
+            chunk: main,
+            ip: 0,
+            caller: None,
+            scopes: 0,
+        });
+
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let chunk = frame.chunk.clone();
+            let instruction = &chunk.code[frame.ip];
+            frame.ip += 1;
+            environment.usage().step(options)?;
+
+            match instruction {
+                Instruction::Constant(constant) => {
+                    let value = primitive_value(&chunk.constants[*constant], options)?;
+                    self.stack.push(value);
+                }
+                Instruction::Unit => self.stack.push(Value::Unit),
+                Instruction::Nil => self.stack.push(Value::None),
+                Instruction::Global(name) => match stdlib::constant(&chunk.names[*name]) {
+                    Some(value) => self.stack.push(value),
+                    None => return Err(Error::UndefinedVariable(chunk.names[*name].clone())),
+                },
+                Instruction::Load { slot, name } => match environment.get(*slot) {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => return Err(Error::UndefinedVariable(chunk.names[*name].clone())),
+                },
+                Instruction::LoadGlobal { slot, name } => match environment.global(*slot) {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => return Err(Error::UndefinedVariable(chunk.names[*name].clone())),
+                },
+                Instruction::Assign { slot, name } => {
+                    let value = self.pop()?;
+                    if !environment.assign(*slot, value) {
+                        return Err(Error::UndefinedVariable(chunk.names[*name].clone()));
+                    }
+                    self.stack.push(Value::Unit);
+                }
+                Instruction::Bind(pattern) => {
+                    let value = self.pop()?;
+                    let mut bindings = Vec::new();
+                    destructure(&chunk.patterns[*pattern], value, &mut bindings)?;
+                    for (slot, global, value) in bindings {
+                        Machine::declare(environment, slot, global, value)?;
+                    }
+                }
+                Instruction::Undefined(name) => {
+                    return Err(Error::UndefinedVariable(chunk.names[*name].clone()))
+                }
+                Instruction::Pop => {
+                    self.pop()?;
+                }
+                Instruction::Sum => self.binary(|left, right| sum(left, right, options))?,
+                Instruction::Product => self.binary(|left, right| product(left, right, options))?,
+                Instruction::Divide => self.binary(divide)?,
+                Instruction::Subtract => {
+                    self.binary(|left, right| subtract(left, right, options))?
+                }
+                Instruction::IntegerDivide => {
+                    self.binary(|left, right| integer_divide(left, right, options))?
+                }
+                Instruction::Modulo => self.binary(|left, right| modulo(left, right, options))?,
+                Instruction::Power => self.binary(|left, right| power(left, right, options))?,
+                Instruction::Compare(operand) => {
+                    self.binary(|left, right| comparison(operand, left, right))?
+                }
+                Instruction::Bitwise(operand) => {
+                    self.binary(|left, right| bitwise(operand, left, right, options))?
+                }
+                Instruction::Unary(operand) => {
+                    let right = self.pop()?;
+                    self.stack.push(unary(operand, right, options)?);
+                }
+                Instruction::Print => {
+                    let value = self.pop()?;
+                    options.output.print(&format_value(value));
+                    self.stack.push(Value::Unit);
+                }
+                Instruction::Tuple(elements) => {
+                    let values = self.take(*elements)?;
+                    self.stack.push(Value::Tuple(values));
+                }
+                Instruction::Index(index) => {
+                    let value = self.pop()?;
+                    self.stack.push(element(value, *index)?);
+                }
+                Instruction::Construct {
+                    declaration,
+                    constructor,
+                    arguments,
+                } => {
+                    let values = self.take(*arguments)?;
+                    let declaration = &chunk.declarations[*declaration];
+                    self.stack
+                        .push(construct(declaration, *constructor, values)?);
+                }
+                Instruction::Field(field) => {
+                    let value = self.pop()?;
+                    self.stack.push(member(value, &chunk.names[*field])?);
+                }
+                Instruction::Call {
+                    definition,
+                    name,
+                    arguments,
+                } => {
+                    let arguments = self.take(*arguments)?;
+                    let function = match self.function(*definition, environment) {
+                        Some(function) => function,
+                        None => return Err(Error::UndefinedFunction(chunk.names[*name].clone())),
+                    };
+                    self.call(function, arguments, environment, options)?;
+                }
+                Instruction::Builtin { name, arguments } => {
+                    let arguments = self.take(*arguments)?;
+                    match stdlib::lookup(&chunk.names[*name]) {
+                        Some(builtin) => self.stack.push(builtin(arguments, options)?),
+                        None => return Err(Error::UndefinedFunction(chunk.names[*name].clone())),
+                    }
+                }
+                Instruction::EnterScope => self.scopes.push(environment.len()),
+                Instruction::ExitScope => {
+                    let height = self.scopes.pop().ok_or_else(Machine::corrupt)?;
+                    environment.truncate(height);
+                }
+                Instruction::Jump(to) => self.jump(*to),
+                Instruction::JumpUnless(to) => match self.pop()? {
+                    Value::Boolean(true) => {}
+                    Value::Boolean(false) => self.jump(*to),
+                    condition => {
+                        return Err(Error::InvalidValues("If".to_string(), vec![condition]))
+                    }
+                },
+                Instruction::Range(exit) => {
+                    let to = self.pop()?;
+                    let from = self.pop()?;
+                    match (&from, &to) {
+                        (Value::Integer(first), Value::Integer(last)) if first > last => {
+                            self.jump(*exit)
+                        }
+                        (Value::Integer(_), Value::Integer(_)) => {
+                            self.stack.push(from);
+                            self.stack.push(to);
+                        }
+                        _ => {
+                            return Err(Error::InvalidValues(
+                                "For => bad [from..to]".to_string(),
+                                vec![from, to],
+                            ))
+                        }
+                    }
+                }
+                Instruction::Iterate(slot) => {
+                    let counter = self.peek(1)?.clone();
+                    Machine::declare(environment, *slot, false, counter)?;
+                }
+                Instruction::Next(start) => {
+                    let last = self.pop()?;
+                    match (self.pop()?, last) {
+                        (Value::Integer(counter), Value::Integer(last)) if counter < last => {
+                            self.stack.push(Value::Integer(counter + 1));
+                            self.stack.push(Value::Integer(last));
+                            self.jump(*start);
+                        }
+                        _ => {}
+                    }
+                }
+                Instruction::Try { pattern, otherwise } => {
+                    let value = self.peek(0)?.clone();
+                    let mut bindings = Vec::new();
+                    match destructure(&chunk.patterns[*pattern], value, &mut bindings) {
+                        Ok(()) => {
+                            self.pop()?;
+                            self.scopes.push(environment.len());
+                            for (slot, global, value) in bindings {
+                                Machine::declare(environment, slot, global, value)?;
+                            }
+                        }
+                        Err(Error::PatternMismatch(..)) => self.jump(*otherwise),
+                        Err(err) => return Err(err),
+                    }
+                }
+                Instruction::Unmatched => return Err(Error::UnmatchedValue(self.pop()?)),
+                Instruction::Return => {
+                    let frame = self.frames.pop().unwrap();
+                    match frame.caller {
+                        Some(base) => environment.leave(base),
+                        None => return self.pop(),
+                    }
+                }
+            }
+            if creates(instruction) {
+                environment.usage().allocate(self.peek(0)?, options)?;
+            }
+        }
+    }
+
+    fn function(&mut self, definition: usize, environment: &Environment) -> Option<Rc<Compiled>> {
+        if let Some(function) = self.functions.get(&definition) {
+            return Some(function.clone());
+        }
+        let function = environment.function(definition)?;
+        let function = Rc::new(compile_function(&function)?);
+        self.functions.insert(definition, function.clone());
+        Some(function)
+    }
+
+    fn call(
+        &mut self,
+        function: Rc<Compiled>,
+        arguments: Vec<Value>,
+        environment: &mut Environment,
+        options: &Options,
+    ) -> Result<(), Error> {
+        if arguments.len() != function.parameters.len() {
+            return Err(Error::InvalidValues(function.name.clone(), arguments));
+        }
+        let frame = self.frames.last_mut().unwrap();
+        match frame.caller {
+            Some(caller) if Self::returns(&frame.chunk, frame.ip) => {
+                self.scopes.truncate(frame.scopes);
+                environment.leave(caller);
+                environment.enter();
+                frame.chunk = function.chunk.clone();
+                frame.ip = 0;
+            }
+            _ if environment.depth() >= options.max_depth => {
+                return Err(Error::StackOverflow(options.max_depth))
+            }
+            _ => {
+                let caller = environment.enter();
+                self.frames.push(Frame {
+                    chunk: function.chunk.clone(),
+                    ip: 0,
+                    caller: Some(caller),
+                    scopes: self.scopes.len(),
+                });
+            }
+        }
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            match parameter.slot {
+                Some(slot) => Machine::declare(environment, slot, false, argument)?,
+                None => return Err(Error::UndefinedVariable(parameter.name.clone())),
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the code from `ip` on only ends scopes and jumps until the
+    // chunk returns, which makes a call just before it a tail call.
+    fn returns(chunk: &Chunk, mut ip: usize) -> bool {
+        loop {
+            match chunk.code[ip] {
+                Instruction::Return => return true,
+                Instruction::ExitScope => ip += 1,
+                Instruction::Jump(to) if to > ip => ip = to,
+                _ => return false,
+            }
+        }
+    }
+
+    // Releases what the frames and scopes still open hold, as the evaluator
+    // does when an error goes up through them.
+    fn unwind(&mut self, environment: &mut Environment) {
+        while let Some(frame) = self.frames.pop() {
+            if let Some(base) = frame.caller {
+                environment.leave(base);
+            }
+        }
+        if let Some(height) = self.scopes.first() {
+            environment.truncate(*height);
+        }
+    }
+
+    fn binary(
+        &mut self,
+        operator: impl FnOnce(Value, Value) -> Result<Value, Error>,
+    ) -> Result<(), Error> {
+        let right = self.pop()?;
+        let left = self.pop()?;
+        self.stack.push(operator(left, right)?);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Value, Error> {
+        self.stack.pop().ok_or_else(Machine::corrupt)
+    }
+
+    // Pops the top `count` values, in the order they were pushed.
+    fn take(&mut self, count: usize) -> Result<Vec<Value>, Error> {
+        match self.stack.len().checked_sub(count) {
+            Some(height) => Ok(self.stack.split_off(height)),
+            None => Err(Machine::corrupt()),
+        }
+    }
+
+    // The value `depth` values below the top of the stack.
+    fn peek(&self, depth: usize) -> Result<&Value, Error> {
+        match self.stack.len().checked_sub(depth + 1) {
+            Some(index) => Ok(&self.stack[index]),
+            None => Err(Machine::corrupt()),
+        }
+    }
+
+    fn declare(environment: &mut Environment, slot: usize, global: bool, value: Value) -> Result<(), Error> {
+        if !environment.declarable(slot) {
+            return Err(Error::InvalidBytecode(format!("slot {} skips a free slot", slot)));
+        }
+        match global {
+            true if environment.depth() > 0 => {
+                return Err(Error::InvalidBytecode(format!("global {} declared in a function", slot)))
+            }
+            true => environment.declare_global(slot, value),
+            false => environment.declare(slot, value),
+        }
+        Ok(())
+    }
+
+    // Compiled code never pops more than it pushed nor ends more scopes than
+    // it began; code read from a file that does is corrupt.
+    fn corrupt() -> Error {
+        Error::InvalidBytecode("the stack runs short".to_string())
+    }
+
+    fn jump(&mut self, to: usize) {
+        self.frames.last_mut().unwrap().ip = to;
+    }
+}
+
+// Whether an instruction pushes a value it created, as the evaluator counts
+// against the limits of the options.
+fn creates(instruction: &Instruction) -> bool {
+    !matches!(
+        instruction,
+        Instruction::Bind(_)
+            | Instruction::Pop
+            | Instruction::Call { .. }
+            | Instruction::EnterScope
+            | Instruction::ExitScope
+            | Instruction::Jump(_)
+            | Instruction::JumpUnless(_)
+            | Instruction::Range(_)
+            | Instruction::Iterate(_)
+            | Instruction::Next(_)
+            | Instruction::Try { .. }
+            | Instruction::Unmatched
+            | Instruction::Return
+    )
+}
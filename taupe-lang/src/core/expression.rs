@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use super::primitives::Primitive;
+use super::{primitives::Primitive, stack, types::Type};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Operand {
@@ -9,6 +9,105 @@ pub enum Operand {
     GreaterThan,
     Not,
     Negate,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Complement,
+    ShiftLeft,
+    ShiftRight,
+}
+
+// A variable as written in the source. The resolver fills in the slot the
+// variable's value lives in; until then it is None. `mutable` and
+// `annotation` are only ever set where a variable is declared. `global` marks
+// the declarations of globals, and the reads of them from function bodies,
+// whose slot is then counted from the bottom of the stack, not the frame.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub slot: Option<usize>,
+    pub mutable: bool,
+    pub annotation: Option<Type>,
+    pub global: bool,
+}
+
+impl Variable {
+    pub fn new(name: String) -> Variable {
+        Variable {
+            name,
+            slot: None,
+            mutable: false,
+            annotation: None,
+            global: false,
+        }
+    }
+
+    pub fn mutable(name: String) -> Variable {
+        Variable {
+            mutable: true,
+            ..Variable::new(name)
+        }
+    }
+
+    pub fn resolved(name: String, slot: usize) -> Variable {
+        Variable {
+            slot: Some(slot),
+            ..Variable::new(name)
+        }
+    }
+}
+
+// A function declared with `fn`. Functions see their parameters, other
+// functions and the immutable globals, but no other variable of the scope
+// declaring them. The resolver numbers each function; calls refer to it by
+// that definition. `generics` names the type parameters its annotations may
+// use.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub name: String,
+    pub line: usize,
+    pub definition: Option<usize>,
+    pub generics: Vec<String>,
+    pub parameters: Vec<Variable>,
+    pub returns: Option<Type>,
+    pub body: ExpressionRef,
+}
+
+// A type declared with `struct` or `enum`, generic over `parameters`. A
+// struct has a single constructor named after it; the fields of an enum's
+// constructors are named by their position.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Declaration {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub enumeration: bool,
+    pub constructors: Vec<Constructor>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Constructor {
+    pub name: String,
+    pub fields: Vec<(String, Type)>,
+}
+
+// The left-hand side of a `let`: a single variable, or the shape of a
+// sequence whose elements are bound in turn. A list pattern may end with
+// `..rest`, which binds the remaining elements as a list. A constructor
+// pattern matches the fields of a struct or enum value in order. A literal
+// binds nothing and matches only a value equal to it.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Variable(Variable),
+    Literal(Primitive),
+    Tuple(Vec<Pattern>),
+    List {
+        elements: Vec<Pattern>,
+        rest: Option<Variable>,
+    },
+    Constructor {
+        name: String,
+        elements: Vec<Pattern>,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -31,6 +130,18 @@ pub enum Expression {
         left: ExpressionRef,
         right: ExpressionRef,
     },
+    IntegerDivide {
+        left: ExpressionRef,
+        right: ExpressionRef,
+    },
+    Modulo {
+        left: ExpressionRef,
+        right: ExpressionRef,
+    },
+    Power {
+        left: ExpressionRef,
+        right: ExpressionRef,
+    },
     If {
         condition: ExpressionRef,
         then_branch: ExpressionRef,
@@ -41,23 +152,28 @@ pub enum Expression {
         operand: Operand,
         right: ExpressionRef,
     },
+    Bitwise {
+        left: ExpressionRef,
+        operand: Operand,
+        right: ExpressionRef,
+    },
     Let {
-        variables: Vec<(String, ExpressionRef)>,
+        variables: Vec<(Pattern, ExpressionRef)>,
         scope: ExpressionRef,
     },
     Use {
-        variable: String,
+        variable: Variable,
     },
     Unary {
         operand: Operand,
         right: ExpressionRef,
     },
     Assign {
-        variable: String,
+        variable: Variable,
         value: ExpressionRef,
     },
     For {
-        variable: String,
+        variable: Variable,
         from: ExpressionRef,
         to: ExpressionRef,
         body: ExpressionRef,
@@ -69,92 +185,139 @@ pub enum Expression {
         left: ExpressionRef,
         right: ExpressionRef,
     },
-    DeriveState {
-        expression: ExpressionRef,
+    // A statement, with the source line it starts on.
+    Line {
+        line: usize,
+        statement: ExpressionRef,
+    },
+    Block {
+        body: ExpressionRef,
+    },
+    Function {
+        function: Rc<Function>,
+        scope: ExpressionRef,
+    },
+    // A call to a stdlib function, or to a user function once resolved.
+    Call {
+        function: String,
+        definition: Option<usize>,
+        arguments: Vec<ExpressionRef>,
+    },
+    Tuple {
+        elements: Vec<ExpressionRef>,
+    },
+    Index {
+        tuple: ExpressionRef,
+        index: usize,
+    },
+    Declare {
+        declaration: Rc<Declaration>,
+        scope: ExpressionRef,
+    },
+    // The resolver turns calls to a constructor into this.
+    Construct {
+        declaration: Rc<Declaration>,
+        constructor: usize,
+        arguments: Vec<ExpressionRef>,
+    },
+    Field {
+        value: ExpressionRef,
+        field: String,
+    },
+    // Runs the first arm whose pattern matches the value.
+    Match {
+        value: ExpressionRef,
+        arms: Vec<(Pattern, ExpressionRef)>,
     },
     None {},
 }
 pub type ExpressionRef = Rc<Expression>;
 
 impl Expression {
+    // A copy of the tree with every ToReplace hole filled with `expression`.
     pub fn replace_with(&self, expression: Expression) -> Expression {
         let mut old_expr = self.clone();
-        let final_ = Expression::replace_with_inner(&mut old_expr, expression.clone());
-        final_
+        stack::grow(|| Expression::replace_with_inner(&mut old_expr, expression))
     }
 
     pub fn replace_with_inner(old_expr: &mut Expression, expression: Expression) -> Expression {
+        let fill = |child: &ExpressionRef| Rc::new(child.replace_with(expression.clone()));
+        let fill_all = |children: &[ExpressionRef]| children.iter().map(fill).collect::<Vec<_>>();
         match old_expr {
-            Expression::Primitive(primitive) => {
-                match primitive {
-                    Primitive::ToReplace => {
-                        expression.clone()
-                    }
-                    _ => old_expr.clone(),
-                }
-            }
-            Expression::Expression(expr) => {
-                Expression::Expression(Rc::new(expr.replace_with(expression.clone())))
+            Expression::Primitive(Primitive::ToReplace) => expression.clone(),
+            Expression::Primitive(_) | Expression::Use { .. } | Expression::None {} => {
+                old_expr.clone()
             }
+            Expression::Expression(expr) => Expression::Expression(fill(expr)),
             Expression::Sum { left, right } => Expression::Sum {
-                left: Rc::new(left.replace_with(expression.clone())),
-                right: Rc::new(right.replace_with(expression.clone())),
+                left: fill(left),
+                right: fill(right),
             },
             Expression::Product { left, right } => Expression::Product {
-                left: Rc::new(left.replace_with(expression.clone())),
-                right: Rc::new(right.replace_with(expression.clone())),
+                left: fill(left),
+                right: fill(right),
             },
             Expression::Divide { left, right } => Expression::Divide {
-                left: Rc::new(left.replace_with(expression.clone())),
-                right: Rc::new(right.replace_with(expression.clone())),
+                left: fill(left),
+                right: fill(right),
             },
             Expression::Subtract { left, right } => Expression::Subtract {
-                left: Rc::new(left.replace_with(expression.clone())),
-                right: Rc::new(right.replace_with(expression.clone())),
+                left: fill(left),
+                right: fill(right),
+            },
+            Expression::IntegerDivide { left, right } => Expression::IntegerDivide {
+                left: fill(left),
+                right: fill(right),
+            },
+            Expression::Modulo { left, right } => Expression::Modulo {
+                left: fill(left),
+                right: fill(right),
+            },
+            Expression::Power { left, right } => Expression::Power {
+                left: fill(left),
+                right: fill(right),
             },
             Expression::If {
                 condition,
                 then_branch,
                 else_branch,
-            } => {
-                let else_branch = Rc::new(else_branch.replace_with(expression.clone()));
-                Expression::If {
-                    condition: Rc::new(condition.replace_with(expression.clone())),
-                    then_branch: Rc::new(then_branch.replace_with(expression.clone())),
-                    else_branch,
-                }
-            }
+            } => Expression::If {
+                condition: fill(condition),
+                then_branch: fill(then_branch),
+                else_branch: fill(else_branch),
+            },
             Expression::Compare {
                 left,
                 operand,
                 right,
             } => Expression::Compare {
-                left: Rc::new(left.replace_with(expression.clone())),
+                left: fill(left),
                 operand: operand.clone(),
-                right: Rc::new(right.replace_with(expression.clone())),
+                right: fill(right),
             },
-            Expression::Let { variables, scope } => {
-                let variables = variables
-                    .into_iter()
-                    .map(|(name, expr)| {
-                        (name.clone(), Rc::new(expr.replace_with(expression.clone())))
-                    })
-                    .collect();
-                Expression::Let {
-                    variables,
-                    scope: Rc::new(scope.replace_with(expression.clone())),
-                }
-            }
-            Expression::Use { variable } => Expression::Use {
-                variable: variable.clone(),
+            Expression::Bitwise {
+                left,
+                operand,
+                right,
+            } => Expression::Bitwise {
+                left: fill(left),
+                operand: operand.clone(),
+                right: fill(right),
+            },
+            Expression::Let { variables, scope } => Expression::Let {
+                variables: variables
+                    .iter()
+                    .map(|(pattern, value)| (pattern.clone(), fill(value)))
+                    .collect(),
+                scope: fill(scope),
             },
             Expression::Unary { operand, right } => Expression::Unary {
                 operand: operand.clone(),
-                right: Rc::new(right.replace_with(expression.clone())),
+                right: fill(right),
             },
             Expression::Assign { variable, value } => Expression::Assign {
                 variable: variable.clone(),
-                value: Rc::new(value.replace_with(expression.clone())),
+                value: fill(value),
             },
             Expression::For {
                 variable,
@@ -163,25 +326,184 @@ impl Expression {
                 body,
             } => Expression::For {
                 variable: variable.clone(),
-                from: Rc::new(from.replace_with(expression.clone())),
-                to: Rc::new(to.replace_with(expression.clone())),
-                body: Rc::new(body.replace_with(expression.clone())),
+                from: fill(from),
+                to: fill(to),
+                body: fill(body),
             },
             Expression::Print { expression: expr } => Expression::Print {
-                expression: Rc::new(expr.replace_with(expression.clone())),
-            },
-            Expression::Chain { left, right } => {
-                left.replace_with(expression.clone());
-                right.replace_with(expression.clone());
-                Expression::Chain {
-                    left: left.clone(),
-                    right: right.clone(),
+                expression: fill(expr),
+            },
+            Expression::Chain { left, right } => Expression::Chain {
+                left: fill(left),
+                right: fill(right),
+            },
+            Expression::Line { line, statement } => Expression::Line {
+                line: *line,
+                statement: fill(statement),
+            },
+            Expression::Block { body } => Expression::Block { body: fill(body) },
+            Expression::Function { function, scope } => Expression::Function {
+                function: Rc::new(Function {
+                    body: fill(&function.body),
+                    ..(**function).clone()
+                }),
+                scope: fill(scope),
+            },
+            Expression::Call {
+                function,
+                definition,
+                arguments,
+            } => Expression::Call {
+                function: function.clone(),
+                definition: *definition,
+                arguments: fill_all(arguments),
+            },
+            Expression::Tuple { elements } => Expression::Tuple {
+                elements: fill_all(elements),
+            },
+            Expression::Index { tuple, index } => Expression::Index {
+                tuple: fill(tuple),
+                index: *index,
+            },
+            Expression::Declare { declaration, scope } => Expression::Declare {
+                declaration: declaration.clone(),
+                scope: fill(scope),
+            },
+            Expression::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => Expression::Construct {
+                declaration: declaration.clone(),
+                constructor: *constructor,
+                arguments: fill_all(arguments),
+            },
+            Expression::Field { value, field } => Expression::Field {
+                value: fill(value),
+                field: field.clone(),
+            },
+            Expression::Match { value, arms } => Expression::Match {
+                value: fill(value),
+                arms: arms
+                    .iter()
+                    .map(|(pattern, body)| (pattern.clone(), fill(body)))
+                    .collect(),
+            },
+        }
+    }
+}
+
+// Dropping a tree node by node would take a stack frame for every level of
+// it, and a long script nests a level for every line. A node instead hands
+// the children only it holds to a list, which it drops one at a time.
+impl Drop for Expression {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        self.release(&mut pending);
+        while let Some(child) = pending.pop() {
+            if let Ok(mut child) = Rc::try_unwrap(child) {
+                child.release(&mut pending);
+            }
+        }
+    }
+}
+
+impl Expression {
+    // Moves the children of the node out to `pending`.
+    fn release(&mut self, pending: &mut Vec<ExpressionRef>) {
+        let mut take = |child: &mut ExpressionRef| {
+            if Rc::strong_count(child) == 1 {
+                pending.push(std::mem::replace(child, Rc::new(Expression::None {})));
+            }
+        };
+        match self {
+            Expression::Primitive(_) | Expression::Use { .. } | Expression::None {} => {}
+            Expression::Expression(inner)
+            | Expression::Unary { right: inner, .. }
+            | Expression::Assign { value: inner, .. }
+            | Expression::Print { expression: inner }
+            | Expression::Line {
+                statement: inner, ..
+            }
+            | Expression::Block { body: inner }
+            | Expression::Index { tuple: inner, .. }
+            | Expression::Declare { scope: inner, .. }
+            | Expression::Field { value: inner, .. } => take(inner),
+            Expression::Sum { left, right }
+            | Expression::Product { left, right }
+            | Expression::Divide { left, right }
+            | Expression::Subtract { left, right }
+            | Expression::IntegerDivide { left, right }
+            | Expression::Modulo { left, right }
+            | Expression::Power { left, right }
+            | Expression::Compare { left, right, .. }
+            | Expression::Bitwise { left, right, .. }
+            | Expression::Chain { left, right } => {
+                take(left);
+                take(right);
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                take(condition);
+                take(then_branch);
+                take(else_branch);
+            }
+            Expression::For { from, to, body, .. } => {
+                take(from);
+                take(to);
+                take(body);
+            }
+            Expression::Let { variables, scope } => {
+                variables.iter_mut().for_each(|(_, value)| take(value));
+                take(scope);
+            }
+            Expression::Function { function, scope } => {
+                if let Some(function) = Rc::get_mut(function) {
+                    take(&mut function.body);
                 }
+                take(scope);
+            }
+            Expression::Call { arguments, .. }
+            | Expression::Tuple {
+                elements: arguments,
+            }
+            | Expression::Construct { arguments, .. } => arguments.iter_mut().for_each(take),
+            Expression::Match { value, arms } => {
+                take(value);
+                arms.iter_mut().for_each(|(_, body)| take(body));
             }
-            Expression::DeriveState { expression: expr } => Expression::DeriveState {
-                expression: Rc::new(expr.replace_with(expression.clone())),
-            },
-            Expression::None {} => Expression::None {},
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_fills_every_hole() {
+        let hole = || Rc::new(Expression::Primitive(Primitive::ToReplace));
+        let tree = Expression::Chain {
+            left: Rc::new(Expression::Print { expression: hole() }),
+            right: Rc::new(Expression::Tuple {
+                elements: vec![hole(), Rc::new(Expression::None {})],
+            }),
+        };
+        let filled = || Rc::new(Expression::Primitive(Primitive::Integer(1)));
+
+        assert_eq!(
+            tree.replace_with(Expression::Primitive(Primitive::Integer(1))),
+            Expression::Chain {
+                left: Rc::new(Expression::Print {
+                    expression: filled()
+                }),
+                right: Rc::new(Expression::Tuple {
+                    elements: vec![filled(), Rc::new(Expression::None {})],
+                }),
+            }
+        );
+    }
+}
@@ -0,0 +1,963 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::stdlib;
+
+use super::{
+    checking::Checker,
+    expression::{Declaration, Expression, Function, Operand, Pattern, Variable},
+    resolution::Resolved,
+    stack,
+    types::Type,
+    values::{Error, Value},
+};
+
+// What an operator asks of a type it cannot tell yet. Sum also takes
+// strings; every other arithmetic operator only takes numbers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Class {
+    Numeric,
+    Addable,
+}
+
+impl Class {
+    fn admits(&self, admitted: &Type) -> bool {
+        match self {
+            Class::Numeric => matches!(admitted, Type::Int | Type::Float),
+            Class::Addable => matches!(admitted, Type::Int | Type::Float | Type::Str),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Class::Numeric => "num",
+            Class::Addable => "num | str",
+        }
+    }
+}
+
+// A type variable is either still free, possibly restricted to a class, or
+// bound to a type. Either way it remembers the line that constrained it, so
+// a conflict can point at it.
+#[derive(Debug, PartialEq, Clone)]
+enum State {
+    Free(Option<(Class, usize)>),
+    Bound(Type, usize),
+}
+
+// The type of a function, generic over `variables`. A function whose body is
+// still being inferred has none, so its recursive calls share its types.
+// `deferred` holds the indexes its body takes of a tuple whose type depends
+// on its arguments, which each call checks once its arguments are known.
+#[derive(Debug, PartialEq, Clone)]
+struct Scheme {
+    variables: Vec<usize>,
+    parameters: Vec<Type>,
+    returns: Type,
+    deferred: Vec<Deferred>,
+}
+
+// An index taken on `line` of a tuple whose type was still a variable, to be
+// checked once the variable is bound.
+#[derive(Debug, PartialEq, Clone)]
+struct Deferred {
+    tuple: Type,
+    index: usize,
+    element: Type,
+    line: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Entry {
+    // A binding, with the function it is declared in if any.
+    Binding(String, Type, Option<usize>),
+    Function(usize),
+}
+
+// Infers the type of every binding and function of a resolved tree without
+// annotations, in the way of Hindley–Milner: each unknown type becomes a
+// variable, operations unify the types they relate, and functions are
+// generalized over the variables left free once their body is inferred, so
+// each call instantiates them anew. Functions are inferred on their first
+// call, or in order of definition if never called.
+//
+// Type parameters are opaque within a generic function, which is then
+// generalized over them too. Each construction of a generic struct or enum
+// instantiates its parameters anew.
+//
+// Where two numbers of different kinds meet, the result is a float as at
+// run time; an operand whose type is not known yet takes the type of the
+// other instead. Powers and globals read from a function body are Unknown,
+// which unifies with anything, and so are the parameters and results of
+// stdlib calls their builtin's signature leaves Unknown. Indexing a tuple
+// whose type is not known yet is checked once it is; reading a field needs
+// the type to be known by then, unless a single struct has a field of that
+// name.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Inferencer {
+    variables: Vec<State>,
+    // The type of each variable of the current frame by slot, with the line
+    // it is declared on.
+    types: Vec<(Type, usize)>,
+    line: usize,
+    functions: HashMap<usize, Rc<Function>>,
+    schemes: HashMap<usize, Scheme>,
+    inferring: Vec<usize>,
+    deferred: Vec<Deferred>,
+    declarations: HashMap<String, Rc<Declaration>>,
+    // The type parameters of the function being inferred.
+    generics: Vec<String>,
+    entries: Vec<(usize, Entry)>,
+}
+
+impl Inferencer {
+    pub fn new() -> Inferencer {
+        Inferencer::default()
+    }
+
+    // The signature of every binding and function, by the line declaring it.
+    pub fn infer(&mut self, resolved: &Resolved) -> Result<Vec<(usize, String)>, Error> {
+        for declaration in &resolved.declarations {
+            self.declarations
+                .insert(declaration.name.clone(), declaration.clone());
+        }
+        for declaration in &resolved.declarations {
+            for constructor in &declaration.constructors {
+                for (_, field) in &constructor.fields {
+                    Checker::known(field, &declaration.parameters, &self.declarations)?;
+                }
+            }
+        }
+        for function in &resolved.functions {
+            self.functions
+                .insert(function.definition.unwrap_or_default(), function.clone());
+        }
+        for function in &resolved.functions {
+            self.function(function.definition.unwrap_or_default())?;
+        }
+        self.expression(&resolved.expression)?;
+        self.settle()?;
+
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.sort_by_key(|(line, _)| *line);
+        Ok(entries
+            .into_iter()
+            .map(|(line, entry)| (line, self.signature(entry)))
+            .collect())
+    }
+
+    fn function(&mut self, definition: usize) -> Result<(), Error> {
+        if self.schemes.contains_key(&definition) {
+            return Ok(());
+        }
+        let function = self.functions[&definition].clone();
+
+        let caller = std::mem::take(&mut self.types);
+        let line = std::mem::replace(&mut self.line, function.line);
+        let generics = std::mem::replace(&mut self.generics, function.generics.clone());
+        self.inferring.push(definition);
+        let body = self.body(definition, &function);
+        self.inferring.pop();
+        self.types = caller;
+        self.line = line;
+        self.generics = generics;
+        body?;
+
+        if !function.generics.is_empty() {
+            let parameters: HashMap<String, Type> = function
+                .generics
+                .iter()
+                .map(|generic| (generic.clone(), self.fresh()))
+                .collect();
+            let scheme = self.schemes[&definition].clone();
+            let scheme = Scheme {
+                variables: Vec::new(),
+                parameters: scheme
+                    .parameters
+                    .iter()
+                    .map(|parameter| Inferencer::parameterize(&self.zonk(parameter), &parameters))
+                    .collect(),
+                returns: Inferencer::parameterize(&self.zonk(&scheme.returns), &parameters),
+                deferred: Vec::new(),
+            };
+            self.schemes.insert(definition, scheme);
+        }
+
+        let mut bound = Vec::new();
+        for inferring in self.inferring.clone() {
+            let scheme = self.schemes[&inferring].clone();
+            for t in scheme.parameters.iter().chain([&scheme.returns]) {
+                self.free(t, &mut bound);
+            }
+        }
+        let mut variables = self.generics(definition);
+        variables.retain(|variable| !bound.contains(variable));
+        // The indexes of a tuple the function is generic over are checked by
+        // each call instead, along with whatever else their elements bring.
+        let (generic, deferred): (Vec<Deferred>, Vec<Deferred>) =
+            std::mem::take(&mut self.deferred)
+                .into_iter()
+                .partition(|deferred| match self.resolve(&deferred.tuple).0 {
+                    Type::Variable(variable) => variables.contains(&variable),
+                    _ => false,
+                });
+        self.deferred = deferred;
+        for deferred in &generic {
+            let mut element = Vec::new();
+            self.free(&deferred.element, &mut element);
+            for variable in element {
+                if !bound.contains(&variable) && !variables.contains(&variable) {
+                    variables.push(variable);
+                }
+            }
+        }
+        let scheme = self.schemes.get_mut(&definition).unwrap();
+        scheme.variables = variables;
+        scheme.deferred = generic;
+        self.entries
+            .push((function.line, Entry::Function(definition)));
+        Ok(())
+    }
+
+    fn body(&mut self, definition: usize, function: &Function) -> Result<(), Error> {
+        let mut parameters = Vec::new();
+        for parameter in &function.parameters {
+            let parameter_type = self.declared(&parameter.annotation)?;
+            self.declare(parameter, parameter_type.clone());
+            parameters.push(parameter_type);
+        }
+        let returns = self.declared(&function.returns)?;
+        self.schemes.insert(
+            definition,
+            Scheme {
+                variables: Vec::new(),
+                parameters,
+                returns: returns.clone(),
+                deferred: Vec::new(),
+            },
+        );
+
+        let body = self.expression(&function.body)?;
+        let returns = self.at(returns, function.line);
+        self.unify(&returns, &body)?;
+        self.settle()
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<Type, Error> {
+        stack::grow(|| self.node(expression))
+    }
+
+    fn node(&mut self, expression: &Expression) -> Result<Type, Error> {
+        match expression {
+            Expression::Primitive(primitive) => Ok(Type::of(primitive)),
+            Expression::Expression(inner) => self.expression(inner),
+            Expression::Sum { left, right } => {
+                self.arithmetic(left, right, Class::Addable, Type::Int)
+            }
+            Expression::Product { left, right }
+            | Expression::Subtract { left, right }
+            | Expression::IntegerDivide { left, right }
+            | Expression::Modulo { left, right } => {
+                self.arithmetic(left, right, Class::Numeric, Type::Int)
+            }
+            Expression::Divide { left, right } => {
+                self.arithmetic(left, right, Class::Numeric, Type::Float)
+            }
+            Expression::Power { left, right } => {
+                self.arithmetic(left, right, Class::Numeric, Type::Unknown)
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expression(condition)?;
+                self.unify(&Type::Bool, &condition)?;
+                let then_type = self.expression(then_branch)?;
+                let else_type = self.expression(else_branch)?;
+                self.unify(&then_type, &else_type)?;
+                Ok(then_type)
+            }
+            Expression::Compare { left, right, .. } => {
+                self.arithmetic(left, right, Class::Numeric, Type::Int)?;
+                Ok(Type::Bool)
+            }
+            Expression::Bitwise { left, right, .. } => {
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                self.unify(&Type::Int, &left)?;
+                self.unify(&Type::Int, &right)?;
+                Ok(Type::Int)
+            }
+            Expression::Unary { operand, right } => {
+                let right = self.expression(right)?;
+                match operand {
+                    Operand::Negate => {
+                        self.constrain(&right, Class::Numeric, self.line)?;
+                        Ok(right)
+                    }
+                    Operand::Not => {
+                        if !matches!(self.resolve(&right).0, Type::Int | Type::Unknown) {
+                            self.unify(&Type::Bool, &right)?;
+                        }
+                        Ok(Type::Bool)
+                    }
+                    _ => {
+                        self.unify(&Type::Int, &right)?;
+                        Ok(Type::Int)
+                    }
+                }
+            }
+            Expression::Let { variables, scope } => {
+                for (pattern, value) in variables {
+                    let value = self.expression(value)?;
+                    self.pattern(pattern, value)?;
+                }
+                self.expression(scope)
+            }
+            Expression::Use { variable } => Ok(match variable.slot {
+                Some(_) if variable.global => Type::Unknown,
+                Some(slot) => self.variable(slot),
+                None => match stdlib::constant(&variable.name) {
+                    Some(Value::Float(_)) => Type::Float,
+                    _ => Type::Unknown,
+                },
+            }),
+            Expression::Assign { variable, value } => {
+                let value = self.expression(value)?;
+                if let Some(slot) = variable.slot {
+                    let declared = self.variable(slot);
+                    self.unify(&declared, &value)?;
+                }
+                Ok(Type::Unit)
+            }
+            Expression::For {
+                variable,
+                from,
+                to,
+                body,
+            } => {
+                let (from, to) = (self.expression(from)?, self.expression(to)?);
+                self.unify(&Type::Int, &from)?;
+                self.unify(&Type::Int, &to)?;
+                self.declare(variable, Type::Int);
+                self.expression(body)?;
+                Ok(Type::Unit)
+            }
+            Expression::Print { expression } => {
+                self.expression(expression)?;
+                Ok(Type::Unit)
+            }
+            Expression::Chain { left, right } => {
+                self.expression(left)?;
+                self.expression(right)
+            }
+            Expression::Line { line, statement } => {
+                self.line = *line;
+                let statement = self.expression(statement)?;
+                self.settle()?;
+                Ok(statement)
+            }
+            Expression::Block { body } => self.expression(body),
+            Expression::Function { scope, .. } => self.expression(scope),
+            Expression::Call {
+                function,
+                definition,
+                arguments,
+            } => {
+                let mut types = Vec::new();
+                for argument in arguments {
+                    types.push(self.expression(argument)?);
+                }
+                let definition = match definition {
+                    Some(definition) => *definition,
+                    None => {
+                        let Some((parameters, returns)) = stdlib::signature(function) else {
+                            return Ok(Type::Unknown);
+                        };
+                        let parameters = self.at(Type::Tuple(parameters), self.line);
+                        self.unify(&parameters, &Type::Tuple(types))?;
+                        return Ok(self.at(returns, self.line));
+                    }
+                };
+                self.function(definition)?;
+                let scheme = self.instantiate(&self.schemes[&definition].clone());
+                let parameters = self.at(
+                    Type::Tuple(scheme.parameters),
+                    self.functions[&definition].line,
+                );
+                self.unify(&parameters, &Type::Tuple(types))?;
+                Ok(scheme.returns)
+            }
+            Expression::Tuple { elements } => {
+                let mut types = Vec::new();
+                for element in elements {
+                    types.push(self.expression(element)?);
+                }
+                Ok(Type::Tuple(types))
+            }
+            Expression::Index { tuple, index } => {
+                let tuple = self.expression(tuple)?;
+                if let Type::Variable(_) = self.resolve(&tuple).0 {
+                    let element = self.fresh();
+                    self.deferred.push(Deferred {
+                        tuple,
+                        index: *index,
+                        element: element.clone(),
+                        line: self.line,
+                    });
+                    return Ok(element);
+                }
+                self.index(&tuple, *index, self.line)
+            }
+            Expression::Declare { scope, .. } => self.expression(scope),
+            Expression::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => {
+                let mut types = Vec::new();
+                for argument in arguments {
+                    types.push(self.expression(argument)?);
+                }
+                let (constructed, fields) = self.instance(declaration, *constructor);
+                self.unify(&Type::Tuple(fields), &Type::Tuple(types))?;
+                Ok(constructed)
+            }
+            Expression::Field { value, field } => {
+                let value = self.expression(value)?;
+                let declaration = match self.resolve(&value).0 {
+                    Type::Unknown => return Ok(Type::Unknown),
+                    Type::Named(name, _) => self.declarations.get(&name).cloned(),
+                    Type::Variable(_) => {
+                        let mut declaring = self.declarations.values().filter(|declaration| {
+                            !declaration.enumeration
+                                && declaration.constructors[0]
+                                    .fields
+                                    .iter()
+                                    .any(|(name, _)| name == field)
+                        });
+                        match (declaring.next(), declaring.next()) {
+                            (Some(declaration), None) => Some(declaration.clone()),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                let declaration = match declaration {
+                    Some(declaration) if !declaration.enumeration => declaration,
+                    _ => return Err(Error::TypeMismatch(field.clone(), vec![self.zonk(&value)])),
+                };
+
+                let (constructed, fields) = self.instance(&declaration, 0);
+                self.unify(&constructed, &value)?;
+                let names = declaration.constructors[0].fields.iter();
+                match names.zip(fields).find(|((name, _), _)| name == field) {
+                    Some((_, field)) => Ok(field),
+                    None => Err(Error::TypeMismatch(field.clone(), vec![self.zonk(&value)])),
+                }
+            }
+            Expression::Match { value, arms } => {
+                let value = self.expression(value)?;
+                let matched = self.fresh();
+                for (pattern, body) in arms {
+                    self.pattern(pattern, value.clone())?;
+                    let body = self.expression(body)?;
+                    self.unify(&matched, &body)?;
+                }
+                Ok(matched)
+            }
+            Expression::None {} => Ok(self.fresh()),
+        }
+    }
+
+    // A struct or enum type with fresh variables for its parameters, along
+    // with the types of the fields of one of its constructors.
+    fn instance(&mut self, declaration: &Declaration, constructor: usize) -> (Type, Vec<Type>) {
+        let arguments: Vec<Type> = declaration.parameters.iter().map(|_| self.fresh()).collect();
+        let parameters: HashMap<String, Type> = declaration
+            .parameters
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect();
+        let fields = declaration.constructors[constructor]
+            .fields
+            .iter()
+            .map(|(_, field)| Inferencer::parameterize(field, &parameters))
+            .collect();
+        (Type::Named(declaration.name.clone(), arguments), fields)
+    }
+
+    // Replaces the type parameters within a type by their types.
+    fn parameterize(parameterized: &Type, parameters: &HashMap<String, Type>) -> Type {
+        match parameterized {
+            Type::Named(name, arguments) if arguments.is_empty() && parameters.contains_key(name) => {
+                parameters[name].clone()
+            }
+            Type::Named(name, arguments) => Type::Named(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| Inferencer::parameterize(argument, parameters))
+                    .collect(),
+            ),
+            Type::List(element) => Type::List(Box::new(Inferencer::parameterize(element, parameters))),
+            Type::Tuple(elements) => Type::Tuple(
+                elements
+                    .iter()
+                    .map(|element| Inferencer::parameterize(element, parameters))
+                    .collect(),
+            ),
+            _ => parameterized.clone(),
+        }
+    }
+
+    // Numbers make the type Type::arithmetic gives; types yet to be worked
+    // out have to be the same on both sides.
+    fn arithmetic(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+        class: Class,
+        integers: Type,
+    ) -> Result<Type, Error> {
+        let (left, right) = (self.expression(left)?, self.expression(right)?);
+        self.constrain(&left, class, self.line)?;
+        self.constrain(&right, class, self.line)?;
+        match Type::arithmetic(&self.resolve(&left).0, &self.resolve(&right).0, integers.clone()) {
+            Some(result) => Ok(result),
+            None => {
+                self.unify(&left, &right)?;
+                Ok(if integers == Type::Int {
+                    left
+                } else {
+                    integers
+                })
+            }
+        }
+    }
+
+    // The variables of a pattern matched against Unknown are Unknown too.
+    fn pattern(&mut self, pattern: &Pattern, value: Type) -> Result<(), Error> {
+        let unknown = self.resolve(&value).0 == Type::Unknown;
+        match pattern {
+            Pattern::Variable(variable) => {
+                if variable.annotation.is_some() {
+                    let declared = self.declared(&variable.annotation)?;
+                    self.unify(&declared, &value)?;
+                }
+                self.entries.push((
+                    self.line,
+                    Entry::Binding(
+                        variable.name.clone(),
+                        value.clone(),
+                        self.inferring.last().copied(),
+                    ),
+                ));
+                self.declare(variable, value);
+            }
+            Pattern::Literal(literal) => self.unify(&Type::of(literal), &value)?,
+            Pattern::Tuple(elements) => {
+                let types: Vec<Type> = if unknown {
+                    vec![Type::Unknown; elements.len()]
+                } else {
+                    elements.iter().map(|_| self.fresh()).collect()
+                };
+                self.unify(&Type::Tuple(types.clone()), &value)?;
+                for (element, element_type) in elements.iter().zip(types) {
+                    self.pattern(element, element_type)?;
+                }
+            }
+            Pattern::List { elements, rest } => {
+                let element_type = if unknown { Type::Unknown } else { self.fresh() };
+                let list = Type::List(Box::new(element_type.clone()));
+                self.unify(&list, &value)?;
+                for element in elements {
+                    self.pattern(element, element_type.clone())?;
+                }
+                if let Some(rest) = rest {
+                    self.pattern(&Pattern::Variable(rest.clone()), list)?;
+                }
+            }
+            Pattern::Constructor { name, elements } => {
+                let found = self.declarations.values().find_map(|declaration| {
+                    let constructors = declaration.constructors.iter();
+                    constructors
+                        .enumerate()
+                        .find(|(_, constructor)| constructor.name == *name)
+                        .map(|(index, constructor)| (declaration.clone(), index, constructor.fields.len()))
+                });
+                let (declaration, constructor) = match found {
+                    Some((declaration, constructor, fields)) if fields == elements.len() => {
+                        (declaration, constructor)
+                    }
+                    _ => return Err(Error::TypeMismatch(name.clone(), vec![self.zonk(&value)])),
+                };
+                let (constructed, fields) = self.instance(&declaration, constructor);
+                self.unify(&constructed, &value)?;
+                for (element, field) in elements.iter().zip(fields) {
+                    let field = if unknown { Type::Unknown } else { field };
+                    self.pattern(element, field)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn declare(&mut self, variable: &Variable, declared: Type) {
+        if let Some(slot) = variable.slot {
+            if self.types.len() <= slot {
+                self.types.resize(slot + 1, (Type::Unknown, 0));
+            }
+            self.types[slot] = (declared, self.line);
+        }
+    }
+
+    // The type of a variable in use, tracing back to its declaration.
+    fn variable(&mut self, slot: usize) -> Type {
+        match self.types.get(slot).cloned() {
+            Some((declared, line)) => self.at(declared, line),
+            None => Type::Unknown,
+        }
+    }
+
+    // The type an annotation stands for; no annotation stands for a fresh
+    // variable.
+    fn declared(&mut self, annotation: &Option<Type>) -> Result<Type, Error> {
+        match annotation {
+            None => Ok(self.fresh()),
+            Some(annotation) => Checker::known(annotation, &self.generics, &self.declarations)
+                .map(|_| annotation.clone()),
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.variables.push(State::Free(None));
+        Type::Variable(self.variables.len() - 1)
+    }
+
+    // `known` as a variable that came to be at `line`.
+    fn at(&mut self, known: Type, line: usize) -> Type {
+        if known == Type::Unknown {
+            return known;
+        }
+        self.variables.push(State::Bound(known, line));
+        Type::Variable(self.variables.len() - 1)
+    }
+
+    // Instantiates the variables of a scheme anew, and defers the indexes it
+    // holds on the new ones.
+    fn instantiate(&mut self, scheme: &Scheme) -> Scheme {
+        let mut instances = HashMap::new();
+        for variable in &scheme.variables {
+            self.variables.push(self.variables[*variable].clone());
+            instances.insert(*variable, Type::Variable(self.variables.len() - 1));
+        }
+        for deferred in &scheme.deferred {
+            let deferred = Deferred {
+                tuple: self.substitute(&deferred.tuple, &instances),
+                element: self.substitute(&deferred.element, &instances),
+                ..deferred.clone()
+            };
+            self.deferred.push(deferred);
+        }
+        Scheme {
+            variables: Vec::new(),
+            parameters: scheme
+                .parameters
+                .iter()
+                .map(|parameter| self.substitute(parameter, &instances))
+                .collect(),
+            returns: self.substitute(&scheme.returns, &instances),
+            deferred: Vec::new(),
+        }
+    }
+
+    // The type of an element of a tuple, indexed on `line`.
+    fn index(&mut self, tuple: &Type, index: usize, line: usize) -> Result<Type, Error> {
+        match self.resolve(tuple) {
+            (Type::Tuple(types), _) if index < types.len() => Ok(types[index].clone()),
+            (Type::Unknown, _) => Ok(Type::Unknown),
+            (tuple, tuple_line) => Err(Error::TypeConflict(
+                Box::new((Type::Tuple(vec![Type::Unknown; index + 1]), line)),
+                Box::new((self.zonk(&tuple), tuple_line)),
+            )),
+        }
+    }
+
+    // Checks the deferred indexes of every tuple whose type is now known.
+    fn settle(&mut self) -> Result<(), Error> {
+        loop {
+            let deferred = std::mem::take(&mut self.deferred);
+            let count = deferred.len();
+            for deferred in deferred {
+                if let Type::Variable(_) = self.resolve(&deferred.tuple).0 {
+                    self.deferred.push(deferred);
+                    continue;
+                }
+                let element = self.index(&deferred.tuple, deferred.index, deferred.line)?;
+                self.unify(&deferred.element, &element)?;
+            }
+            if self.deferred.len() == count {
+                return Ok(());
+            }
+        }
+    }
+
+    // Replaces variables by their instances. A bound variable is only
+    // replaced by the type it is bound to where that type mentions one of
+    // them, so the rest still trace back to the line that bound them.
+    fn substitute(&self, substituted: &Type, instances: &HashMap<usize, Type>) -> Type {
+        match substituted {
+            Type::Variable(variable) => match (instances.get(variable), &self.variables[*variable])
+            {
+                (Some(instance), _) => instance.clone(),
+                (None, State::Bound(bound, _)) => {
+                    let mut within = Vec::new();
+                    self.free(bound, &mut within);
+                    if within
+                        .iter()
+                        .any(|variable| instances.contains_key(variable))
+                    {
+                        self.substitute(bound, instances)
+                    } else {
+                        substituted.clone()
+                    }
+                }
+                (None, State::Free(_)) => substituted.clone(),
+            },
+            Type::List(element) => Type::List(Box::new(self.substitute(element, instances))),
+            Type::Tuple(elements) => Type::Tuple(
+                elements
+                    .iter()
+                    .map(|element| self.substitute(element, instances))
+                    .collect(),
+            ),
+            Type::Named(name, arguments) => Type::Named(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| self.substitute(argument, instances))
+                    .collect(),
+            ),
+            _ => substituted.clone(),
+        }
+    }
+
+    // Follows bound variables down to a type that is not one, along with the
+    // line that bound the last of them, or the current line if none did.
+    fn resolve(&self, resolved: &Type) -> (Type, usize) {
+        let (mut resolved, mut line) = (resolved.clone(), self.line);
+        while let Type::Variable(variable) = resolved {
+            match &self.variables[variable] {
+                State::Bound(bound, bound_line) => {
+                    resolved = bound.clone();
+                    line = *bound_line;
+                }
+                State::Free(_) => break,
+            }
+        }
+        (resolved, line)
+    }
+
+    // Resolves every variable within a type.
+    fn zonk(&self, zonked: &Type) -> Type {
+        match self.resolve(zonked).0 {
+            Type::List(element) => Type::List(Box::new(self.zonk(&element))),
+            Type::Tuple(elements) => {
+                Type::Tuple(elements.iter().map(|element| self.zonk(element)).collect())
+            }
+            Type::Named(name, arguments) => Type::Named(
+                name,
+                arguments.iter().map(|argument| self.zonk(argument)).collect(),
+            ),
+            zonked => zonked,
+        }
+    }
+
+    fn free(&self, within: &Type, variables: &mut Vec<usize>) {
+        match self.zonk(within) {
+            Type::Variable(variable) if !variables.contains(&variable) => variables.push(variable),
+            Type::List(element) => self.free(&element, variables),
+            Type::Tuple(elements) | Type::Named(_, elements) => {
+                for element in &elements {
+                    self.free(element, variables);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn unify(&mut self, expected: &Type, found: &Type) -> Result<(), Error> {
+        let (expected, expected_line) = self.resolve(expected);
+        let (found, found_line) = self.resolve(found);
+        match (&expected, &found) {
+            (Type::Unknown, _) | (_, Type::Unknown) => Ok(()),
+            (Type::Variable(expected), Type::Variable(found)) if expected == found => Ok(()),
+            (Type::Variable(variable), _) => self.bind(*variable, found, found_line),
+            (_, Type::Variable(variable)) => self.bind(*variable, expected, expected_line),
+            (Type::List(expected_element), Type::List(found_element)) => {
+                let expected_element = self.at(expected_element.as_ref().clone(), expected_line);
+                let found_element = self.at(found_element.as_ref().clone(), found_line);
+                self.unify(&expected_element, &found_element)
+            }
+            (Type::Tuple(expected_elements), Type::Tuple(found_elements))
+                if expected_elements.len() == found_elements.len() =>
+            {
+                self.elements(expected_elements, expected_line, found_elements, found_line)
+            }
+            (
+                Type::Named(expected_name, expected_arguments),
+                Type::Named(found_name, found_arguments),
+            ) if expected_name == found_name
+                && expected_arguments.len() == found_arguments.len() =>
+            {
+                self.elements(expected_arguments, expected_line, found_arguments, found_line)
+            }
+            _ if expected == found => Ok(()),
+            _ => Err(Error::TypeConflict(
+                Box::new((self.zonk(&expected), expected_line)),
+                Box::new((self.zonk(&found), found_line)),
+            )),
+        }
+    }
+
+    fn elements(
+        &mut self,
+        expected: &[Type],
+        expected_line: usize,
+        found: &[Type],
+        found_line: usize,
+    ) -> Result<(), Error> {
+        for (expected_element, found_element) in expected.iter().zip(found) {
+            let expected_element = self.at(expected_element.clone(), expected_line);
+            let found_element = self.at(found_element.clone(), found_line);
+            self.unify(&expected_element, &found_element)?;
+        }
+        Ok(())
+    }
+
+    fn bind(&mut self, variable: usize, bound: Type, line: usize) -> Result<(), Error> {
+        let mut within = Vec::new();
+        self.free(&bound, &mut within);
+        if within.contains(&variable) {
+            return Err(Error::TypeConflict(
+                Box::new((Type::Variable(variable), self.line)),
+                Box::new((self.zonk(&bound), line)),
+            ));
+        }
+        if let State::Free(Some((class, class_line))) = self.variables[variable] {
+            self.constrain(&bound, class, class_line)?;
+        }
+        self.variables[variable] = State::Bound(bound, line);
+        Ok(())
+    }
+
+    // Restricts a type to a class, as asked at `line`: a free variable is
+    // restricted from now on, any other type must already belong to it.
+    fn constrain(&mut self, constrained: &Type, class: Class, line: usize) -> Result<(), Error> {
+        let (constrained, constrained_line) = self.resolve(constrained);
+        match constrained {
+            Type::Variable(variable) => {
+                if let State::Free(current) = &mut self.variables[variable] {
+                    match current {
+                        None => *current = Some((class, line)),
+                        Some((Class::Addable, _)) if class == Class::Numeric => {
+                            *current = Some((class, line))
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(())
+            }
+            Type::Unknown => Ok(()),
+            _ if class.admits(&constrained) => Ok(()),
+            _ => Err(Error::TypeConflict(
+                Box::new((Type::Named(class.name().to_string(), Vec::new()), line)),
+                Box::new((self.zonk(&constrained), constrained_line)),
+            )),
+        }
+    }
+
+    // The free variables of a function's type, in order of appearance.
+    fn generics(&self, definition: usize) -> Vec<usize> {
+        let scheme = &self.schemes[&definition];
+        let mut variables = Vec::new();
+        for t in scheme.parameters.iter().chain([&scheme.returns]) {
+            self.free(t, &mut variables);
+        }
+        variables
+    }
+
+    // Writes out the types of an entry, naming its free variables T, U, ...
+    // in order of appearance. Bindings in a function name the variables of
+    // the function as its signature does.
+    fn signature(&self, entry: Entry) -> String {
+        match entry {
+            Entry::Binding(name, bound, function) => {
+                let mut variables = match function {
+                    Some(definition) => self.generics(definition),
+                    None => Vec::new(),
+                };
+                self.free(&bound, &mut variables);
+                let names = Inferencer::names(&variables);
+                format!("let {}: {}", name, self.named(&bound, &names))
+            }
+            Entry::Function(definition) => {
+                let function = &self.functions[&definition];
+                let scheme = &self.schemes[&definition];
+                let variables = self.generics(definition);
+                let names = Inferencer::names(&variables);
+
+                let generics: Vec<String> = variables
+                    .iter()
+                    .filter(|variable| scheme.variables.contains(variable))
+                    .map(|variable| match &self.variables[*variable] {
+                        State::Free(Some((class, _))) => {
+                            format!("{}: {}", names[variable], class.name())
+                        }
+                        _ => names[variable].to_string(),
+                    })
+                    .collect();
+                let parameters: Vec<String> = function
+                    .parameters
+                    .iter()
+                    .zip(&scheme.parameters)
+                    .map(|(parameter, parameter_type)| {
+                        format!("{}: {}", parameter.name, self.named(parameter_type, &names))
+                    })
+                    .collect();
+
+                format!(
+                    "fn {}{}({}) -> {}",
+                    function.name,
+                    match generics.as_slice() {
+                        [] => String::new(),
+                        _ => format!("<{}>", generics.join(", ")),
+                    },
+                    parameters.join(", "),
+                    self.named(&scheme.returns, &names)
+                )
+            }
+        }
+    }
+
+    fn named(&self, named: &Type, names: &HashMap<usize, Type>) -> Type {
+        self.substitute(&self.zonk(named), names)
+    }
+
+    fn names(variables: &[usize]) -> HashMap<usize, Type> {
+        const LETTERS: &str = "TUVWXYZABCDEFGHIJKLMNOPQRS";
+        variables
+            .iter()
+            .enumerate()
+            .map(|(index, variable)| {
+                let letter = LETTERS.chars().nth(index % LETTERS.len()).unwrap();
+                let name = match index / LETTERS.len() {
+                    0 => letter.to_string(),
+                    round => format!("{}{}", letter, round),
+                };
+                (*variable, Type::Named(name, Vec::new()))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    io::{self, BufRead, Read},
+    rc::Rc,
+};
+
+// Where `input` and `read_all` read from. A line comes without its line
+// ending, and there is no line left at the end of the input.
+pub trait Input {
+    fn read_line(&mut self) -> Option<String>;
+    fn read_all(&mut self) -> String;
+}
+
+// Reads from stdin.
+pub struct Stdin;
+
+impl Input for Stdin {
+    fn read_line(&mut self) -> Option<String> {
+        read_line(&mut io::stdin().lock())
+    }
+
+    fn read_all(&mut self) -> String {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).ok();
+        text
+    }
+}
+
+// Reads from any buffered reader, such as a file or, for tests and hosts to
+// script the input, text in memory:
+//
+//     let input = InputRef::new(Reader::new(Cursor::new("first\nsecond\n")));
+pub struct Reader<R>(R);
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(reader: R) -> Reader<R> {
+        Reader(reader)
+    }
+}
+
+impl<R: BufRead> Input for Reader<R> {
+    fn read_line(&mut self) -> Option<String> {
+        read_line(&mut self.0)
+    }
+
+    fn read_all(&mut self) -> String {
+        let mut text = String::new();
+        self.0.read_to_string(&mut text).ok();
+        text
+    }
+}
+
+// A read that fails ends the input like its end does.
+fn read_line(reader: &mut impl BufRead) -> Option<String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => {
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            Some(line)
+        }
+    }
+}
+
+// The Input of the options, shared by their clones.
+#[derive(Clone)]
+pub struct InputRef(Rc<RefCell<dyn Input>>);
+
+impl InputRef {
+    pub fn new(input: impl Input + 'static) -> InputRef {
+        InputRef(Rc::new(RefCell::new(input)))
+    }
+
+    pub fn read_line(&self) -> Option<String> {
+        self.0.borrow_mut().read_line()
+    }
+
+    pub fn read_all(&self) -> String {
+        self.0.borrow_mut().read_all()
+    }
+}
+
+impl<T: Input + 'static> From<Rc<RefCell<T>>> for InputRef {
+    fn from(input: Rc<RefCell<T>>) -> Self {
+        InputRef(input)
+    }
+}
+
+impl Default for InputRef {
+    fn default() -> Self {
+        InputRef::new(Stdin)
+    }
+}
+
+impl fmt::Debug for InputRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InputRef")
+    }
+}
+
+impl PartialEq for InputRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn it_reads_lines_without_their_endings() {
+        let input = InputRef::new(Reader::new(Cursor::new("first\r\nsecond\nlast")));
+
+        assert_eq!(input.read_line(), Some("first".to_string()));
+        assert_eq!(input.clone().read_line(), Some("second".to_string()));
+        assert_eq!(input.read_line(), Some("last".to_string()));
+        assert_eq!(input.read_line(), None);
+    }
+
+    #[test]
+    fn it_reads_all_that_is_left() {
+        let input = InputRef::new(Reader::new(Cursor::new("first\nsecond\nthird\n")));
+
+        input.read_line();
+
+        assert_eq!(input.read_all(), "second\nthird\n");
+        assert_eq!(input.read_all(), "");
+        assert_eq!(input.read_line(), None);
+    }
+}
@@ -1,23 +1,92 @@
-use std::collections::HashMap;
-
-use super::{evaluation::evaluate, expression::ExpressionRef, values::Value};
+use super::{
+    checking::Checker,
+    compilation::compile,
+    environment::Environment,
+    evaluation::evaluate,
+    execution::execute,
+    expression::ExpressionRef,
+    optimization::optimize,
+    options::{Backend, Options},
+    resolution::Resolver,
+    values::{Error, Value},
+};
 
 pub fn interpret(expr: ExpressionRef) -> Value {
-    match evaluate(&expr, &None) {
+    interpret_with_options(
+        expr,
+        &mut Resolver::new(),
+        &mut Checker::new(),
+        &mut Environment::new(),
+        &Options::default(),
+    )
+}
+
+pub fn interpret_with_state(
+    expr: ExpressionRef,
+    resolver: &mut Resolver,
+    checker: &mut Checker,
+    environment: &mut Environment,
+) -> Value {
+    interpret_with_options(expr, resolver, checker, environment, &Options::default())
+}
+
+// Resolves and type checks the whole tree before running any of it, so
+// undefined variables and type mismatches are reported without side effects
+// and unused variables are flagged up front. The checked tree is optimized
+// before it runs. Errors are reported to the output of the options, and the
+// value is then None.
+pub fn interpret_with_options(
+    expr: ExpressionRef,
+    resolver: &mut Resolver,
+    checker: &mut Checker,
+    environment: &mut Environment,
+    options: &Options,
+) -> Value {
+    match try_interpret(expr, resolver, checker, environment, options) {
         Ok(value) => value,
         Err(err) => {
-            println!("{:?}", err);
+            options.output.diagnose(&format!("{:?}", err));
             Value::None
         }
     }
 }
 
-pub fn interpret_with_state(expr: ExpressionRef, state: Option<HashMap<String, ExpressionRef>>) -> Value {
-    match evaluate(&expr, &state) {
-        Ok(value) => value,
-        Err(err) => {
-            println!("{:?}", err);
-            Value::None
-        }
+// Interprets like `interpret_with_options`, but returns errors instead of
+// reporting them. Warnings are still reported.
+pub fn try_interpret(
+    expr: ExpressionRef,
+    resolver: &mut Resolver,
+    checker: &mut Checker,
+    environment: &mut Environment,
+    options: &Options,
+) -> Result<Value, Error> {
+    let resolved = resolver.resolve(&expr)?;
+    for warning in &resolved.warnings {
+        options.output.diagnose(&format!("{:?}", warning));
+    }
+    if let Err(err) = checker.check(&resolved) {
+        resolver.forget(environment.len());
+        return Err(err);
+    }
+    let resolved = optimize(resolved, options);
+    if options.dump_tree {
+        options
+            .output
+            .diagnose(&format!("{:#?}", resolved.expression));
+    }
+    for function in &resolved.functions {
+        environment.define(function.clone());
+    }
+
+    environment.restart();
+    let result = match options.engine {
+        Backend::Tree => evaluate(&resolved.expression, environment, options),
+        #[cfg(feature = "jit")]
+        Backend::Jit => evaluate(&resolved.expression, environment, options),
+        Backend::Bytecode => execute(&compile(&resolved), environment, options),
+    };
+    if result.is_err() {
+        resolver.forget(environment.len());
     }
+    result
 }
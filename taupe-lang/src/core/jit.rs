@@ -0,0 +1,1130 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::atomic::AtomicBool};
+
+use cranelift_codegen::ir::{
+    self, condcodes::IntCC, types, AbiParam, Block, InstBuilder, MemFlags, Signature,
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, FuncId, Module};
+
+use super::{
+    environment::Environment,
+    expression::{Expression, ExpressionRef, Function, Operand, Pattern},
+    options::{Options, Overflow},
+    primitives::Primitive,
+    stack,
+    types::Type,
+    values::Value,
+};
+
+// Compiles integer-only code to native code with Cranelift, for the Jit
+// engine: functions whose arguments are all integers or booleans, and `for`
+// loops over variables that are. Anything else, such as printing, strings,
+// a call to the stdlib or reading a global from a function body, keeps the
+// whole function or loop in the evaluator.
+//
+// Native code never fails. When a result would overflow, unless the overflow
+// option wraps, when a division is by zero, when a call would nest deeper
+// than the options allow or run short of stack, or when the run is
+// interrupted, it gives up instead, and the evaluator runs the function or
+// loop again from the start. Since native code has no side effects, and a
+// loop only writes its variables back once it is done, running again is the
+// same as never having started.
+//
+// Code is compiled the first time a function is called with arguments of the
+// same kinds, or a loop runs over variables of the same kinds, and kept for
+// as long as anything else holds what it was compiled from.
+pub(crate) fn call(
+    function: &Rc<Function>,
+    arguments: &[Value],
+    environment: &Environment,
+    options: &Options,
+) -> Option<Value> {
+    if !native(options) {
+        return None;
+    }
+    let mut values = Vec::new();
+    let mut kinds = Vec::new();
+    for argument in arguments {
+        let (kind, value) = Kind::of(argument)?;
+        kinds.push(kind);
+        values.push(value);
+    }
+    let wrapping = options.overflow == Overflow::Wrapping;
+    let native = CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .function(function, kinds, wrapping, environment)
+    })?;
+    native.run(&mut values, environment, options)
+}
+
+// Runs the loop `for <slot> in [from..=to] { body }` natively, writing back
+// the variables below the loop's slot once it is done.
+pub(crate) fn repeat(
+    slot: usize,
+    from: i64,
+    to: i64,
+    body: &ExpressionRef,
+    environment: &mut Environment,
+    options: &Options,
+) -> Option<Value> {
+    if !native(options) {
+        return None;
+    }
+    let outer: Vec<_> = (0..slot)
+        .map(|slot| environment.get(slot).and_then(Kind::of))
+        .collect();
+    let kinds: Vec<_> = outer
+        .iter()
+        .map(|bound| bound.map(|(kind, _)| kind))
+        .collect();
+    let mut values = vec![from, to];
+    values.extend(
+        outer
+            .iter()
+            .map(|bound| bound.map_or(0, |(_, value)| value)),
+    );
+
+    let wrapping = options.overflow == Overflow::Wrapping;
+    let native = CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .repeat(slot, body, kinds.clone(), wrapping, environment)
+    })?;
+    native.run(&mut values, environment, options)?;
+    for (slot, kind) in kinds.iter().enumerate() {
+        if let Some(kind) = kind {
+            environment.assign(slot, kind.value(values[slot + 2]));
+        }
+    }
+    Some(Value::Unit)
+}
+
+// Native code takes no steps to count, so it cannot run under a limit of
+// steps or time.
+fn native(options: &Options) -> bool {
+    options.max_steps.is_none() && options.timeout.is_none()
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+// What a value is to native code, where every value is an i64. Booleans are
+// zero or one; the other kinds are never read.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Kind {
+    Int,
+    Bool,
+    Unit,
+    Nothing,
+    // The value of an `if` whose branches differ.
+    Mixed,
+}
+
+impl Kind {
+    fn of(value: &Value) -> Option<(Kind, i64)> {
+        match value {
+            Value::Integer(value) => Some((Kind::Int, *value)),
+            Value::Boolean(value) => Some((Kind::Bool, *value as i64)),
+            _ => None,
+        }
+    }
+
+    fn value(self, bits: i64) -> Value {
+        match self {
+            Kind::Int => Value::Integer(bits),
+            Kind::Bool => Value::Boolean(bits != 0),
+            Kind::Unit => Value::Unit,
+            Kind::Nothing | Kind::Mixed => Value::None,
+        }
+    }
+
+    fn bound(self) -> bool {
+        matches!(self, Kind::Int | Kind::Bool)
+    }
+}
+
+// Compiled code, called with its State and the values it works on.
+#[derive(Clone, Copy)]
+struct Native {
+    code: *const u8,
+    returns: Kind,
+}
+
+// What native code shares with its caller: a flag it sets when it gives up,
+// how many more calls it may nest, the lowest address its stack may reach,
+// and the flag of the Interrupt of the options.
+#[repr(C)]
+struct State {
+    failed: u8,
+    calls: i64,
+    limit: usize,
+    interrupt: *const AtomicBool,
+}
+
+const CALLS: i32 = std::mem::offset_of!(State, calls) as i32;
+const LIMIT: i32 = std::mem::offset_of!(State, limit) as i32;
+const INTERRUPT: i32 = std::mem::offset_of!(State, interrupt) as i32;
+
+type Entry = unsafe extern "C" fn(*mut State, *mut i64) -> i64;
+
+impl Native {
+    fn run(
+        self,
+        values: &mut [i64],
+        environment: &Environment,
+        options: &Options,
+    ) -> Option<Value> {
+        let calls = options.max_depth.saturating_sub(environment.depth());
+        let mut state = State {
+            failed: 0,
+            calls: i64::try_from(calls).unwrap_or(i64::MAX),
+            limit: stack::limit()?,
+            interrupt: options.interrupt.flag(),
+        };
+        // SAFETY: `code` was compiled with the signature of an Entry, by a
+        // module the cache keeps alive.
+        let result = unsafe {
+            let entry = std::mem::transmute::<*const u8, Entry>(self.code);
+            entry(&mut state, values.as_mut_ptr())
+        };
+        match state.failed {
+            0 => Some(self.returns.value(result)),
+            _ => None,
+        }
+    }
+}
+
+type FunctionKey = (*const Function, Vec<Kind>, bool);
+type LoopKey = (*const Expression, Vec<Option<Kind>>, bool);
+
+// How many entries the cache holds before it first looks for entries to drop.
+const SWEEP: usize = 64;
+
+// Compiled functions and loops, or None for those that cannot be compiled,
+// keyed by what they were compiled from. Each keeps that alive, so its
+// address is never reused by something else, until the cache is the last to
+// hold it: nothing can run the code anymore, and the entry is dropped along
+// with its module. The cache looks for such entries whenever it has doubled
+// since it last did, so a REPL or an Engine evaluating tree after tree keeps
+// only the code of trees still in use.
+struct Cache {
+    functions: HashMap<FunctionKey, Cached<Function>>,
+    loops: HashMap<LoopKey, Cached<Expression>>,
+    swept: usize,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache {
+            functions: HashMap::new(),
+            loops: HashMap::new(),
+            swept: SWEEP / 2,
+        }
+    }
+}
+
+struct Cached<T> {
+    source: Rc<T>,
+    native: Option<Native>,
+    module: Option<JITModule>,
+}
+
+impl<T> Cached<T> {
+    // Whether anything but the cache still holds what the code was compiled
+    // from. The module of code that nothing holds anymore is freed.
+    fn held(&mut self) -> bool {
+        if Rc::strong_count(&self.source) > 1 {
+            return true;
+        }
+        if let Some(module) = self.module.take() {
+            // SAFETY: native code only runs while its caller holds what it
+            // was compiled from, and nothing does anymore.
+            unsafe { module.free_memory() };
+        }
+        false
+    }
+}
+
+impl Cache {
+    fn function(
+        &mut self,
+        function: &Rc<Function>,
+        kinds: Vec<Kind>,
+        wrapping: bool,
+        environment: &Environment,
+    ) -> Option<Native> {
+        let key = (Rc::as_ptr(function), kinds, wrapping);
+        if let Some(cached) = self.functions.get(&key) {
+            return cached.native;
+        }
+        self.sweep();
+        let compiled = Cache::compile(environment, wrapping, |session| {
+            let (id, returns) = session.function(function, &key.1)?;
+            Some((session.entry(id, key.1.len())?, returns))
+        });
+        let native = compiled.as_ref().map(|(native, _)| *native);
+        self.functions.insert(
+            key,
+            Cached {
+                source: function.clone(),
+                native,
+                module: compiled.map(|(_, module)| module),
+            },
+        );
+        native
+    }
+
+    fn repeat(
+        &mut self,
+        slot: usize,
+        body: &ExpressionRef,
+        kinds: Vec<Option<Kind>>,
+        wrapping: bool,
+        environment: &Environment,
+    ) -> Option<Native> {
+        let key = (Rc::as_ptr(body), kinds, wrapping);
+        if let Some(cached) = self.loops.get(&key) {
+            return cached.native;
+        }
+        self.sweep();
+        let compiled = Cache::compile(environment, wrapping, |session| {
+            Some((session.repeat(slot, body, &key.1)?, Kind::Unit))
+        });
+        let native = compiled.as_ref().map(|(native, _)| *native);
+        self.loops.insert(
+            key,
+            Cached {
+                source: body.clone(),
+                native,
+                module: compiled.map(|(_, module)| module),
+            },
+        );
+        native
+    }
+
+    fn len(&self) -> usize {
+        self.functions.len() + self.loops.len()
+    }
+
+    fn sweep(&mut self) {
+        if self.len() < self.swept * 2 {
+            return;
+        }
+        self.functions.retain(|_, cached| cached.held());
+        self.loops.retain(|_, cached| cached.held());
+        self.swept = self.len().max(SWEEP / 2);
+    }
+
+    // Compiles an entry, along with everything it calls, in a module of its
+    // own, which is thrown away when anything cannot be compiled.
+    fn compile(
+        environment: &Environment,
+        wrapping: bool,
+        entry: impl FnOnce(&mut Session) -> Option<(FuncId, Kind)>,
+    ) -> Option<(Native, JITModule)> {
+        let builder = JITBuilder::new(default_libcall_names()).ok()?;
+        let mut session = Session {
+            module: JITModule::new(builder),
+            environment,
+            wrapping,
+            functions: HashMap::new(),
+        };
+        let compiled = entry(&mut session);
+        let mut module = session.module;
+        let (id, returns) = match compiled {
+            Some(compiled) if module.finalize_definitions().is_ok() => compiled,
+            _ => {
+                // SAFETY: nothing was handed out of the module.
+                unsafe { module.free_memory() };
+                return None;
+            }
+        };
+        let code = module.get_finalized_function(id);
+        Some((Native { code, returns }, module))
+    }
+}
+
+struct Session<'a> {
+    module: JITModule,
+    environment: &'a Environment,
+    wrapping: bool,
+    // The functions compiled so far, by definition and the kinds of their
+    // arguments, with the kind they return.
+    functions: HashMap<(usize, Vec<Kind>), (FuncId, Kind)>,
+}
+
+impl Session<'_> {
+    // Compiles a function taking the State and its arguments, returning its
+    // value. A function returns the integer or boolean it is annotated
+    // with, or an integer.
+    fn function(&mut self, function: &Function, kinds: &[Kind]) -> Option<(FuncId, Kind)> {
+        let key = (function.definition?, kinds.to_vec());
+        if let Some(compiled) = self.functions.get(&key) {
+            return Some(*compiled);
+        }
+        let returns = match function.returns {
+            None | Some(Type::Int) => Kind::Int,
+            Some(Type::Bool) => Kind::Bool,
+            _ => return None,
+        };
+        if function.parameters.len() != kinds.len() {
+            return None;
+        }
+
+        let mut signature = self.signature();
+        for _ in kinds {
+            signature.params.push(AbiParam::new(types::I64));
+        }
+        let id = self.module.declare_anonymous_function(&signature).ok()?;
+        // Declared before its body, for calls to itself.
+        self.functions.insert(key, (id, returns));
+
+        let mut context = self.module.make_context();
+        context.func.signature = signature;
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut translator = Translator::new(
+            self,
+            FunctionBuilder::new(&mut context.func, &mut builder_context),
+        );
+        let parameters = translator.parameters.clone();
+        for ((parameter, kind), value) in
+            function.parameters.iter().zip(kinds).zip(&parameters[1..])
+        {
+            translator.declare(parameter.slot?, *kind, *value);
+        }
+        translator.enter();
+        let body = translator.expression(&function.body)?;
+        if body.kind != returns {
+            return None;
+        }
+        translator.leave();
+        translator.builder.ins().return_(&[body.value]);
+        translator.finish();
+
+        self.module.define_function(id, &mut context).ok()?;
+        Some((id, returns))
+    }
+
+    // Compiles an entry that loads the arguments of a function from memory
+    // and calls it.
+    fn entry(&mut self, function: FuncId, arguments: usize) -> Option<FuncId> {
+        let signature = self.entry_signature();
+        let id = self.module.declare_anonymous_function(&signature).ok()?;
+        let mut context = self.module.make_context();
+        context.func.signature = signature;
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut translator = Translator::new(
+            self,
+            FunctionBuilder::new(&mut context.func, &mut builder_context),
+        );
+        let parameters = translator.parameters.clone();
+        let mut values = vec![parameters[0]];
+        for argument in 0..arguments {
+            values.push(translator.load(parameters[1], argument));
+        }
+        let reference = translator
+            .session
+            .module
+            .declare_func_in_func(function, translator.builder.func);
+        let call = translator.builder.ins().call(reference, &values);
+        let result = translator.builder.inst_results(call)[0];
+        translator.builder.ins().return_(&[result]);
+        translator.finish();
+
+        self.module.define_function(id, &mut context).ok()?;
+        Some(id)
+    }
+
+    // Compiles a loop taking the State and its values in memory: its bounds,
+    // then the variables below its slot, which it writes back when it is
+    // done. Variables that are not integers or booleans cannot be used.
+    fn repeat(&mut self, slot: usize, body: &Expression, kinds: &[Option<Kind>]) -> Option<FuncId> {
+        let signature = self.entry_signature();
+        let id = self.module.declare_anonymous_function(&signature).ok()?;
+        let mut context = self.module.make_context();
+        context.func.signature = signature;
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut translator = Translator::new(
+            self,
+            FunctionBuilder::new(&mut context.func, &mut builder_context),
+        );
+        let values = translator.parameters[1];
+        let from = translator.load(values, 0);
+        let to = translator.load(values, 1);
+        translator.kinds = vec![None; kinds.len()];
+        for (outer, kind) in kinds.iter().enumerate() {
+            if let Some(kind) = kind {
+                let value = translator.load(values, outer + 2);
+                translator.declare(outer, *kind, value);
+            }
+        }
+        translator.repeat(slot, from, to, body)?;
+        for (outer, kind) in kinds.iter().enumerate() {
+            if kind.is_some() {
+                let variable = translator.variable(outer);
+                let value = translator.builder.use_var(variable);
+                translator.store(values, outer + 2, value);
+            }
+        }
+        let zero = translator.builder.ins().iconst(types::I64, 0);
+        translator.builder.ins().return_(&[zero]);
+        translator.finish();
+
+        self.module.define_function(id, &mut context).ok()?;
+        Some(id)
+    }
+
+    // The State, and the values of a function.
+    fn signature(&self) -> Signature {
+        let mut signature = self.module.make_signature();
+        let pointer = self.module.target_config().pointer_type();
+        signature.params.push(AbiParam::new(pointer));
+        signature.returns.push(AbiParam::new(types::I64));
+        signature
+    }
+
+    // The State, and the values an entry loads and stores.
+    fn entry_signature(&self) -> Signature {
+        let mut signature = self.signature();
+        let pointer = self.module.target_config().pointer_type();
+        signature.params.push(AbiParam::new(pointer));
+        signature
+    }
+}
+
+struct Typed {
+    kind: Kind,
+    value: ir::Value,
+}
+
+enum Arithmetic {
+    Sum,
+    Subtract,
+    Product,
+}
+
+// Translates one function of a session, slot by slot: every slot of the
+// function has a variable of its own, along with the kind of value it holds
+// in the scope being translated.
+struct Translator<'s, 'a, 'b> {
+    session: &'s mut Session<'a>,
+    builder: FunctionBuilder<'b>,
+    // Where the State is, and the block that sets its flag and gives up.
+    state: ir::Value,
+    failed: Block,
+    parameters: Vec<ir::Value>,
+    variables: Vec<Option<Variable>>,
+    kinds: Vec<Option<Kind>>,
+    declared: usize,
+}
+
+impl<'s, 'a, 'b> Translator<'s, 'a, 'b> {
+    fn new(session: &'s mut Session<'a>, mut builder: FunctionBuilder<'b>) -> Self {
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let parameters = builder.block_params(entry).to_vec();
+        let failed = builder.create_block();
+        Translator {
+            session,
+            builder,
+            state: parameters[0],
+            failed,
+            parameters,
+            variables: Vec::new(),
+            kinds: Vec::new(),
+            declared: 0,
+        }
+    }
+
+    fn finish(mut self) {
+        self.builder.switch_to_block(self.failed);
+        let one = self.builder.ins().iconst(types::I8, 1);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), one, self.state, 0);
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().return_(&[zero]);
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Option<Typed> {
+        stack::grow(|| self.node(expression))
+    }
+
+    fn node(&mut self, expression: &Expression) -> Option<Typed> {
+        let typed = match expression {
+            Expression::Primitive(Primitive::Integer(value)) => Typed {
+                kind: Kind::Int,
+                value: self.builder.ins().iconst(types::I64, *value),
+            },
+            Expression::Primitive(Primitive::Boolean(value)) => Typed {
+                kind: Kind::Bool,
+                value: self.builder.ins().iconst(types::I64, *value as i64),
+            },
+            Expression::None {} => self.nothing(Kind::Nothing),
+            Expression::Expression(inner)
+            | Expression::Line {
+                statement: inner, ..
+            }
+            | Expression::Function { scope: inner, .. }
+            | Expression::Declare { scope: inner, .. } => return self.expression(inner),
+            Expression::Sum { left, right } => self.arithmetic(Arithmetic::Sum, left, right)?,
+            Expression::Subtract { left, right } => {
+                self.arithmetic(Arithmetic::Subtract, left, right)?
+            }
+            Expression::Product { left, right } => {
+                self.arithmetic(Arithmetic::Product, left, right)?
+            }
+            Expression::IntegerDivide { left, right } => self.division(left, right, false)?,
+            Expression::Modulo { left, right } => self.division(left, right, true)?,
+            Expression::Compare {
+                left,
+                operand,
+                right,
+            } => {
+                let condition = match operand {
+                    Operand::Equals => IntCC::Equal,
+                    Operand::LessThan => IntCC::SignedLessThan,
+                    Operand::GreaterThan => IntCC::SignedGreaterThan,
+                    _ => return None,
+                };
+                let left = self.integer(left)?;
+                let right = self.integer(right)?;
+                let compared = self.builder.ins().icmp(condition, left, right);
+                self.boolean(compared)
+            }
+            Expression::Bitwise {
+                left,
+                operand,
+                right,
+            } => {
+                let left = self.integer(left)?;
+                let right = self.integer(right)?;
+                let value = match operand {
+                    Operand::BitAnd => self.builder.ins().band(left, right),
+                    Operand::BitOr => self.builder.ins().bor(left, right),
+                    Operand::BitXor => self.builder.ins().bxor(left, right),
+                    _ => return None,
+                };
+                Typed {
+                    kind: Kind::Int,
+                    value,
+                }
+            }
+            Expression::Unary { operand, right } => self.unary(operand, right)?,
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => self.branch(condition, then_branch, else_branch)?,
+            Expression::Let { variables, scope } => {
+                for (pattern, value) in variables {
+                    let slot = match pattern {
+                        Pattern::Variable(variable) => variable.slot?,
+                        _ => return None,
+                    };
+                    let value = self.expression(value)?;
+                    if !value.kind.bound() {
+                        return None;
+                    }
+                    self.declare(slot, value.kind, value.value);
+                }
+                return self.expression(scope);
+            }
+            Expression::Use { variable } => {
+                if variable.global {
+                    return None;
+                }
+                let slot = variable.slot?;
+                let kind = (*self.kinds.get(slot)?)?;
+                let variable = self.variable(slot);
+                Typed {
+                    kind,
+                    value: self.builder.use_var(variable),
+                }
+            }
+            Expression::Assign { variable, value } => {
+                let slot = variable.slot?;
+                let value = self.expression(value)?;
+                if *self.kinds.get(slot)? != Some(value.kind) {
+                    return None;
+                }
+                let variable = self.variable(slot);
+                self.builder.def_var(variable, value.value);
+                self.nothing(Kind::Unit)
+            }
+            Expression::For {
+                variable,
+                from,
+                to,
+                body,
+            } => {
+                let from = self.integer(from)?;
+                let to = self.integer(to)?;
+                self.repeat(variable.slot?, from, to, body)?;
+                self.nothing(Kind::Unit)
+            }
+            Expression::Chain { left, right } => {
+                self.expression(left)?;
+                return self.expression(right);
+            }
+            Expression::Block { body } => return self.scoped(body),
+            Expression::Call {
+                definition: Some(definition),
+                arguments,
+                ..
+            } => self.call(*definition, arguments)?,
+            _ => return None,
+        };
+        Some(typed)
+    }
+
+    fn integer(&mut self, expression: &Expression) -> Option<ir::Value> {
+        let typed = self.expression(expression)?;
+        match typed.kind {
+            Kind::Int => Some(typed.value),
+            _ => None,
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        operator: Arithmetic,
+        left: &Expression,
+        right: &Expression,
+    ) -> Option<Typed> {
+        let left = self.integer(left)?;
+        let right = self.integer(right)?;
+        let ins = self.builder.ins();
+        let value = if self.session.wrapping {
+            match operator {
+                Arithmetic::Sum => ins.iadd(left, right),
+                Arithmetic::Subtract => ins.isub(left, right),
+                Arithmetic::Product => ins.imul(left, right),
+            }
+        } else {
+            let (value, overflow) = match operator {
+                Arithmetic::Sum => ins.sadd_overflow(left, right),
+                Arithmetic::Subtract => ins.ssub_overflow(left, right),
+                Arithmetic::Product => ins.smul_overflow(left, right),
+            };
+            self.fail_if(overflow);
+            value
+        };
+        Some(Typed {
+            kind: Kind::Int,
+            value,
+        })
+    }
+
+    // Floored division, or the modulo that goes with it. The evaluator
+    // handles a division by zero, and the one division that overflows.
+    fn division(&mut self, left: &Expression, right: &Expression, modulo: bool) -> Option<Typed> {
+        let left = self.integer(left)?;
+        let right = self.integer(right)?;
+        let zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+        self.fail_if(zero);
+        let minimum = self.builder.ins().icmp_imm(IntCC::Equal, left, i64::MIN);
+        let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, right, -1);
+        let overflow = self.builder.ins().band(minimum, minus_one);
+        self.fail_if(overflow);
+
+        let ins = self.builder.ins();
+        let remainder = ins.srem(left, right);
+        let inexact = self.builder.ins().icmp_imm(IntCC::NotEqual, remainder, 0);
+        let signs = if modulo {
+            self.builder.ins().bxor(remainder, right)
+        } else {
+            self.builder.ins().bxor(left, right)
+        };
+        let opposite = self.builder.ins().icmp_imm(IntCC::SignedLessThan, signs, 0);
+        let adjust = self.builder.ins().band(inexact, opposite);
+        let value = if modulo {
+            let zero = self.builder.ins().iconst(types::I64, 0);
+            let correction = self.builder.ins().select(adjust, right, zero);
+            self.builder.ins().iadd(remainder, correction)
+        } else {
+            let quotient = self.builder.ins().sdiv(left, right);
+            let correction = self.builder.ins().uextend(types::I64, adjust);
+            self.builder.ins().isub(quotient, correction)
+        };
+        Some(Typed {
+            kind: Kind::Int,
+            value,
+        })
+    }
+
+    fn unary(&mut self, operand: &Operand, right: &Expression) -> Option<Typed> {
+        let right = self.expression(right)?;
+        let typed = match (operand, right.kind) {
+            (Operand::Negate, Kind::Int) if self.session.wrapping => Typed {
+                kind: Kind::Int,
+                value: self.builder.ins().ineg(right.value),
+            },
+            (Operand::Negate, Kind::Int) => {
+                let zero = self.builder.ins().iconst(types::I64, 0);
+                let (value, overflow) = self.builder.ins().ssub_overflow(zero, right.value);
+                self.fail_if(overflow);
+                Typed {
+                    kind: Kind::Int,
+                    value,
+                }
+            }
+            (Operand::Not, Kind::Int) => {
+                let zero = self.builder.ins().icmp_imm(IntCC::Equal, right.value, 0);
+                self.boolean(zero)
+            }
+            (Operand::Not, Kind::Bool) => Typed {
+                kind: Kind::Bool,
+                value: self.builder.ins().bxor_imm(right.value, 1),
+            },
+            (Operand::Complement, Kind::Int) => Typed {
+                kind: Kind::Int,
+                value: self.builder.ins().bnot(right.value),
+            },
+            _ => return None,
+        };
+        Some(typed)
+    }
+
+    fn branch(
+        &mut self,
+        condition: &Expression,
+        then_branch: &Expression,
+        else_branch: &Expression,
+    ) -> Option<Typed> {
+        let condition = self.expression(condition)?;
+        if condition.kind != Kind::Bool {
+            return None;
+        }
+        let then_block = self.builder.create_block();
+        let else_block = self.builder.create_block();
+        let merge = self.builder.create_block();
+        let value = self.builder.append_block_param(merge, types::I64);
+        self.builder
+            .ins()
+            .brif(condition.value, then_block, &[], else_block, &[]);
+
+        self.builder.switch_to_block(then_block);
+        let then_value = self.scoped(then_branch)?;
+        self.builder.ins().jump(merge, &[then_value.value]);
+        self.builder.switch_to_block(else_block);
+        let else_value = self.scoped(else_branch)?;
+        self.builder.ins().jump(merge, &[else_value.value]);
+        self.builder.switch_to_block(merge);
+
+        let kind = match then_value.kind == else_value.kind {
+            true => then_value.kind,
+            false => Kind::Mixed,
+        };
+        Some(Typed { kind, value })
+    }
+
+    // Counts from `from` up to `to` included, declaring the loop variable in
+    // a new scope on every turn.
+    fn repeat(
+        &mut self,
+        slot: usize,
+        from: ir::Value,
+        to: ir::Value,
+        body: &Expression,
+    ) -> Option<()> {
+        let counter = self.fresh();
+        let last = self.fresh();
+        self.builder.def_var(counter, from);
+        self.builder.def_var(last, to);
+        let start = self.builder.create_block();
+        let step = self.builder.create_block();
+        let exit = self.builder.create_block();
+        let empty = self.builder.ins().icmp(IntCC::SignedGreaterThan, from, to);
+        self.builder.ins().brif(empty, exit, &[], start, &[]);
+
+        self.builder.switch_to_block(start);
+        let height = self.kinds.len();
+        let value = self.builder.use_var(counter);
+        self.declare(slot, Kind::Int, value);
+        self.expression(body)?;
+        self.kinds.truncate(height);
+        let value = self.builder.use_var(counter);
+        let last = self.builder.use_var(last);
+        let done = self.builder.ins().icmp(IntCC::Equal, value, last);
+        self.builder.ins().brif(done, exit, &[], step, &[]);
+
+        self.builder.switch_to_block(step);
+        self.interrupted();
+        let next = self.builder.ins().iadd_imm(value, 1);
+        self.builder.def_var(counter, next);
+        self.builder.ins().jump(start, &[]);
+        self.builder.switch_to_block(exit);
+        Some(())
+    }
+
+    fn call(&mut self, definition: usize, arguments: &[ExpressionRef]) -> Option<Typed> {
+        let function = self.session.environment.function(definition)?;
+        let mut values = vec![self.state];
+        let mut kinds = Vec::new();
+        for argument in arguments {
+            let argument = self.expression(argument)?;
+            if !argument.kind.bound() {
+                return None;
+            }
+            kinds.push(argument.kind);
+            values.push(argument.value);
+        }
+        let (id, returns) = self.session.function(&function, &kinds)?;
+        let reference = self
+            .session
+            .module
+            .declare_func_in_func(id, self.builder.func);
+        let call = self.builder.ins().call(reference, &values);
+        let value = self.builder.inst_results(call)[0];
+        // Gives up as soon as the function did.
+        let failed = self
+            .builder
+            .ins()
+            .load(types::I8, MemFlags::trusted(), self.state, 0);
+        self.fail_if(failed);
+        Some(Typed {
+            kind: returns,
+            value,
+        })
+    }
+
+    // Counts a call against the State, giving up when it would nest one call
+    // too many, leave too little stack, or the run is interrupted.
+    fn enter(&mut self) {
+        let calls = self.load_calls();
+        let exhausted = self.builder.ins().icmp_imm(IntCC::Equal, calls, 0);
+        self.fail_if(exhausted);
+        let pointer = self.session.module.target_config().pointer_type();
+        let limit = self
+            .builder
+            .ins()
+            .load(pointer, MemFlags::trusted(), self.state, LIMIT);
+        let top = self.builder.ins().get_stack_pointer(pointer);
+        let short = self.builder.ins().icmp(IntCC::UnsignedLessThan, top, limit);
+        self.fail_if(short);
+        self.interrupted();
+        let calls = self.builder.ins().iadd_imm(calls, -1);
+        self.store_calls(calls);
+    }
+
+    // Gives up once the run is interrupted, for the evaluator to report it.
+    fn interrupted(&mut self) {
+        let pointer = self.session.module.target_config().pointer_type();
+        let flag = self
+            .builder
+            .ins()
+            .load(pointer, MemFlags::trusted(), self.state, INTERRUPT);
+        let raised = self
+            .builder
+            .ins()
+            .load(types::I8, MemFlags::trusted(), flag, 0);
+        self.fail_if(raised);
+    }
+
+    fn leave(&mut self) {
+        let calls = self.load_calls();
+        let calls = self.builder.ins().iadd_imm(calls, 1);
+        self.store_calls(calls);
+    }
+
+    fn load_calls(&mut self) -> ir::Value {
+        self.builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), self.state, CALLS)
+    }
+
+    fn store_calls(&mut self, calls: ir::Value) {
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), calls, self.state, CALLS);
+    }
+
+    fn scoped(&mut self, body: &Expression) -> Option<Typed> {
+        let height = self.kinds.len();
+        let typed = self.expression(body);
+        self.kinds.truncate(height);
+        typed
+    }
+
+    fn declare(&mut self, slot: usize, kind: Kind, value: ir::Value) {
+        if self.kinds.len() <= slot {
+            self.kinds.resize(slot + 1, None);
+        }
+        self.kinds[slot] = Some(kind);
+        let variable = self.variable(slot);
+        self.builder.def_var(variable, value);
+    }
+
+    fn variable(&mut self, slot: usize) -> Variable {
+        if self.variables.len() <= slot {
+            self.variables.resize(slot + 1, None);
+        }
+        match self.variables[slot] {
+            Some(variable) => variable,
+            None => {
+                let variable = self.fresh();
+                self.variables[slot] = Some(variable);
+                variable
+            }
+        }
+    }
+
+    fn fresh(&mut self) -> Variable {
+        let variable = Variable::from_u32(self.declared as u32);
+        self.declared += 1;
+        self.builder.declare_var(variable, types::I64);
+        variable
+    }
+
+    fn fail_if(&mut self, condition: ir::Value) {
+        let next = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(condition, self.failed, &[], next, &[]);
+        self.builder.switch_to_block(next);
+    }
+
+    fn boolean(&mut self, condition: ir::Value) -> Typed {
+        Typed {
+            kind: Kind::Bool,
+            value: self.builder.ins().uextend(types::I64, condition),
+        }
+    }
+
+    fn nothing(&mut self, kind: Kind) -> Typed {
+        Typed {
+            kind,
+            value: self.builder.ins().iconst(types::I64, 0),
+        }
+    }
+
+    fn load(&mut self, values: ir::Value, index: usize) -> ir::Value {
+        self.builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), values, (index * 8) as i32)
+    }
+
+    fn store(&mut self, values: ir::Value, index: usize, value: ir::Value) {
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), value, values, (index * 8) as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{evaluation::evaluate, options::Backend, resolution::Resolver},
+        parser::Parser,
+        translator::Translator,
+    };
+
+    use super::*;
+
+    fn given_this_function(source: &str) -> (Rc<Function>, Environment) {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+        let mut environment = Environment::new();
+        for function in &resolved.functions {
+            environment.define(function.clone());
+        }
+        (resolved.functions[0].clone(), environment)
+    }
+
+    #[test]
+    fn it_runs_integer_functions_natively() {
+        let (function, environment) = given_this_function(
+            "fn fib(n: int) -> int {\n  if < n 2 { n } else { + fib(- n 1) fib(- n 2) }\n}",
+        );
+
+        assert_eq!(
+            call(
+                &function,
+                &[Value::Integer(30)],
+                &environment,
+                &Options::default()
+            ),
+            Some(Value::Integer(832040))
+        );
+    }
+
+    #[test]
+    fn it_declines_what_it_cannot_compile() {
+        let (function, environment) = given_this_function("fn loud(n) {\n  print n\n  n\n}");
+
+        assert_eq!(
+            call(
+                &function,
+                &[Value::Integer(1)],
+                &environment,
+                &Options::default()
+            ),
+            None
+        );
+        assert_eq!(
+            call(
+                &function,
+                &[Value::Float(1.0)],
+                &environment,
+                &Options::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn it_drops_the_code_of_trees_no_longer_held() {
+        let options = Options {
+            engine: Backend::Jit,
+            ..Options::default()
+        };
+
+        for _ in 0..1000 {
+            let tokens = Parser::new()
+                .quiet()
+                .parse("let mut t = 0\nfor i in [0..=10] {\n  t += i\n}\nt");
+            let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+            assert_eq!(
+                evaluate(&resolved.expression, &mut Environment::new(), &options),
+                Ok(Value::Integer(55))
+            );
+        }
+
+        assert!(CACHE.with(|cache| cache.borrow().len()) <= SWEEP);
+    }
+
+    #[test]
+    fn it_gives_up_on_overflow_unless_wrapping() {
+        let (function, environment) = given_this_function("fn double(n) {\n  * n 2\n}");
+        let wrapping = Options {
+            overflow: Overflow::Wrapping,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            call(
+                &function,
+                &[Value::Integer(i64::MAX)],
+                &environment,
+                &Options::default()
+            ),
+            None
+        );
+        assert_eq!(
+            call(
+                &function,
+                &[Value::Integer(i64::MAX)],
+                &environment,
+                &wrapping
+            ),
+            Some(Value::Integer(-2))
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use super::{
+    options::Options,
+    values::{Error, Value},
+};
+
+// How often, in steps, a run with a timeout looks at the clock.
+const CLOCK: u64 = 1024;
+
+// A flag another thread, or a handler of Ctrl-C, raises to stop the run in
+// progress. The run fails with Interrupted at its next step, lowering the
+// flag again. Clones share their flag.
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    pub fn raise(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn lower(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    // Lowers the flag, returning whether it was raised.
+    pub fn take(&self) -> bool {
+        self.0.load(Ordering::Relaxed) && self.0.swap(false, Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "jit")]
+    pub(crate) fn flag(&self) -> &AtomicBool {
+        &self.0
+    }
+}
+
+impl PartialEq for Interrupt {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// What a run has used of the limits its options set: the steps it took, the
+// bytes of the values it created and when it started. A step is an
+// expression to the evaluator and an instruction to the stack machine.
+// Bytes add up as values are created, whether or not they are still held.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Usage {
+    steps: u64,
+    bytes: usize,
+    started: Option<Instant>,
+}
+
+impl Usage {
+    pub fn step(&mut self, options: &Options) -> Result<(), Error> {
+        if options.interrupt.take() {
+            return Err(Error::Interrupted);
+        }
+        self.steps += 1;
+        if let Some(max_steps) = options.max_steps {
+            if self.steps > max_steps {
+                return Err(Error::StepLimit(max_steps));
+            }
+        }
+        if let Some(timeout) = options.timeout {
+            let started = *self.started.get_or_insert_with(Instant::now);
+            if self.steps.is_multiple_of(CLOCK) && started.elapsed() > timeout {
+                return Err(Error::Timeout(timeout));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn allocate(&mut self, value: &Value, options: &Options) -> Result<(), Error> {
+        if let Some(max_bytes) = options.max_bytes {
+            self.bytes = self.bytes.saturating_add(value.size());
+            if self.bytes > max_bytes {
+                return Err(Error::MemoryLimit(max_bytes));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The most bytes a single operation may build under a timeout, which the
+// clock cannot stop since it is only read between steps: a string, copied in
+// time linear in its length, and a power of integers, whose multiplications
+// take much longer for the same size.
+const TIMED_BYTES: usize = 1 << 24;
+const TIMED_POWER_BYTES: usize = 1 << 16;
+
+// Fails before a value of `bytes` is built when it alone would take more
+// than the maximum bytes of the options, so that building it cannot exhaust
+// memory first, or more than a run with a timeout may build at once, so that
+// building it cannot outlast the timeout. Usage::allocate counts it once it
+// is built.
+pub fn reserve(bytes: usize, options: &Options) -> Result<(), Error> {
+    within(bytes, TIMED_BYTES, options)
+}
+
+// Like reserve, for the result of a power.
+pub fn reserve_power(bytes: usize, options: &Options) -> Result<(), Error> {
+    within(bytes, TIMED_POWER_BYTES, options)
+}
+
+fn within(bytes: usize, timed: usize, options: &Options) -> Result<(), Error> {
+    match (options.max_bytes, options.timeout) {
+        (Some(max_bytes), _) if bytes > max_bytes => Err(Error::MemoryLimit(max_bytes)),
+        (_, Some(timeout)) if bytes > timed => Err(Error::Timeout(timeout)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn it_fails_past_the_maximum_steps() {
+        let options = Options {
+            max_steps: Some(2),
+            ..Options::default()
+        };
+        let mut usage = Usage::default();
+
+        assert_eq!(usage.step(&options), Ok(()));
+        assert_eq!(usage.step(&options), Ok(()));
+        assert_eq!(usage.step(&options), Err(Error::StepLimit(2)));
+    }
+
+    #[test]
+    fn it_adds_up_the_bytes_of_every_value() {
+        let options = Options {
+            max_bytes: Some(100),
+            ..Options::default()
+        };
+        let mut usage = Usage::default();
+        let value = Value::String("x".repeat(60));
+
+        assert_eq!(usage.allocate(&value, &options), Ok(()));
+        assert_eq!(
+            usage.allocate(&value, &options),
+            Err(Error::MemoryLimit(100))
+        );
+    }
+
+    #[test]
+    fn it_stops_once_interrupted() {
+        let options = Options::default();
+        let interrupt = options.interrupt.clone();
+        let mut usage = Usage::default();
+
+        assert_eq!(usage.step(&options), Ok(()));
+        interrupt.raise();
+        assert_eq!(usage.step(&options), Err(Error::Interrupted));
+        assert_eq!(usage.step(&options), Ok(()));
+    }
+
+    #[test]
+    fn it_times_out() {
+        let options = Options {
+            timeout: Some(Duration::ZERO),
+            ..Options::default()
+        };
+        let mut usage = Usage::default();
+
+        let result = (0..CLOCK).try_for_each(|_| usage.step(&options));
+
+        assert_eq!(result, Err(Error::Timeout(Duration::ZERO)));
+    }
+}
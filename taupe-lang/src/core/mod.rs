@@ -1,5 +1,21 @@
+pub mod checking;
+pub mod compilation;
+pub mod encoding;
+pub mod environment;
 pub mod evaluation;
+pub mod execution;
 pub mod expression;
+pub mod inference;
+pub mod input;
 pub mod interpretation;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod limits;
+pub mod optimization;
+pub mod options;
+pub mod output;
 pub mod primitives;
+pub mod resolution;
+pub mod stack;
+pub mod types;
 pub mod values;
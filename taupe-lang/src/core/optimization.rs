@@ -0,0 +1,429 @@
+use std::rc::Rc;
+
+use super::{
+    environment::Environment,
+    evaluation::evaluate,
+    expression::{Expression, ExpressionRef, Function},
+    options::Options,
+    primitives::Primitive,
+    resolution::Resolved,
+    stack,
+    values::Value,
+};
+
+// Simplifies a resolved tree before it runs:
+//
+// - an operator whose operands are all primitives is replaced by its result,
+//   computed with the same options the evaluation will use. An operator that
+//   fails, such as a division by zero, is kept so it fails when it runs, and
+//   so is one whose result would take more than FOLD_BYTES: it is not worth
+//   keeping in the tree, and is left to the limits of the run to build.
+// - an `if` whose condition is a boolean primitive is replaced by the branch
+//   it would take.
+// - `Expression` wrappers are dropped, and so are statements that do nothing.
+//
+// Function bodies are optimized too, and the functions the Environment will
+// define are replaced by their optimized versions.
+
+// The most bytes the value of a folded operator may take, which is checked
+// before a large power or string is built.
+const FOLD_BYTES: usize = 1024;
+
+pub fn optimize(resolved: Resolved, options: &Options) -> Resolved {
+    let mut optimizer = Optimizer {
+        options,
+        functions: Vec::new(),
+    };
+    let expression = optimizer.expression(&resolved.expression);
+    let functions = resolved
+        .functions
+        .iter()
+        .map(|function| {
+            let optimized = optimizer
+                .functions
+                .iter()
+                .find(|optimized| optimized.definition == function.definition);
+            match optimized {
+                Some(optimized) => optimized.clone(),
+                None => optimizer.function(function),
+            }
+        })
+        .collect();
+
+    Resolved {
+        expression,
+        functions,
+        ..resolved
+    }
+}
+
+struct Optimizer<'a> {
+    options: &'a Options,
+    functions: Vec<Rc<Function>>,
+}
+
+impl Optimizer<'_> {
+    fn expression(&mut self, expression: &Expression) -> ExpressionRef {
+        stack::grow(|| self.node(expression))
+    }
+
+    fn node(&mut self, expression: &Expression) -> ExpressionRef {
+        let optimized = match expression {
+            Expression::Primitive(_) | Expression::None {} | Expression::Use { .. } => {
+                expression.clone()
+            }
+            Expression::Expression(inner) => return self.expression(inner),
+            Expression::Sum { left, right } => Optimizer::fold(
+                Expression::Sum {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Product { left, right } => Optimizer::fold(
+                Expression::Product {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Divide { left, right } => Optimizer::fold(
+                Expression::Divide {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Subtract { left, right } => Optimizer::fold(
+                Expression::Subtract {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::IntegerDivide { left, right } => Optimizer::fold(
+                Expression::IntegerDivide {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Modulo { left, right } => Optimizer::fold(
+                Expression::Modulo {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Power { left, right } => Optimizer::fold(
+                Expression::Power {
+                    left: self.expression(left),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Compare {
+                left,
+                operand,
+                right,
+            } => Optimizer::fold(
+                Expression::Compare {
+                    left: self.expression(left),
+                    operand: operand.clone(),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Bitwise {
+                left,
+                operand,
+                right,
+            } => Optimizer::fold(
+                Expression::Bitwise {
+                    left: self.expression(left),
+                    operand: operand.clone(),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::Unary { operand, right } => Optimizer::fold(
+                Expression::Unary {
+                    operand: operand.clone(),
+                    right: self.expression(right),
+                },
+                self.options,
+            ),
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expression(condition);
+                let branch = match condition.as_ref() {
+                    Expression::Primitive(Primitive::Boolean(true)) => then_branch,
+                    Expression::Primitive(Primitive::Boolean(false)) => else_branch,
+                    _ => {
+                        return Rc::new(Expression::If {
+                            condition,
+                            then_branch: self.expression(then_branch),
+                            else_branch: self.expression(else_branch),
+                        })
+                    }
+                };
+                // The branch keeps a scope of its own.
+                return Optimizer::block(self.expression(branch));
+            }
+            Expression::Let { variables, scope } => Expression::Let {
+                variables: variables
+                    .iter()
+                    .map(|(pattern, value)| (pattern.clone(), self.expression(value)))
+                    .collect(),
+                scope: self.expression(scope),
+            },
+            Expression::Assign { variable, value } => Expression::Assign {
+                variable: variable.clone(),
+                value: self.expression(value),
+            },
+            Expression::For {
+                variable,
+                from,
+                to,
+                body,
+            } => Expression::For {
+                variable: variable.clone(),
+                from: self.expression(from),
+                to: self.expression(to),
+                body: self.expression(body),
+            },
+            Expression::Print { expression } => Expression::Print {
+                expression: self.expression(expression),
+            },
+            Expression::Chain { left, right } => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                match left.as_ref() {
+                    Expression::None {} => return right,
+                    _ => Expression::Chain { left, right },
+                }
+            }
+            Expression::Line { line, statement } => {
+                let statement = self.expression(statement);
+                match statement.as_ref() {
+                    Expression::None {} => return statement,
+                    _ => Expression::Line {
+                        line: *line,
+                        statement,
+                    },
+                }
+            }
+            Expression::Block { body } => return Optimizer::block(self.expression(body)),
+            Expression::Function { function, scope } => Expression::Function {
+                function: self.function(function),
+                scope: self.expression(scope),
+            },
+            Expression::Call {
+                function,
+                definition,
+                arguments,
+            } => Expression::Call {
+                function: function.clone(),
+                definition: *definition,
+                arguments: self.arguments(arguments),
+            },
+            Expression::Tuple { elements } => Expression::Tuple {
+                elements: self.arguments(elements),
+            },
+            Expression::Index { tuple, index } => Expression::Index {
+                tuple: self.expression(tuple),
+                index: *index,
+            },
+            Expression::Declare { declaration, scope } => Expression::Declare {
+                declaration: declaration.clone(),
+                scope: self.expression(scope),
+            },
+            Expression::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => Expression::Construct {
+                declaration: declaration.clone(),
+                constructor: *constructor,
+                arguments: self.arguments(arguments),
+            },
+            Expression::Field { value, field } => Expression::Field {
+                value: self.expression(value),
+                field: field.clone(),
+            },
+            Expression::Match { value, arms } => Expression::Match {
+                value: self.expression(value),
+                arms: arms
+                    .iter()
+                    .map(|(pattern, body)| (pattern.clone(), self.expression(body)))
+                    .collect(),
+            },
+        };
+
+        Rc::new(optimized)
+    }
+
+    fn arguments(&mut self, arguments: &[ExpressionRef]) -> Vec<ExpressionRef> {
+        arguments
+            .iter()
+            .map(|argument| self.expression(argument))
+            .collect()
+    }
+
+    fn function(&mut self, function: &Function) -> Rc<Function> {
+        let optimized = Rc::new(Function {
+            body: self.expression(&function.body),
+            ..function.clone()
+        });
+        self.functions.push(optimized.clone());
+        optimized
+    }
+
+    fn block(body: ExpressionRef) -> ExpressionRef {
+        match body.as_ref() {
+            Expression::None {} => body,
+            _ => Rc::new(Expression::Block { body }),
+        }
+    }
+
+    // Replaces an operator by its value when its operands are primitives and
+    // it evaluates without error.
+    fn fold(operator: Expression, options: &Options) -> Expression {
+        let constant = match &operator {
+            Expression::Sum { left, right }
+            | Expression::Product { left, right }
+            | Expression::Divide { left, right }
+            | Expression::Subtract { left, right }
+            | Expression::IntegerDivide { left, right }
+            | Expression::Modulo { left, right }
+            | Expression::Power { left, right }
+            | Expression::Compare { left, right, .. }
+            | Expression::Bitwise { left, right, .. } => {
+                Optimizer::primitive(left) && Optimizer::primitive(right)
+            }
+            Expression::Unary { right, .. } => Optimizer::primitive(right),
+            _ => false,
+        };
+        if !constant {
+            return operator;
+        }
+
+        let options = Options {
+            max_bytes: Some(FOLD_BYTES),
+            ..options.clone()
+        };
+        let value = evaluate(&operator, &mut Environment::new(), &options);
+        let primitive = match value {
+            Ok(Value::Integer(value)) => Primitive::Integer(value),
+            Ok(Value::BigInteger(value)) => Primitive::BigInteger(value),
+            Ok(Value::Float(value)) => Primitive::Float(value),
+            Ok(Value::String(value)) => Primitive::String(value),
+            Ok(Value::Boolean(value)) => Primitive::Boolean(value),
+            _ => return operator,
+        };
+        Expression::Primitive(primitive)
+    }
+
+    fn primitive(expression: &Expression) -> bool {
+        matches!(expression, Expression::Primitive(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{core::resolution::Resolver, parser::Parser, translator::Translator};
+
+    use super::*;
+
+    fn optimized(source: &str) -> ExpressionRef {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+        optimize(resolved, &Options::default()).expression
+    }
+
+    fn line(line: usize, statement: Expression) -> ExpressionRef {
+        Rc::new(Expression::Line {
+            line,
+            statement: Rc::new(statement),
+        })
+    }
+
+    fn print(primitive: Primitive) -> Expression {
+        Expression::Print {
+            expression: Rc::new(Expression::Primitive(primitive)),
+        }
+    }
+
+    #[test]
+    fn it_folds_constant_arithmetic() {
+        assert_eq!(
+            optimized("print + 4 4"),
+            line(1, print(Primitive::Integer(8)))
+        );
+        assert_eq!(
+            optimized("print < * 2 3 / 13 2"),
+            line(1, print(Primitive::Boolean(true)))
+        );
+    }
+
+    #[test]
+    fn it_keeps_operators_that_fail() {
+        assert_eq!(
+            optimized("print / 1 0"),
+            line(
+                1,
+                Expression::Print {
+                    expression: Rc::new(Expression::Divide {
+                        left: Rc::new(Expression::Primitive(Primitive::Integer(1))),
+                        right: Rc::new(Expression::Primitive(Primitive::Integer(0))),
+                    }),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn it_keeps_operators_with_large_results() {
+        let expression = optimized("print ** 3 100000000");
+        let Expression::Line { statement, .. } = expression.as_ref() else {
+            panic!("expected a line, got {:?}", expression);
+        };
+
+        assert!(matches!(
+            statement.as_ref(),
+            Expression::Print { expression } if matches!(expression.as_ref(), Expression::Power { .. })
+        ));
+    }
+
+    #[test]
+    fn it_prunes_branches_of_constant_conditions() {
+        assert_eq!(
+            optimized("if < 1 2 {\n  print \"yes\"\n} else {\n  print \"no\"\n}"),
+            line(
+                1,
+                Expression::Block {
+                    body: line(2, print(Primitive::String("yes".to_string()))),
+                }
+            )
+        );
+        assert_eq!(
+            optimized("if false {\n  print 1\n}\nprint 2"),
+            line(4, print(Primitive::Integer(2)))
+        );
+    }
+
+    #[test]
+    fn it_folds_function_bodies() {
+        let tokens = Parser::new().parse("fn eight() {\n  + 4 4\n}\nprint eight()");
+        let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+        let optimized = optimize(resolved, &Options::default());
+
+        assert_eq!(
+            optimized.functions[0].body,
+            line(2, Expression::Primitive(Primitive::Integer(8)))
+        );
+    }
+}
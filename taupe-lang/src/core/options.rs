@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use super::{input::InputRef, limits::Interrupt, output::OutputRef, values::Value};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Options {
+    pub overflow: Overflow,
+    pub engine: Backend,
+    // Prints the optimized tree before running it.
+    pub dump_tree: bool,
+    // How many calls may be in progress at once, not counting calls in tail
+    // position, before evaluation fails with a stack overflow.
+    pub max_depth: usize,
+    // Limits on what a run may use, with no limit when None: how many steps
+    // it may take, how many bytes the values it creates may add up to, and
+    // how long it may take. Native code of the Jit backend takes no steps, so
+    // the evaluator runs everything itself under a limit of steps or time.
+    pub max_steps: Option<u64>,
+    pub max_bytes: Option<usize>,
+    pub timeout: Option<Duration>,
+    // Stops the run in progress once raised.
+    pub interrupt: Interrupt,
+    // Where `print` writes, and where errors and warnings are reported.
+    pub output: OutputRef,
+    // Where `input` and `read_all` read from.
+    pub input: InputRef,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            overflow: Overflow::default(),
+            engine: Backend::default(),
+            dump_tree: false,
+            max_depth: 10_000,
+            max_steps: None,
+            max_bytes: None,
+            timeout: None,
+            interrupt: Interrupt::default(),
+            output: OutputRef::default(),
+            input: InputRef::default(),
+        }
+    }
+}
+
+// How integer arithmetic behaves when a result does not fit in 64 bits.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Overflow {
+    #[default]
+    Promote,
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+impl Overflow {
+    // Fits an exact integer result, or yields None when it overflows.
+    pub fn fit(self, value: BigInt) -> Option<Value> {
+        match self {
+            Overflow::Promote => Some(Value::from(value)),
+            Overflow::Checked => value.to_i64().map(Value::Integer),
+            Overflow::Wrapping => {
+                let low_bits = value & BigInt::from(u64::MAX);
+                Some(Value::Integer(low_bits.to_u64().unwrap() as i64))
+            }
+            Overflow::Saturating => match value.to_i64() {
+                Some(value) => Some(Value::Integer(value)),
+                None if value > BigInt::from(0) => Some(Value::Integer(i64::MAX)),
+                None => Some(Value::Integer(i64::MIN)),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "promote" => Ok(Overflow::Promote),
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            "saturating" => Ok(Overflow::Saturating),
+            _ => Err(format!("Unknown overflow behavior: {}", name)),
+        }
+    }
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Overflow::Promote => "promote",
+            Overflow::Checked => "checked",
+            Overflow::Wrapping => "wrapping",
+            Overflow::Saturating => "saturating",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// What runs a checked tree: the tree-walking evaluator, or the stack machine
+// over the tree compiled to bytecode. With the `jit` feature, the evaluator
+// can also run integer-only functions and loops as native code.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Backend {
+    #[default]
+    Tree,
+    Bytecode,
+    #[cfg(feature = "jit")]
+    Jit,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "tree" => Ok(Backend::Tree),
+            "bytecode" => Ok(Backend::Bytecode),
+            #[cfg(feature = "jit")]
+            "jit" => Ok(Backend::Jit),
+            _ => Err(format!("Unknown engine: {}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn past_the_maximum() -> BigInt {
+        BigInt::from(i64::MAX) + 1
+    }
+
+    #[test]
+    fn it_promotes_to_a_big_integer() {
+        assert_eq!(
+            Overflow::Promote.fit(past_the_maximum()),
+            Some(Value::BigInteger(past_the_maximum()))
+        );
+        assert_eq!(
+            Overflow::Promote.fit(BigInt::from(3)),
+            Some(Value::Integer(3))
+        );
+    }
+
+    #[test]
+    fn it_reports_overflow_when_checked() {
+        assert_eq!(Overflow::Checked.fit(past_the_maximum()), None);
+        assert_eq!(
+            Overflow::Checked.fit(BigInt::from(3)),
+            Some(Value::Integer(3))
+        );
+    }
+
+    #[test]
+    fn it_wraps_around() {
+        assert_eq!(
+            Overflow::Wrapping.fit(past_the_maximum()),
+            Some(Value::Integer(i64::MIN))
+        );
+        assert_eq!(
+            Overflow::Wrapping.fit(BigInt::from(i64::MIN) - 1),
+            Some(Value::Integer(i64::MAX))
+        );
+    }
+
+    #[test]
+    fn it_saturates_at_the_bounds() {
+        assert_eq!(
+            Overflow::Saturating.fit(past_the_maximum() * 2),
+            Some(Value::Integer(i64::MAX))
+        );
+        assert_eq!(
+            Overflow::Saturating.fit(BigInt::from(i64::MIN) - 1),
+            Some(Value::Integer(i64::MIN))
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+};
+
+// Where a run writes: the lines the program prints, the prompts of `input`,
+// which stay on the line the answer is typed on, and apart from them the
+// diagnostics about it, such as errors and warnings.
+pub trait Output {
+    fn print(&mut self, line: &str);
+    fn prompt(&mut self, text: &str);
+    fn diagnose(&mut self, line: &str);
+}
+
+// Prints to stdout, and diagnoses to stderr.
+pub struct Stdout;
+
+impl Output for Stdout {
+    fn print(&mut self, line: &str) {
+        println!("{}", line);
+    }
+
+    fn prompt(&mut self, text: &str) {
+        print!("{}", text);
+        io::stdout().flush().ok();
+    }
+
+    fn diagnose(&mut self, line: &str) {
+        eprintln!("{}", line);
+    }
+}
+
+// Keeps every line written to it, for tests and hosts to read back.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Buffer {
+    pub printed: String,
+    pub diagnostics: String,
+}
+
+impl Output for Buffer {
+    fn print(&mut self, line: &str) {
+        self.printed.push_str(line);
+        self.printed.push('\n');
+    }
+
+    fn prompt(&mut self, text: &str) {
+        self.printed.push_str(text);
+    }
+
+    fn diagnose(&mut self, line: &str) {
+        self.diagnostics.push_str(line);
+        self.diagnostics.push('\n');
+    }
+}
+
+// The Output of the options, shared by their clones. Keep another reference
+// to a Buffer to read what a run wrote to it:
+//
+//     let buffer = Rc::new(RefCell::new(Buffer::default()));
+//     let output = OutputRef::from(buffer.clone());
+#[derive(Clone)]
+pub struct OutputRef(Rc<RefCell<dyn Output>>);
+
+impl OutputRef {
+    pub fn new(output: impl Output + 'static) -> OutputRef {
+        OutputRef(Rc::new(RefCell::new(output)))
+    }
+
+    pub fn print(&self, line: &str) {
+        self.0.borrow_mut().print(line);
+    }
+
+    pub fn prompt(&self, text: &str) {
+        self.0.borrow_mut().prompt(text);
+    }
+
+    pub fn diagnose(&self, line: &str) {
+        self.0.borrow_mut().diagnose(line);
+    }
+}
+
+impl<T: Output + 'static> From<Rc<RefCell<T>>> for OutputRef {
+    fn from(output: Rc<RefCell<T>>) -> Self {
+        OutputRef(output)
+    }
+}
+
+impl Default for OutputRef {
+    fn default() -> Self {
+        OutputRef::new(Stdout)
+    }
+}
+
+impl fmt::Debug for OutputRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OutputRef")
+    }
+}
+
+impl PartialEq for OutputRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_printed_lines_apart_from_diagnostics() {
+        let buffer = Rc::new(RefCell::new(Buffer::default()));
+        let output = OutputRef::from(buffer.clone());
+
+        output.print("1");
+        output.diagnose("warning");
+        output.clone().print("2");
+
+        assert_eq!(buffer.borrow().printed, "1\n2\n");
+        assert_eq!(buffer.borrow().diagnostics, "warning\n");
+    }
+}
@@ -1,7 +1,12 @@
+use num_bigint::BigInt;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Primitive {
-    Integer(i32),
+    Integer(i64),
+    BigInteger(BigInt),
+    Float(f64),
     String(String),
     Boolean(bool),
+    // A hole in a tree, which `Expression::replace_with` fills.
     ToReplace,
 }
@@ -0,0 +1,731 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::stdlib;
+
+use super::{
+    expression::{Declaration, Expression, ExpressionRef, Function, Pattern, Variable},
+    stack,
+    values::Error,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Warning {
+    UnusedVariable(String),
+}
+
+// `functions` holds every function declared in the resolved tree, for the
+// Environment to define before evaluation; `declarations` every struct and
+// enum, for the checker.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Resolved {
+    pub expression: ExpressionRef,
+    pub functions: Vec<Rc<Function>>,
+    pub declarations: Vec<Rc<Declaration>>,
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Binding {
+    name: String,
+    slot: usize,
+    mutable: bool,
+    used: bool,
+}
+
+// A frame is the scope of a function body: variables declared outside it are
+// out of reach, and its slots start over at 0. Only the immutable globals
+// declared before the function, including those of a host, can still be
+// read, by their slot from the bottom of the stack; anything else from
+// outside has to be passed in as an argument. Functions are visible across
+// frames. They are hoisted, so every function of a scope is known before any
+// of its statements is resolved; `next_function` is the first of them whose
+// declaration is still to be resolved. `names` holds where each binding is in
+// `bindings`, so a scope with many of them is searched in constant time.
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Scope {
+    bindings: Vec<Binding>,
+    names: HashMap<String, usize>,
+    functions: Vec<(String, usize)>,
+    next_function: usize,
+    frame: bool,
+}
+
+impl Scope {
+    // Rebuilds `names` after bindings were removed.
+    fn index(&mut self) {
+        self.names = self
+            .bindings
+            .iter()
+            .enumerate()
+            .map(|(index, binding)| (binding.name.clone(), index))
+            .collect();
+    }
+}
+
+// Binds every variable of a tree to the slot its declaration will occupy in
+// the Environment, mirroring the scopes the evaluator opens: `if` branches,
+// `for` bodies and blocks get their own scope, `let` declares into the
+// current one. Undefined variables fail the resolution; variables that are
+// never read are reported as warnings. Names starting with `_` are exempt.
+// Assigning a binding not declared with `let mut` fails the resolution too,
+// and so does calling a function that is neither declared nor in the stdlib.
+//
+// Structs and enums are hoisted like functions, but their constructors are
+// visible everywhere once declared. Calls to a constructor become Construct
+// expressions, and so do the names of constructors without fields, in
+// expressions as in patterns. `p.field` reads a field of the variable `p`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Resolver {
+    scopes: Vec<Scope>,
+    definitions: usize,
+    functions: Vec<Rc<Function>>,
+    constructors: HashMap<String, (Rc<Declaration>, usize)>,
+    declarations: Vec<Rc<Declaration>>,
+    // Whether globals outlive a single resolution, as they do in the REPL.
+    // Those are never reported unused since a later input may still read them.
+    persistent: bool,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver {
+            scopes: vec![Scope::default()],
+            definitions: 0,
+            functions: Vec::new(),
+            constructors: HashMap::new(),
+            declarations: Vec::new(),
+            persistent: false,
+        }
+    }
+
+    pub fn persistent() -> Resolver {
+        Resolver {
+            persistent: true,
+            ..Resolver::new()
+        }
+    }
+
+    pub fn resolve(&mut self, expression: &Expression) -> Result<Resolved, Error> {
+        let globals = self.scopes[0].clone();
+        let mut warnings = Vec::new();
+
+        self.hoist(expression);
+        let resolved = self.expression(expression, &mut warnings);
+        let functions = std::mem::take(&mut self.functions);
+        let declarations = std::mem::take(&mut self.declarations);
+        match resolved {
+            Ok(expression) => {
+                if !self.persistent {
+                    let globals = std::mem::take(&mut self.scopes[0]);
+                    Resolver::unused(globals.bindings, &mut warnings);
+                }
+                Ok(Resolved {
+                    expression,
+                    functions,
+                    declarations,
+                    warnings,
+                })
+            }
+            Err(err) => {
+                self.scopes = vec![globals];
+                Err(err)
+            }
+        }
+    }
+
+    // Drops the globals whose declaration never ran because the evaluation
+    // failed first, given the number of slots the Environment ended up with.
+    pub fn forget(&mut self, slots: usize) {
+        let globals = &mut self.scopes[0];
+        globals.bindings.retain(|binding| binding.slot < slots);
+        globals.index();
+    }
+
+    // The slot of a global, if there is one by that name.
+    pub fn global(&self, name: &str) -> Option<usize> {
+        let globals = &self.scopes[0];
+        let index = *globals.names.get(name)?;
+        Some(globals.bindings[index].slot)
+    }
+
+    // Declares a global for a host to fill, or yields the slot of the one
+    // already declared by that name. It is never reported unused.
+    pub fn define(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.global(name) {
+            return slot;
+        }
+        let globals = &mut self.scopes[0];
+        let slot = globals.bindings.len();
+        globals.names.insert(name.to_string(), globals.bindings.len());
+        globals.bindings.push(Binding {
+            name: name.to_string(),
+            slot,
+            mutable: false,
+            used: true,
+        });
+        slot
+    }
+
+    fn expression(
+        &mut self,
+        expression: &Expression,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<ExpressionRef, Error> {
+        stack::grow(|| self.node(expression, warnings))
+    }
+
+    fn node(
+        &mut self,
+        expression: &Expression,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<ExpressionRef, Error> {
+        let resolved = match expression {
+            Expression::Primitive(_) | Expression::None {} => expression.clone(),
+            Expression::Expression(inner) => {
+                Expression::Expression(self.expression(inner, warnings)?)
+            }
+            Expression::Sum { left, right } => Expression::Sum {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Product { left, right } => Expression::Product {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Divide { left, right } => Expression::Divide {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Subtract { left, right } => Expression::Subtract {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::IntegerDivide { left, right } => Expression::IntegerDivide {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Modulo { left, right } => Expression::Modulo {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Power { left, right } => Expression::Power {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => Expression::If {
+                condition: self.expression(condition, warnings)?,
+                then_branch: self.scoped(then_branch, warnings)?,
+                else_branch: self.scoped(else_branch, warnings)?,
+            },
+            Expression::Compare {
+                left,
+                operand,
+                right,
+            } => Expression::Compare {
+                left: self.expression(left, warnings)?,
+                operand: operand.clone(),
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Bitwise {
+                left,
+                operand,
+                right,
+            } => Expression::Bitwise {
+                left: self.expression(left, warnings)?,
+                operand: operand.clone(),
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Let { variables, scope } => {
+                let mut resolved = Vec::new();
+                for (pattern, value) in variables {
+                    let value = self.expression(value, warnings)?;
+                    resolved.push((self.pattern(pattern, warnings)?, value));
+                }
+                Expression::Let {
+                    variables: resolved,
+                    scope: self.expression(scope, warnings)?,
+                }
+            }
+            Expression::Use { variable } => self.reference(variable)?,
+            Expression::Unary { operand, right } => Expression::Unary {
+                operand: operand.clone(),
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Assign { variable, value } => Expression::Assign {
+                value: self.expression(value, warnings)?,
+                variable: self.lookup(variable, false)?,
+            },
+            Expression::For {
+                variable,
+                from,
+                to,
+                body,
+            } => {
+                let from = self.expression(from, warnings)?;
+                let to = self.expression(to, warnings)?;
+
+                self.scopes.push(Scope::default());
+                let variable = self.declare(variable, warnings);
+                self.hoist(body);
+                let body = self.expression(body, warnings);
+                self.end_scope(warnings);
+
+                Expression::For {
+                    variable,
+                    from,
+                    to,
+                    body: body?,
+                }
+            }
+            Expression::Print { expression } => Expression::Print {
+                expression: self.expression(expression, warnings)?,
+            },
+            Expression::Chain { left, right } => Expression::Chain {
+                left: self.expression(left, warnings)?,
+                right: self.expression(right, warnings)?,
+            },
+            Expression::Line { line, statement } => Expression::Line {
+                line: *line,
+                statement: self.expression(statement, warnings)?,
+            },
+            Expression::Block { body } => Expression::Block {
+                body: self.scoped(body, warnings)?,
+            },
+            Expression::Function { function, scope } => Expression::Function {
+                function: self.function(function, warnings)?,
+                scope: self.expression(scope, warnings)?,
+            },
+            Expression::Call {
+                function,
+                arguments,
+                ..
+            } if self.constructors.contains_key(function) => {
+                let (declaration, constructor) = self.constructors[function].clone();
+                Expression::Construct {
+                    declaration,
+                    constructor,
+                    arguments: self.arguments(arguments, warnings)?,
+                }
+            }
+            Expression::Call {
+                function,
+                arguments,
+                ..
+            } => Expression::Call {
+                function: function.clone(),
+                definition: self.definition(function)?,
+                arguments: self.arguments(arguments, warnings)?,
+            },
+            Expression::Tuple { elements } => Expression::Tuple {
+                elements: elements
+                    .iter()
+                    .map(|element| self.expression(element, warnings))
+                    .collect::<Result<_, _>>()?,
+            },
+            Expression::Index { tuple, index } => Expression::Index {
+                tuple: self.expression(tuple, warnings)?,
+                index: *index,
+            },
+            Expression::Declare { declaration, scope } => Expression::Declare {
+                declaration: declaration.clone(),
+                scope: self.expression(scope, warnings)?,
+            },
+            Expression::Construct {
+                declaration,
+                constructor,
+                arguments,
+            } => Expression::Construct {
+                declaration: declaration.clone(),
+                constructor: *constructor,
+                arguments: self.arguments(arguments, warnings)?,
+            },
+            Expression::Field { value, field } => Expression::Field {
+                value: self.expression(value, warnings)?,
+                field: field.clone(),
+            },
+            Expression::Match { value, arms } => {
+                let value = self.expression(value, warnings)?;
+                let mut resolved = Vec::new();
+                for (pattern, body) in arms {
+                    self.scopes.push(Scope::default());
+                    let arm = self.pattern(pattern, warnings).and_then(|pattern| {
+                        self.hoist(body);
+                        Ok((pattern, self.expression(body, warnings)?))
+                    });
+                    self.end_scope(warnings);
+                    resolved.push(arm?);
+                }
+                Expression::Match {
+                    value,
+                    arms: resolved,
+                }
+            }
+        };
+
+        Ok(Rc::new(resolved))
+    }
+
+    fn arguments(
+        &mut self,
+        arguments: &[ExpressionRef],
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<ExpressionRef>, Error> {
+        arguments
+            .iter()
+            .map(|argument| self.expression(argument, warnings))
+            .collect()
+    }
+
+    fn scoped(
+        &mut self,
+        expression: &Expression,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<ExpressionRef, Error> {
+        self.scopes.push(Scope::default());
+        self.hoist(expression);
+        let resolved = self.expression(expression, warnings);
+        self.end_scope(warnings);
+        resolved
+    }
+
+    fn end_scope(&mut self, warnings: &mut Vec<Warning>) {
+        let scope = self.scopes.pop().unwrap();
+        Resolver::unused(scope.bindings, warnings);
+    }
+
+    // Numbers the functions declared by the statements of the current scope,
+    // and declares the constructors of its structs and enums.
+    fn hoist(&mut self, mut expression: &Expression) {
+        loop {
+            expression = match expression {
+                Expression::Declare { declaration, scope } => {
+                    for (index, constructor) in declaration.constructors.iter().enumerate() {
+                        self.constructors
+                            .insert(constructor.name.clone(), (declaration.clone(), index));
+                    }
+                    self.declarations.push(declaration.clone());
+                    scope
+                }
+                Expression::Function { function, scope } => {
+                    let definition = self.definitions;
+                    self.definitions += 1;
+                    let current = self.scopes.last_mut().unwrap();
+                    current.functions.push((function.name.clone(), definition));
+                    scope
+                }
+                Expression::Let { scope, .. } => scope,
+                Expression::Chain { right, .. } => right,
+                Expression::Line { statement, .. } => statement,
+                _ => return,
+            }
+        }
+    }
+
+    fn function(
+        &mut self,
+        function: &Function,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Rc<Function>, Error> {
+        let current = self.scopes.last_mut().unwrap();
+        let definition = current.functions[current.next_function].1;
+        current.next_function += 1;
+
+        self.scopes.push(Scope {
+            frame: true,
+            ..Scope::default()
+        });
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| self.declare(parameter, warnings))
+            .collect();
+        self.hoist(&function.body);
+        let body = self.expression(&function.body, warnings);
+        self.end_scope(warnings);
+
+        let function = Rc::new(Function {
+            definition: Some(definition),
+            parameters,
+            body: body?,
+            ..function.clone()
+        });
+        self.functions.push(function.clone());
+        Ok(function)
+    }
+
+    // User functions shadow the stdlib, whose functions stay unresolved.
+    fn definition(&self, function: &str) -> Result<Option<usize>, Error> {
+        let definition = self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .functions
+                .iter()
+                .rev()
+                .find(|(name, _)| name == function)
+        });
+
+        match definition {
+            Some((_, definition)) => Ok(Some(*definition)),
+            None if stdlib::lookup(function).is_some() => Ok(None),
+            None => Err(Error::UndefinedFunction(function.to_string())),
+        }
+    }
+
+    fn unused(scope: Vec<Binding>, warnings: &mut Vec<Warning>) {
+        for binding in scope {
+            if !binding.used && !binding.name.starts_with('_') {
+                warnings.push(Warning::UnusedVariable(binding.name));
+            }
+        }
+    }
+
+    // Declares the variables of a pattern from left to right, the order in
+    // which the evaluator binds them.
+    fn pattern(
+        &mut self,
+        pattern: &Pattern,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Pattern, Error> {
+        Ok(match pattern {
+            Pattern::Variable(variable)
+                if !variable.mutable
+                    && variable.annotation.is_none()
+                    && self.constructors.contains_key(&variable.name) =>
+            {
+                Pattern::Constructor {
+                    name: variable.name.clone(),
+                    elements: Vec::new(),
+                }
+            }
+            Pattern::Variable(variable) => Pattern::Variable(self.declare(variable, warnings)),
+            Pattern::Literal(literal) => Pattern::Literal(literal.clone()),
+            Pattern::Tuple(elements) => Pattern::Tuple(self.patterns(elements, warnings)?),
+            Pattern::List { elements, rest } => Pattern::List {
+                elements: self.patterns(elements, warnings)?,
+                rest: rest.as_ref().map(|rest| self.declare(rest, warnings)),
+            },
+            Pattern::Constructor { name, elements } => {
+                if !self.constructors.contains_key(name) {
+                    return Err(Error::UndefinedConstructor(name.clone()));
+                }
+                Pattern::Constructor {
+                    name: name.clone(),
+                    elements: self.patterns(elements, warnings)?,
+                }
+            }
+        })
+    }
+
+    fn patterns(
+        &mut self,
+        patterns: &[Pattern],
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<Pattern>, Error> {
+        patterns
+            .iter()
+            .map(|pattern| self.pattern(pattern, warnings))
+            .collect()
+    }
+
+    // A redeclaration in the same scope takes over the slot of the binding it
+    // replaces; any other declaration takes the next free slot.
+    fn declare(&mut self, variable: &Variable, warnings: &mut Vec<Warning>) -> Variable {
+        let next = self
+            .frame()
+            .iter()
+            .map(|scope| scope.bindings.len())
+            .sum::<usize>();
+        let scope = self.scopes.last_mut().unwrap();
+
+        let slot = match scope.names.get(&variable.name) {
+            Some(&index) => {
+                let replaced = scope.bindings.remove(index);
+                Resolver::unused(vec![replaced.clone()], warnings);
+                scope.index();
+                replaced.slot
+            }
+            None => next,
+        };
+        scope
+            .names
+            .insert(variable.name.clone(), scope.bindings.len());
+        scope.bindings.push(Binding {
+            name: variable.name.clone(),
+            slot,
+            mutable: variable.mutable,
+            used: false,
+        });
+
+        Variable {
+            slot: Some(slot),
+            global: self.scopes.len() == 1,
+            ..variable.clone()
+        }
+    }
+
+    // The scopes of the innermost frame, or every scope outside functions.
+    fn frame(&self) -> &[Scope] {
+        let start = self
+            .scopes
+            .iter()
+            .rposition(|scope| scope.frame)
+            .unwrap_or(0);
+        &self.scopes[start..]
+    }
+
+    // A variable in use, a constructor without fields, or the field of a
+    // variable.
+    fn reference(&mut self, variable: &Variable) -> Result<Expression, Error> {
+        let err = match self.lookup(variable, true) {
+            Ok(variable) => return Ok(Expression::Use { variable }),
+            Err(err) => err,
+        };
+        if let Some((declaration, constructor)) = self.constructors.get(&variable.name) {
+            return Ok(Expression::Construct {
+                declaration: declaration.clone(),
+                constructor: *constructor,
+                arguments: Vec::new(),
+            });
+        }
+
+        let mut fields = variable.name.split('.');
+        let head = Variable::new(fields.next().unwrap_or_default().to_string());
+        match self.lookup(&head, true) {
+            Ok(head) if variable.name.contains('.') => {
+                let mut expression = Expression::Use { variable: head };
+                for field in fields {
+                    expression = Expression::Field {
+                        value: Rc::new(expression),
+                        field: field.to_string(),
+                    };
+                }
+                Ok(expression)
+            }
+            _ => Err(err),
+        }
+    }
+
+    // Stdlib constants are not bound to a slot: the evaluator looks them up
+    // by name.
+    fn lookup(&mut self, variable: &Variable, read: bool) -> Result<Variable, Error> {
+        let start = self.scopes.len() - self.frame().len();
+        let declared = |scope: &Scope| scope.names.get(&variable.name).copied();
+        let mut found = (start..self.scopes.len())
+            .rev()
+            .find_map(|scope| Some((scope, declared(&self.scopes[scope])?)));
+        // A function body may still read an immutable global.
+        let mut global = false;
+        if let (None, true, Some(index)) = (found, start > 0 && read, declared(&self.scopes[0])) {
+            if !self.scopes[0].bindings[index].mutable {
+                found = Some((0, index));
+                global = true;
+            }
+        }
+        let binding = found.map(|(scope, index)| &mut self.scopes[scope].bindings[index]);
+
+        match binding {
+            Some(binding) if !read && !binding.mutable => {
+                Err(Error::ImmutableVariable(variable.name.clone()))
+            }
+            Some(binding) => {
+                binding.used |= read;
+                Ok(Variable {
+                    global,
+                    ..Variable::resolved(variable.name.clone(), binding.slot)
+                })
+            }
+            None if read && stdlib::constant(&variable.name).is_some() => {
+                Ok(Variable::new(variable.name.clone()))
+            }
+            None => Err(Error::UndefinedVariable(variable.name.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::Parser, translator::Translator};
+
+    fn given_this_source(source: &str) -> Result<Resolved, Error> {
+        Resolver::new().resolve(&Translator::from(Parser::new().parse(source)))
+    }
+
+    #[test]
+    fn it_reports_undefined_variables_before_running() {
+        assert_eq!(
+            given_this_source("if false { print 1 } else { print typo }"),
+            Err(Error::UndefinedVariable("typo".to_string()))
+        );
+        assert_eq!(
+            given_this_source("if true { let y = 1 }\nprint y"),
+            Err(Error::UndefinedVariable("y".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_reports_unused_variables() {
+        let resolved = given_this_source("let x = 1\nlet _y = 2\nfor i in [0..3] { print x }");
+
+        assert_eq!(
+            resolved.map(|resolved| resolved.warnings),
+            Ok(vec![Warning::UnusedVariable("i".to_string())])
+        );
+    }
+
+    #[test]
+    fn it_releases_slots_when_a_scope_ends() {
+        let resolved = given_this_source("{ let a = 1\nprint a }\nlet b = 2\nprint b").unwrap();
+
+        let Expression::Chain { right, .. } = resolved.expression.as_ref() else {
+            panic!("expected a chain, got {:?}", resolved.expression);
+        };
+        let Expression::Line { statement, .. } = right.as_ref() else {
+            panic!("expected a line, got {:?}", right);
+        };
+        let Expression::Let { variables, .. } = statement.as_ref() else {
+            panic!("expected a let, got {:?}", statement);
+        };
+        assert_eq!(
+            variables[0].0,
+            Pattern::Variable(Variable {
+                global: true,
+                ..Variable::resolved("b".to_string(), 0)
+            })
+        );
+    }
+
+    #[test]
+    fn it_keeps_globals_of_a_persistent_resolver() {
+        let mut resolver = Resolver::persistent();
+
+        let first = resolver.resolve(&Translator::from(Parser::new().parse("let x = 1")));
+        let second = resolver.resolve(&Translator::from(Parser::new().parse("print x")));
+
+        assert_eq!(first.map(|resolved| resolved.warnings), Ok(vec![]));
+        assert!(second.is_ok());
+    }
+
+    #[test]
+    fn it_forgets_globals_of_a_failed_resolution() {
+        let mut resolver = Resolver::persistent();
+
+        let failed = resolver.resolve(&Translator::from(Parser::new().parse("let x = 1\nprint y")));
+        let next = resolver.resolve(&Translator::from(Parser::new().parse("print x")));
+
+        assert!(failed.is_err());
+        assert_eq!(
+            next.map(|resolved| resolved.warnings),
+            Err(Error::UndefinedVariable("x".to_string()))
+        );
+    }
+}
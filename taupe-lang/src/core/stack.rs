@@ -0,0 +1,23 @@
+// How much stack a pass may use between two calls to `grow`.
+const RED_ZONE: usize = 256 * 1024;
+
+// The size of each stack `grow` moves onto.
+const SEGMENT: usize = 8 * 1024 * 1024;
+
+// Runs `f` with at least RED_ZONE bytes of stack left, moving onto a stack
+// allocated on the heap when the current one runs low. Every pass that
+// recurses over the tree does so through here, so neither a long script,
+// which nests a node for every line, nor a deep expression overflows the
+// stack of the thread it runs on.
+pub(crate) fn grow<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, SEGMENT, f)
+}
+
+// The lowest address code called from here may take its stack down to while
+// leaving it the red zone, if the stack is known.
+#[cfg(feature = "jit")]
+pub(crate) fn limit() -> Option<usize> {
+    let here = 0u8;
+    let top = &here as *const u8 as usize;
+    Some(top - stacker::remaining_stack()? + RED_ZONE)
+}
@@ -0,0 +1,156 @@
+use super::primitives::Primitive;
+
+// The static types of values, as written in annotations and as computed by
+// the checker. Unknown is the type of anything the checker cannot tell, such
+// as an unannotated mutable variable; it is compatible with every type, so
+// unannotated code is only checked where its types are evident.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    Int,
+    Float,
+    Str,
+    Bool,
+    List(Box<Type>),
+    Tuple(Vec<Type>),
+    Unit,
+    // Any other name, with its type arguments: a type parameter, a declared
+    // struct or enum such as Option<int>, or a name the checker reports.
+    Named(String, Vec<Type>),
+    // A type the inferencer has yet to work out, by number.
+    Variable(usize),
+    Unknown,
+}
+
+impl Type {
+    // The type of a literal value.
+    pub fn of(primitive: &Primitive) -> Type {
+        match primitive {
+            Primitive::Integer(_) | Primitive::BigInteger(_) => Type::Int,
+            Primitive::Float(_) => Type::Float,
+            Primitive::String(_) => Type::Str,
+            Primitive::Boolean(_) => Type::Bool,
+            Primitive::ToReplace => Type::Unknown,
+        }
+    }
+
+    // Whether a value of type `other` may be used where `self` is expected.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::List(expected), Type::List(actual)) => expected.accepts(actual),
+            (Type::Named(expected_name, expected), Type::Named(actual_name, actual))
+                if expected_name != actual_name =>
+            {
+                false
+            }
+            (Type::Tuple(expected), Type::Tuple(actual))
+            | (Type::Named(_, expected), Type::Named(_, actual)) => {
+                expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual)
+                        .all(|(expected, actual)| expected.accepts(actual))
+            }
+            _ => self == other,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Unknown)
+    }
+
+    // The type an arithmetic operator makes out of two numbers: two integers
+    // make `integers`, which Power leaves Unknown since a negative exponent
+    // makes a float even out of integers; any float among numbers makes a
+    // float. None when either side is not known to be a number.
+    pub fn arithmetic(left: &Type, right: &Type, integers: Type) -> Option<Type> {
+        match (left, right) {
+            (Type::Int, Type::Int) => Some(integers),
+            (Type::Float, _) | (_, Type::Float) if left.is_numeric() && right.is_numeric() => {
+                Some(Type::Float)
+            }
+            _ if left.is_numeric() && right.is_numeric() => Some(Type::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Str => write!(f, "str"),
+            Type::Bool => write!(f, "bool"),
+            Type::List(element) => write!(f, "[{}]", element),
+            Type::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(Type::to_string).collect();
+                match elements.as_slice() {
+                    [element] => write!(f, "({},)", element),
+                    _ => write!(f, "({})", elements.join(", ")),
+                }
+            }
+            Type::Unit => write!(f, "()"),
+            Type::Named(name, arguments) if arguments.is_empty() => write!(f, "{}", name),
+            Type::Named(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(Type::to_string).collect();
+                write!(f, "{}<{}>", name, arguments.join(", "))
+            }
+            Type::Variable(variable) => write!(f, "'{}", variable),
+            Type::Unknown => write!(f, "_"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_unknown_anywhere() {
+        assert!(Type::Int.accepts(&Type::Unknown));
+        assert!(Type::Unknown.accepts(&Type::Str));
+        assert!(Type::List(Box::new(Type::Int)).accepts(&Type::List(Box::new(Type::Unknown))));
+    }
+
+    #[test]
+    fn it_refuses_different_types() {
+        assert!(!Type::Float.accepts(&Type::Int));
+        assert!(!Type::Tuple(vec![Type::Int]).accepts(&Type::Tuple(vec![Type::Int, Type::Int])));
+        assert!(!Type::Named("Option".to_string(), vec![Type::Int])
+            .accepts(&Type::Named("List".to_string(), vec![Type::Int])));
+    }
+
+    #[test]
+    fn it_types_arithmetic_on_numbers() {
+        assert_eq!(
+            Type::arithmetic(&Type::Int, &Type::Int, Type::Int),
+            Some(Type::Int)
+        );
+        assert_eq!(
+            Type::arithmetic(&Type::Int, &Type::Float, Type::Int),
+            Some(Type::Float)
+        );
+        assert_eq!(
+            Type::arithmetic(&Type::Float, &Type::Unknown, Type::Int),
+            Some(Type::Float)
+        );
+        assert_eq!(
+            Type::arithmetic(&Type::Int, &Type::Unknown, Type::Int),
+            Some(Type::Unknown)
+        );
+        assert_eq!(Type::arithmetic(&Type::Int, &Type::Str, Type::Int), None);
+    }
+
+    #[test]
+    fn it_displays_types_as_annotations() {
+        let tuple = Type::Tuple(vec![Type::List(Box::new(Type::Str)), Type::Bool]);
+
+        assert_eq!(tuple.to_string(), "([str], bool)");
+        assert_eq!(Type::Tuple(vec![Type::Int]).to_string(), "(int,)");
+        assert_eq!(
+            Type::Named("Pair".to_string(), vec![Type::Int, Type::Str]).to_string(),
+            "Pair<int, str>"
+        );
+    }
+}
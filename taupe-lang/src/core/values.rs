@@ -1,19 +1,83 @@
-use std::collections::HashMap;
+use std::{mem::size_of, time::Duration};
 
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 
-use super::expression::{ExpressionRef, Operand, Expression};
+use super::{
+    expression::{Expression, Operand, Pattern},
+    types::Type,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     String(String),
-    Integer(i32),
+    Integer(i64),
+    BigInteger(BigInt),
     Float(f64),
     Boolean(bool),
-    State(Option<HashMap<String, ExpressionRef>>),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    Struct(String, Vec<(String, Value)>),
+    Variant(String, Vec<Value>),
     Unit,
     None,
 }
 
+impl Value {
+    // Integers promote to floats wherever both kinds meet.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Integer(value) => Some(*value as f64),
+            Value::BigInteger(value) => value.to_f64(),
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_big_integer(&self) -> Option<BigInt> {
+        match self {
+            Value::Integer(value) => Some(BigInt::from(*value)),
+            Value::BigInteger(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    // The bytes a value holds on the heap.
+    pub fn size(&self) -> usize {
+        let elements = |values: &[Value]| -> usize {
+            values
+                .iter()
+                .map(|value| size_of::<Value>() + value.size())
+                .sum()
+        };
+        match self {
+            Value::String(value) => value.len(),
+            Value::BigInteger(value) => value.bits().div_ceil(8) as usize,
+            Value::List(values) | Value::Tuple(values) => elements(values),
+            Value::Variant(name, values) => name.len() + elements(values),
+            Value::Struct(name, fields) => {
+                let fields: usize = fields
+                    .iter()
+                    .map(|(field, value)| size_of::<(String, Value)>() + field.len() + value.size())
+                    .sum();
+                name.len() + fields
+            }
+            _ => 0,
+        }
+    }
+}
+
+// Big integers only ever hold values outside the i64 range, so equal
+// numbers always share a representation.
+impl From<BigInt> for Value {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Value::Integer(value),
+            None => Value::BigInteger(value),
+        }
+    }
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -22,8 +86,34 @@ impl std::fmt::Display for Value {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Error {
+    // The errors the parser found in a source.
+    ParseError(Vec<String>),
+    // A file that could not be read, with why.
+    UnreadableFile(String, String),
     InvalidOperand(Operand),
-    InvalidExpression(Expression),
+    InvalidExpression(Box<Expression>),
     InvalidValues(String, Vec<Value>),
+    IntegerOverflow(String, Vec<Value>),
+    DivisionByZero(String, Vec<Value>),
+    InvalidShift(String, Vec<Value>),
     UndefinedVariable(String),
+    ImmutableVariable(String),
+    PatternMismatch(Box<Pattern>, Value),
+    UnmatchedValue(Value),
+    TypeMismatch(String, Vec<Type>),
+    // The expected and the found type, each with the line it comes from.
+    TypeConflict(Box<(Type, usize)>, Box<(Type, usize)>),
+    UndefinedType(String),
+    UndefinedFunction(String),
+    UndefinedConstructor(String),
+    // The maximum depth of calls, which a call went past.
+    StackOverflow(usize),
+    // The limits of the options a run went past.
+    StepLimit(u64),
+    MemoryLimit(usize),
+    Timeout(Duration),
+    Interrupted,
+    InvalidBytecode(String),
+    // The version a bytecode file was built for, and the one that reads it.
+    IncompatibleBytecode(u16, u16),
 }
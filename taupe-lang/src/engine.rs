@@ -0,0 +1,102 @@
+use std::{fs, path::Path};
+
+use crate::{
+    core::{
+        checking::Checker,
+        environment::Environment,
+        interpretation::try_interpret,
+        options::Options,
+        resolution::Resolver,
+        values::{Error, Value},
+    },
+    parser::Parser,
+    translator::Translator,
+};
+
+// Runs sources for a host application, keeping what they declare between
+// calls as the REPL does: a global or function one source declares can be
+// used by the next. Errors are returned rather than reported, and globals
+// can be set and read from the host:
+//
+//     let mut engine = Engine::new();
+//     engine.set_global("port", Value::Integer(8080));
+//     let port = engine.eval("+ port 1")?;
+//
+// Parse errors and warnings are still reported to the output of the
+// options, but not the source itself.
+//
+// An Engine is not Send: the output and input of its options, and the
+// functions it keeps, are shared through Rc. It stays on the thread that
+// made it, and a host running sources on several threads makes one per
+// thread.
+#[derive(Debug)]
+pub struct Engine {
+    resolver: Resolver,
+    checker: Checker,
+    environment: Environment,
+    options: Options,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::with_options(Options::default())
+    }
+
+    pub fn with_options(options: Options) -> Engine {
+        Engine {
+            resolver: Resolver::persistent(),
+            checker: Checker::new(),
+            environment: Environment::new(),
+            options,
+        }
+    }
+
+    // The value of the last statement of the source.
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        let mut parser = Parser::new()
+            .with_output(self.options.output.clone())
+            .quiet();
+        let tokens = parser.parse(source);
+        if parser.in_error {
+            return Err(Error::ParseError(parser.errors));
+        }
+        try_interpret(
+            Translator::from(tokens),
+            &mut self.resolver,
+            &mut self.checker,
+            &mut self.environment,
+            &self.options,
+        )
+    }
+
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Value, Error> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(source) => self.eval(&source),
+            Err(err) => Err(Error::UnreadableFile(
+                path.display().to_string(),
+                err.to_string(),
+            )),
+        }
+    }
+
+    // Declares the global, or replaces its value when there is one by that
+    // name. Sources can read it, function bodies included, but only assign
+    // it if they declared it `let mut` themselves.
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        let slot = self.resolver.define(name);
+        self.checker.define(slot);
+        self.environment.declare_global(slot, value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        let slot = self.resolver.global(name)?;
+        self.environment.get(slot).cloned()
+    }
+}
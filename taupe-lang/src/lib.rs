@@ -2,6 +2,10 @@
 extern crate derive_new;
 
 pub mod core;
+pub mod engine;
 pub mod parser;
+pub mod stdlib;
 pub mod translator;
 
+pub use engine::Engine;
+
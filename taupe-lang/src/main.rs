@@ -1,50 +1,264 @@
 use std::{
-    env,
+    env, fs,
     fs::File,
     io::{self, Write},
-    rc::Rc,
+    path::Path,
+    process,
+    time::Duration,
 };
 
+#[cfg(unix)]
+use std::sync::OnceLock;
+
 use taupe::{
     core::{
-        expression::Expression,
-        interpretation::{interpret, interpret_with_state},
+        checking::Checker,
+        compilation::compile,
+        encoding::{decode, encode},
+        environment::Environment,
+        execution::execute,
+        inference::Inferencer,
+        interpretation::interpret_with_options,
+        limits::Interrupt,
+        optimization::optimize,
+        options::{Options, Overflow},
+        resolution::Resolver,
+        values::Error,
     },
     parser::Parser,
     translator::Translator,
 };
 
 pub fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut options = Options::default();
+    // The overflow mode given on the command line, which a built file may
+    // disagree with.
+    let mut overflow = None;
+    let mut args: Vec<String> = Vec::new();
+    for arg in env::args() {
+        if arg == "--dump-tree" {
+            options.dump_tree = true;
+            continue;
+        }
+        let parsed = if let Some(mode) = arg.strip_prefix("--overflow=") {
+            mode.parse().map(|parsed| {
+                options.overflow = parsed;
+                overflow = Some(parsed);
+            })
+        } else if let Some(engine) = arg.strip_prefix("--engine=") {
+            engine.parse().map(|engine| options.engine = engine)
+        } else if let Some(depth) = arg.strip_prefix("--max-depth=") {
+            depth
+                .parse()
+                .map(|depth| options.max_depth = depth)
+                .map_err(|_| format!("Invalid maximum depth: {}", depth))
+        } else if let Some(steps) = arg.strip_prefix("--max-steps=") {
+            steps
+                .parse()
+                .map(|steps| options.max_steps = Some(steps))
+                .map_err(|_| format!("Invalid maximum steps: {}", steps))
+        } else if let Some(bytes) = arg.strip_prefix("--max-bytes=") {
+            bytes
+                .parse()
+                .map(|bytes| options.max_bytes = Some(bytes))
+                .map_err(|_| format!("Invalid maximum bytes: {}", bytes))
+        } else if let Some(milliseconds) = arg.strip_prefix("--timeout=") {
+            milliseconds
+                .parse()
+                .map(|milliseconds| options.timeout = Some(Duration::from_millis(milliseconds)))
+                .map_err(|_| format!("Invalid timeout: {}", milliseconds))
+        } else {
+            args.push(arg);
+            Ok(())
+        };
+        if let Err(err) = parsed {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+
     match args.len() {
         1 => {
+            let interrupt = Interrupt::default();
+            catch_interrupts(&interrupt);
+            let options = Options {
+                interrupt: interrupt.clone(),
+                ..options
+            };
             let mut contents = String::new();
-            let mut state = None;
+            let mut resolver = Resolver::persistent();
+            let mut checker = Checker::new();
+            let mut environment = Environment::new();
             while let Ok(n) = io::stdin().read_line(&mut contents) {
-                write!(io::stdout(), "> ").unwrap();
+                print!("> ");
+                io::stdout().flush().unwrap();
                 if n == 0 {
                     break;
                 }
                 let tokens = Parser::new().parse(&contents);
                 contents.clear();
-                let expr = Expression::DeriveState {
-                    expression: Translator::from(tokens),
-                };
-                let value = interpret_with_state(Rc::new(expr), state.clone());
-                state = match value {
-                    taupe::core::values::Value::State(value) => value,
-                    _ => None,
-                };
+                // Ctrl-C pressed at the prompt interrupts nothing.
+                interrupt.lower();
+                interpret_with_options(
+                    Translator::from(tokens),
+                    &mut resolver,
+                    &mut checker,
+                    &mut environment,
+                    &options,
+                );
             }
         }
         2 => {
             let file = File::open(&args[1]).unwrap();
             let mut parser = Parser::new_from(file);
             let tokens = parser.parse_source().unwrap();
-            interpret(Translator::from(tokens));
+            interpret_with_options(
+                Translator::from(tokens),
+                &mut Resolver::new(),
+                &mut Checker::new(),
+                &mut Environment::new(),
+                &options,
+            );
         }
+        3 if args[1] == "check" => check(&args[2], &options),
+        3 if args[1] == "build" => build(&args[2], &bytecode_path(&args[2]), &options),
+        5 if args[1] == "build" && args[3] == "-o" => build(&args[2], &args[4], &options),
+        3 if args[1] == "run" => run(&args[2], overflow, &options),
         _ => {
-            println!("Usage: taupe <file>");
+            println!(
+                "Usage: taupe [--overflow=promote|checked|wrapping|saturating] [--engine=tree|bytecode] [--max-depth=<calls>] [--max-steps=<steps>] [--max-bytes=<bytes>] [--timeout=<ms>] [--dump-tree] <file>"
+            );
+            println!("       taupe check <file>");
+            println!("       taupe build <file> [-o <file.taub>]");
+            println!("       taupe run <file.taub>");
+        }
+    }
+}
+
+// Raises `interrupt` on Ctrl-C instead of ending the process, so the REPL
+// only stops the statement it is running.
+#[cfg(unix)]
+fn catch_interrupts(interrupt: &Interrupt) {
+    static INTERRUPT: OnceLock<Interrupt> = OnceLock::new();
+
+    extern "C" fn raise(_: libc::c_int) {
+        if let Some(interrupt) = INTERRUPT.get() {
+            interrupt.raise();
+        }
+    }
+
+    INTERRUPT.get_or_init(|| interrupt.clone());
+    // SAFETY: the handler only reads a OnceLock that is already set and
+    // stores to an atomic flag, both of which are safe in a signal handler.
+    unsafe {
+        libc::signal(libc::SIGINT, raise as *const () as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+fn catch_interrupts(_: &Interrupt) {}
+
+// Infers the types of a file without running it and prints the signature of
+// every binding and function, prefixed with the line declaring it.
+fn check(path: &str, options: &Options) {
+    let file = File::open(path).unwrap();
+    let mut parser = Parser::new_from(file);
+    let tokens = parser.parse_source().unwrap();
+    let resolved = match Resolver::new().resolve(&Translator::from(tokens)) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            options.output.diagnose(&format!("{:?}", err));
+            process::exit(1);
+        }
+    };
+    for warning in &resolved.warnings {
+        options.output.diagnose(&format!("{:?}", warning));
+    }
+    match Inferencer::new().infer(&resolved) {
+        Ok(signatures) => {
+            for (line, signature) in signatures {
+                options.output.print(&format!("{}: {}", line, signature));
+            }
+        }
+        Err(err) => {
+            options.output.diagnose(&format!("{:?}", err));
+            process::exit(1);
+        }
+    }
+}
+
+// Compiles a file to bytecode and writes it out, so it can be run later
+// without parsing it again. Constants are folded with the options given here,
+// and the file keeps the overflow mode to run with the same.
+fn build(path: &str, output: &str, options: &Options) {
+    let file = File::open(path).unwrap();
+    let mut parser = Parser::new_from(file);
+    let tokens = parser.parse_source().unwrap();
+    let resolved = match Resolver::new().resolve(&Translator::from(tokens)) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            options.output.diagnose(&format!("{:?}", err));
+            process::exit(1);
+        }
+    };
+    for warning in &resolved.warnings {
+        options.output.diagnose(&format!("{:?}", warning));
+    }
+    if let Err(err) = Checker::new().check(&resolved) {
+        options.output.diagnose(&format!("{:?}", err));
+        process::exit(1);
+    }
+    let program = compile(&optimize(resolved, options));
+    if let Err(err) = fs::write(output, encode(&program, options.overflow)) {
+        options.output.diagnose(&format!("{}: {}", output, err));
+        process::exit(1);
+    }
+}
+
+// Runs a file written by `taupe build` with the overflow mode it was built
+// with, refusing a different one given on the command line.
+fn run(path: &str, overflow: Option<Overflow>, options: &Options) {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            options.output.diagnose(&format!("{}: {}", path, err));
+            process::exit(1);
+        }
+    };
+    let (program, built) = match decode(&bytes) {
+        Ok(decoded) => decoded,
+        Err(Error::IncompatibleBytecode(found, expected)) => {
+            options.output.diagnose(&format!(
+                "{} was built for bytecode version {}, but this taupe runs version {}; rebuild it with taupe build",
+                path, found, expected
+            ));
+            process::exit(1);
         }
+        Err(err) => {
+            options.output.diagnose(&format!("{}: {:?}", path, err));
+            process::exit(1);
+        }
+    };
+    if overflow.is_some_and(|overflow| overflow != built) {
+        options.output.diagnose(&format!(
+            "{} was built with --overflow={}; rebuild it to run with another overflow mode",
+            path, built
+        ));
+        process::exit(1);
+    }
+    let options = Options {
+        overflow: built,
+        ..options.clone()
+    };
+    if let Err(err) = execute(&program, &mut Environment::new(), &options) {
+        options.output.diagnose(&format!("{:?}", err));
     }
 }
+
+// The path of a script with its extension swapped for .taub.
+fn bytecode_path(path: &str) -> String {
+    Path::new(path)
+        .with_extension("taub")
+        .to_string_lossy()
+        .into_owned()
+}
@@ -5,10 +5,22 @@ use std::{fs::File, io::Read};
 use token::Token;
 
 use self::scanner::Scanner;
+use crate::core::output::OutputRef;
 
+// Reports the source it parses, unless made quiet, and the errors it finds
+// to the diagnostics of its output, and keeps the errors in `errors` as well.
 pub struct Parser {
     pub in_error: bool,
+    pub errors: Vec<String>,
     source: Option<File>,
+    output: OutputRef,
+    echo: bool,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
 }
 
 impl Parser {
@@ -20,14 +32,32 @@ impl Parser {
     pub fn new_from(source: File) -> Parser {
         Parser {
             in_error: false,
+            errors: Vec::new(),
             source: Some(source),
+            output: OutputRef::default(),
+            echo: true,
         }
     }
 
     pub fn new() -> Parser {
         Parser {
             in_error: false,
+            errors: Vec::new(),
             source: None,
+            output: OutputRef::default(),
+            echo: true,
+        }
+    }
+
+    pub fn with_output(self, output: OutputRef) -> Parser {
+        Parser { output, ..self }
+    }
+
+    // Leaves the source out of the diagnostics, keeping only the errors.
+    pub fn quiet(self) -> Parser {
+        Parser {
+            echo: false,
+            ..self
         }
     }
 
@@ -39,10 +69,7 @@ impl Parser {
             .as_ref()
             .unwrap_or_else(|| panic!("No source file provided"))
             .read_to_string(&mut contents)
-            .and_then(|_| {
-                let tokens = self.parse(&contents);
-                Ok(tokens)
-            });
+            .map(|_| self.parse(&contents));
 
         if self.in_error {
             Err("Parse error".to_string())
@@ -52,7 +79,10 @@ impl Parser {
     }
 
     pub fn parse(&mut self, source: &str) -> Vec<Token> {
-        println!("Parsing source: \n {}", source);
+        if self.echo {
+            self.output
+                .diagnose(&format!("Parsing source: \n {}", source));
+        }
         Scanner::new(source.to_string()).scan(self)
     }
 
@@ -61,14 +91,16 @@ impl Parser {
     }
 
     fn report(&mut self, line: usize, sort: &str, msg: &str) {
-        println!("[{}] Error {}: {}", line, sort, msg);
+        let error = format!("[{}] Error {}: {}", line, sort, msg);
+        self.output.diagnose(&error);
+        self.errors.push(error);
         self.in_error = true;
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{*, token::TokenType};
+    use super::{token::TokenType, *};
 
     #[test]
     fn verify_that_it_can_read_groups() {
@@ -10,8 +10,7 @@ pub struct Scanner {
 
 impl Scanner {
     pub fn scan(self, program: &mut Parser) -> Vec<Token> {
-        let source = self.source;
-        let contents = source;
+        let contents: Vec<char> = self.source.chars().collect();
 
         let mut tokens: Vec<Token> = Vec::new();
         let mut current = 0;
@@ -46,6 +45,12 @@ impl Scanner {
                         ))
                     }
                 }
+                ':' => Some(Token::new(
+                    TokenType::Colon,
+                    ":".to_string(),
+                    ":".to_string(),
+                    line,
+                )),
                 ',' => Some(Token::new(
                     TokenType::Comma,
                     ",".to_string(),
@@ -76,28 +81,123 @@ impl Scanner {
                     "]".to_string(),
                     line,
                 )),
-                '+' => Some(Token::new(
-                    TokenType::Plus,
-                    "+".to_string(),
-                    "+".to_string(),
+                '+' => {
+                    if Scanner::next('=', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::PlusEqual,
+                            "+=".to_string(),
+                            "+=".to_string(),
+                            line,
+                        ))
+                    } else {
+                        Some(Token::new(
+                            TokenType::Plus,
+                            "+".to_string(),
+                            "+".to_string(),
+                            line,
+                        ))
+                    }
+                }
+                '-' => {
+                    if Scanner::next('=', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::MinusEqual,
+                            "-=".to_string(),
+                            "-=".to_string(),
+                            line,
+                        ))
+                    } else if Scanner::next('>', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::Arrow,
+                            "->".to_string(),
+                            "->".to_string(),
+                            line,
+                        ))
+                    } else {
+                        Some(Token::new(
+                            TokenType::Minus,
+                            "-".to_string(),
+                            "-".to_string(),
+                            line,
+                        ))
+                    }
+                }
+                '/' => {
+                    if Scanner::next('/', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::SlashSlash,
+                            "//".to_string(),
+                            "//".to_string(),
+                            line,
+                        ))
+                    } else if Scanner::next('=', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::SlashEqual,
+                            "/=".to_string(),
+                            "/=".to_string(),
+                            line,
+                        ))
+                    } else {
+                        Some(Token::new(
+                            TokenType::Slash,
+                            "/".to_string(),
+                            "/".to_string(),
+                            line,
+                        ))
+                    }
+                }
+                '*' => {
+                    if Scanner::next('*', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::StarStar,
+                            "**".to_string(),
+                            "**".to_string(),
+                            line,
+                        ))
+                    } else if Scanner::next('=', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::StarEqual,
+                            "*=".to_string(),
+                            "*=".to_string(),
+                            line,
+                        ))
+                    } else {
+                        Some(Token::new(
+                            TokenType::Star,
+                            "*".to_string(),
+                            "*".to_string(),
+                            line,
+                        ))
+                    }
+                }
+                '%' => Some(Token::new(
+                    TokenType::Percent,
+                    "%".to_string(),
+                    "%".to_string(),
                     line,
                 )),
-                '-' => Some(Token::new(
-                    TokenType::Minus,
-                    "-".to_string(),
-                    "-".to_string(),
+                '&' => Some(Token::new(
+                    TokenType::Ampersand,
+                    "&".to_string(),
+                    "&".to_string(),
                     line,
                 )),
-                '/' => Some(Token::new(
-                    TokenType::Slash,
-                    "/".to_string(),
-                    "/".to_string(),
+                '|' => Some(Token::new(
+                    TokenType::Pipe,
+                    "|".to_string(),
+                    "|".to_string(),
                     line,
                 )),
-                '*' => Some(Token::new(
-                    TokenType::Star,
-                    "*".to_string(),
-                    "*".to_string(),
+                '^' => Some(Token::new(
+                    TokenType::Caret,
+                    "^".to_string(),
+                    "^".to_string(),
+                    line,
+                )),
+                '~' => Some(Token::new(
+                    TokenType::Tilde,
+                    "~".to_string(),
+                    "~".to_string(),
                     line,
                 )),
                 '{' => Some(Token::new(
@@ -113,7 +213,14 @@ impl Scanner {
                     line,
                 )),
                 '<' => {
-                    if Scanner::next('=', &contents, &mut current) {
+                    if Scanner::next('<', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::LessLess,
+                            "<<".to_string(),
+                            "<<".to_string(),
+                            line,
+                        ))
+                    } else if Scanner::next('=', &contents, &mut current) {
                         Some(Token::new(
                             TokenType::LessEqual,
                             "<=".to_string(),
@@ -130,7 +237,14 @@ impl Scanner {
                     }
                 }
                 '>' => {
-                    if Scanner::next('=', &contents, &mut current) {
+                    if Scanner::next('>', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::GreaterGreater,
+                            ">>".to_string(),
+                            ">>".to_string(),
+                            line,
+                        ))
+                    } else if Scanner::next('=', &contents, &mut current) {
                         Some(Token::new(
                             TokenType::GreaterEqual,
                             ">=".to_string(),
@@ -154,6 +268,13 @@ impl Scanner {
                             "".to_string(),
                             line,
                         ))
+                    } else if Scanner::next('>', &contents, &mut current) {
+                        Some(Token::new(
+                            TokenType::FatArrow,
+                            "=>".to_string(),
+                            "=>".to_string(),
+                            line,
+                        ))
                     } else {
                         Some(Token::new(
                             TokenType::Equal,
@@ -195,12 +316,7 @@ impl Scanner {
                 }
                 '0'..='9' => {
                     let number = Scanner::number(&contents, &mut current);
-                    Some(Token::new(
-                        TokenType::Number,
-                        number.to_string(),
-                        number.to_string(),
-                        line,
-                    ))
+                    Some(Token::new(TokenType::Number, number.clone(), number, line))
                 }
                 '!' => {
                     if Scanner::next('=', &contents, &mut current) {
@@ -233,20 +349,17 @@ impl Scanner {
         tokens
     }
 
-    fn peek(contents: &str, current: usize) -> char {
-        match contents.chars().nth(current) {
-            Some(c) => c,
-            None => '\0',
-        }
+    fn peek(contents: &[char], current: usize) -> char {
+        contents.get(current).copied().unwrap_or('\0')
     }
 
-    fn advance(contents: &str, current: &mut usize) -> char {
-        let char = contents.chars().nth(*current).unwrap();
+    fn advance(contents: &[char], current: &mut usize) -> char {
+        let char = contents[*current];
         *current += 1;
         char
     }
 
-    fn next(expected: char, contents: &str, current: &mut usize) -> bool {
+    fn next(expected: char, contents: &[char], current: &mut usize) -> bool {
         if Scanner::peek(contents, *current) == expected {
             *current += 1;
             true
@@ -255,11 +368,11 @@ impl Scanner {
         }
     }
 
-    fn string(contents: &str, current: &mut usize) -> Result<String, String> {
+    fn string(contents: &[char], current: &mut usize) -> Result<String, String> {
         let mut result = String::new();
         while Scanner::peek(contents, *current) != '"' {
-            if current >= &mut contents.len() {
-                return Err(format!("Unterminated string"));
+            if *current >= contents.len() {
+                return Err("Unterminated string".to_string());
             }
 
             result.push(Scanner::advance(contents, current));
@@ -268,18 +381,29 @@ impl Scanner {
         Ok(result)
     }
 
-    fn number(contents: &str, current: &mut usize) -> usize {
-        let mut result = contents.chars().nth(*current - 1).unwrap().to_string();
-        while Scanner::peek(contents, *current).is_digit(10) {
+    fn number(contents: &[char], current: &mut usize) -> String {
+        let mut result = contents[*current - 1].to_string();
+        while Scanner::peek(contents, *current).is_ascii_digit() {
             let a = Scanner::advance(contents, current);
             result.push(a);
         }
-        result.parse::<usize>().unwrap()
+        // A dot only starts a fraction when a digit follows, so `0..=6` stays a range.
+        if Scanner::peek(contents, *current) == '.'
+            && Scanner::peek(contents, *current + 1).is_ascii_digit()
+        {
+            result.push(Scanner::advance(contents, current));
+            while Scanner::peek(contents, *current).is_ascii_digit() {
+                result.push(Scanner::advance(contents, current));
+            }
+        }
+        result
     }
 
-    fn identifier(contents: &str, current: &mut usize) -> String {
-        let mut result = contents.chars().nth(*current - 1).unwrap().to_string();
-        while Scanner::peek(contents, *current).is_alphanumeric() {
+    fn identifier(contents: &[char], current: &mut usize) -> String {
+        let mut result = contents[*current - 1].to_string();
+        while Scanner::peek(contents, *current).is_alphanumeric()
+            || Scanner::peek(contents, *current) == '_'
+        {
             result.push(Scanner::advance(contents, current));
         }
         result
@@ -293,8 +417,13 @@ impl Scanner {
             "true" => TokenType::True,
             "false" => TokenType::False,
             "let" => TokenType::Let,
+            "mut" => TokenType::Mut,
             "for" => TokenType::For,
+            "fn" => TokenType::Fn,
             "in" => TokenType::In,
+            "struct" => TokenType::Struct,
+            "enum" => TokenType::Enum,
+            "match" => TokenType::Match,
             _ => TokenType::Identifier,
         }
     }
@@ -363,4 +492,142 @@ mod tests {
         ];
         assert_eq!(tokens, expected);
     }
+
+    #[test]
+    fn it_reads_strings_with_multibyte_characters() {
+        let source = "print string.to_upper(\"héllo\")";
+        let mut parser = Parser::new();
+
+        let tokens = Scanner::new(source.to_string()).scan(&mut parser);
+
+        let expected = vec![
+            Token::new(
+                TokenType::Print,
+                "print".to_string(),
+                "print".to_string(),
+                1,
+            ),
+            Token::new(
+                TokenType::Identifier,
+                "string".to_string(),
+                "string".to_string(),
+                1,
+            ),
+            Token::new(TokenType::Dot, ".".to_string(), ".".to_string(), 1),
+            Token::new(
+                TokenType::Identifier,
+                "to_upper".to_string(),
+                "to_upper".to_string(),
+                1,
+            ),
+            Token::new(TokenType::LeftParen, "(".to_string(), "(".to_string(), 1),
+            Token::new(
+                TokenType::String,
+                "héllo".to_string(),
+                "héllo".to_string(),
+                1,
+            ),
+            Token::new(TokenType::RightParen, ")".to_string(), ")".to_string(), 1),
+        ];
+        assert_eq!(tokens, expected);
+        assert!(!parser.in_error);
+    }
+
+    #[test]
+    fn it_reads_floats_without_breaking_ranges() {
+        let source = "[0..=2.5]";
+        let mut parser = Parser::new();
+
+        let tokens = Scanner::new(source.to_string()).scan(&mut parser);
+
+        let expected = vec![
+            Token::new(TokenType::LeftBracket, "[".to_string(), "[".to_string(), 1),
+            Token::new(TokenType::Number, "0".to_string(), "0".to_string(), 1),
+            Token::new(
+                TokenType::DotDotEqual,
+                "..=".to_string(),
+                "..=".to_string(),
+                1,
+            ),
+            Token::new(TokenType::Number, "2.5".to_string(), "2.5".to_string(), 1),
+            Token::new(TokenType::RightBracket, "]".to_string(), "]".to_string(), 1),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn it_reads_doubled_operators() {
+        let source = "// ** % / *";
+        let mut parser = Parser::new();
+
+        let tokens = Scanner::new(source.to_string()).scan(&mut parser);
+
+        let expected = vec![
+            Token::new(TokenType::SlashSlash, "//".to_string(), "//".to_string(), 1),
+            Token::new(TokenType::StarStar, "**".to_string(), "**".to_string(), 1),
+            Token::new(TokenType::Percent, "%".to_string(), "%".to_string(), 1),
+            Token::new(TokenType::Slash, "/".to_string(), "/".to_string(), 1),
+            Token::new(TokenType::Star, "*".to_string(), "*".to_string(), 1),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn it_reads_bitwise_operators() {
+        let source = "& | ^ ~ << >> <=";
+        let mut parser = Parser::new();
+
+        let tokens = Scanner::new(source.to_string()).scan(&mut parser);
+
+        let expected = vec![
+            Token::new(TokenType::Ampersand, "&".to_string(), "&".to_string(), 1),
+            Token::new(TokenType::Pipe, "|".to_string(), "|".to_string(), 1),
+            Token::new(TokenType::Caret, "^".to_string(), "^".to_string(), 1),
+            Token::new(TokenType::Tilde, "~".to_string(), "~".to_string(), 1),
+            Token::new(TokenType::LessLess, "<<".to_string(), "<<".to_string(), 1),
+            Token::new(
+                TokenType::GreaterGreater,
+                ">>".to_string(),
+                ">>".to_string(),
+                1,
+            ),
+            Token::new(TokenType::LessEqual, "<=".to_string(), "<=".to_string(), 1),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn it_reads_type_declarations_and_matches() {
+        let source = "enum match x => y";
+        let mut parser = Parser::new();
+
+        let tokens = Scanner::new(source.to_string()).scan(&mut parser);
+
+        let expected = vec![
+            Token::new(TokenType::Enum, "enum".to_string(), "enum".to_string(), 1),
+            Token::new(TokenType::Match, "match".to_string(), "match".to_string(), 1),
+            Token::new(TokenType::Identifier, "x".to_string(), "x".to_string(), 1),
+            Token::new(TokenType::FatArrow, "=>".to_string(), "=>".to_string(), 1),
+            Token::new(TokenType::Identifier, "y".to_string(), "y".to_string(), 1),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn it_reads_compound_assignments() {
+        let source = "x += 1 -= *= /=";
+        let mut parser = Parser::new();
+
+        let tokens = Scanner::new(source.to_string()).scan(&mut parser);
+
+        let expected = vec![
+            Token::new(TokenType::Identifier, "x".to_string(), "x".to_string(), 1),
+            Token::new(TokenType::PlusEqual, "+=".to_string(), "+=".to_string(), 1),
+            Token::new(TokenType::Number, "1".to_string(), "1".to_string(), 1),
+            Token::new(TokenType::MinusEqual, "-=".to_string(), "-=".to_string(), 1),
+            Token::new(TokenType::StarEqual, "*=".to_string(), "*=".to_string(), 1),
+            Token::new(TokenType::SlashEqual, "/=".to_string(), "/=".to_string(), 1),
+        ];
+        assert_eq!(tokens, expected);
+    }
 }
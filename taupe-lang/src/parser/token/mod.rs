@@ -30,8 +30,24 @@ pub enum TokenType {
     // SEMICOLON,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    Colon,
 
     // -- One or two character tokens.
+    SlashSlash,
+    StarStar,
+    LessLess,
+    GreaterGreater,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    Arrow,
+    FatArrow,
     Bang,
     BangEqual,
     Equal,
@@ -51,7 +67,7 @@ pub enum TokenType {
     // CLASS,
     Else,
     False,
-    // FUN,
+    Fn,
     For,
     In,
     If,
@@ -63,6 +79,10 @@ pub enum TokenType {
     // THIS,
     True,
     Let,
+    Mut,
+    Struct,
+    Enum,
+    Match,
     // WHILE,
 
     // EOF,
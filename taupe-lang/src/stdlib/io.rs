@@ -0,0 +1,45 @@
+use crate::core::{
+    options::Options,
+    types::Type,
+    values::{Error, Value},
+};
+
+use super::Builtin;
+
+pub fn lookup(name: &str) -> Option<Builtin> {
+    match name {
+        "input" => Some(input),
+        "read_all" => Some(read_all),
+        _ => None,
+    }
+}
+
+// `input` takes a prompt or not, so only `read_all` has a signature.
+pub fn signature(name: &str) -> Option<(Vec<Type>, Type)> {
+    match name {
+        "read_all" => Some((Vec::new(), Type::Str)),
+        _ => None,
+    }
+}
+
+// Reads a line, after writing the prompt when there is one, or yields None
+// at the end of the input.
+fn input(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [] => {}
+        [Value::String(prompt)] => options.output.prompt(prompt),
+        _ => return Err(Error::InvalidValues("input".to_string(), arguments)),
+    }
+    Ok(match options.input.read_line() {
+        Some(line) => Value::String(line),
+        None => Value::None,
+    })
+}
+
+// Reads the rest of the input, such as all of a piped stdin.
+fn read_all(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [] => Ok(Value::String(options.input.read_all())),
+        _ => Err(Error::InvalidValues("read_all".to_string(), arguments)),
+    }
+}
@@ -0,0 +1,318 @@
+use std::f64::consts;
+
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed};
+
+use crate::core::{
+    options::Options,
+    types::Type,
+    values::{Error, Value},
+};
+
+use super::Builtin;
+
+pub fn lookup(name: &str) -> Option<Builtin> {
+    match name {
+        "abs" => Some(abs),
+        "min" => Some(min),
+        "max" => Some(max),
+        "pow" => Some(pow),
+        "sqrt" => Some(sqrt),
+        "floor" => Some(floor),
+        "ceil" => Some(ceil),
+        "round" => Some(round),
+        "sin" => Some(sin),
+        "cos" => Some(cos),
+        "tan" => Some(tan),
+        "asin" => Some(asin),
+        "acos" => Some(acos),
+        "atan" => Some(atan),
+        "atan2" => Some(atan2),
+        _ => None,
+    }
+}
+
+pub fn signature(name: &str) -> Option<(Vec<Type>, Type)> {
+    let signature = match name {
+        "abs" => (vec![Type::Unknown], Type::Unknown),
+        "min" | "max" => (vec![Type::Unknown, Type::Unknown], Type::Unknown),
+        "pow" | "atan2" => (vec![Type::Unknown, Type::Unknown], Type::Float),
+        "floor" | "ceil" | "round" => (vec![Type::Unknown], Type::Int),
+        "sqrt" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan" => {
+            (vec![Type::Unknown], Type::Float)
+        }
+        _ => return None,
+    };
+    Some(signature)
+}
+
+pub fn constant(name: &str) -> Option<Value> {
+    match name {
+        "pi" => Some(Value::Float(consts::PI)),
+        "e" => Some(Value::Float(consts::E)),
+        _ => None,
+    }
+}
+
+fn invalid(name: &str, arguments: Vec<Value>) -> Result<Value, Error> {
+    Err(Error::InvalidValues(format!("math.{}", name), arguments))
+}
+
+// Applies a float function to a single numeric argument.
+fn unary(name: &str, arguments: Vec<Value>, function: fn(f64) -> f64) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [value] => match value.as_float() {
+            Some(value) => Ok(Value::Float(function(value))),
+            None => invalid(name, arguments),
+        },
+        _ => invalid(name, arguments),
+    }
+}
+
+// Fits an integer result to the overflow mode of the options.
+fn fit(
+    name: &str,
+    arguments: Vec<Value>,
+    value: BigInt,
+    options: &Options,
+) -> Result<Value, Error> {
+    match options.overflow.fit(value) {
+        Some(value) => Ok(value),
+        None => Err(Error::IntegerOverflow(format!("math.{}", name), arguments)),
+    }
+}
+
+// Rounds a numeric argument to an integer, failing on NaN and infinities.
+fn integral(
+    name: &str,
+    arguments: Vec<Value>,
+    options: &Options,
+    function: fn(f64) -> f64,
+) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::Integer(_)] | [Value::BigInteger(_)] => Ok(arguments[0].clone()),
+        [Value::Float(value)] => match BigInt::from_f64(function(*value)) {
+            Some(rounded) => fit(name, arguments, rounded, options),
+            None => invalid(name, arguments),
+        },
+        _ => invalid(name, arguments),
+    }
+}
+
+fn abs(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::Float(value)] => Ok(Value::Float(value.abs())),
+        [value] => match value.as_big_integer() {
+            Some(value) => fit("abs", arguments, value.abs(), options),
+            None => invalid("abs", arguments),
+        },
+        _ => invalid("abs", arguments),
+    }
+}
+
+fn min(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [left, right] => match (left.as_big_integer(), right.as_big_integer()) {
+            (Some(left), Some(right)) => Ok(Value::from(left.min(right))),
+            _ => match (left.as_float(), right.as_float()) {
+                (Some(left), Some(right)) => Ok(Value::Float(left.min(right))),
+                _ => invalid("min", arguments),
+            },
+        },
+        _ => invalid("min", arguments),
+    }
+}
+
+fn max(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [left, right] => match (left.as_big_integer(), right.as_big_integer()) {
+            (Some(left), Some(right)) => Ok(Value::from(left.max(right))),
+            _ => match (left.as_float(), right.as_float()) {
+                (Some(left), Some(right)) => Ok(Value::Float(left.max(right))),
+                _ => invalid("max", arguments),
+            },
+        },
+        _ => invalid("max", arguments),
+    }
+}
+
+fn pow(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [base, exponent] => match (base.as_float(), exponent.as_float()) {
+            (Some(base), Some(exponent)) => Ok(Value::Float(base.powf(exponent))),
+            _ => invalid("pow", arguments),
+        },
+        _ => invalid("pow", arguments),
+    }
+}
+
+fn sqrt(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [value] if value.as_float().is_some_and(|value| value < 0.0) => invalid("sqrt", arguments),
+        _ => unary("sqrt", arguments, f64::sqrt),
+    }
+}
+
+fn floor(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    integral("floor", arguments, options, f64::floor)
+}
+
+fn ceil(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    integral("ceil", arguments, options, f64::ceil)
+}
+
+fn round(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    integral("round", arguments, options, f64::round)
+}
+
+fn sin(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    unary("sin", arguments, f64::sin)
+}
+
+fn cos(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    unary("cos", arguments, f64::cos)
+}
+
+fn tan(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    unary("tan", arguments, f64::tan)
+}
+
+fn asin(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    unary("asin", arguments, f64::asin)
+}
+
+fn acos(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    unary("acos", arguments, f64::acos)
+}
+
+fn atan(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    unary("atan", arguments, f64::atan)
+}
+
+fn atan2(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [y, x] => match (y.as_float(), x.as_float()) {
+            (Some(y), Some(x)) => Ok(Value::Float(y.atan2(x))),
+            _ => invalid("atan2", arguments),
+        },
+        _ => invalid("atan2", arguments),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::options::Overflow;
+
+    #[test]
+    fn it_keeps_integers_when_both_sides_are_integers() {
+        assert_eq!(
+            min(
+                vec![Value::Integer(3), Value::Integer(-2)],
+                &Options::default()
+            ),
+            Ok(Value::Integer(-2))
+        );
+        assert_eq!(
+            abs(vec![Value::Integer(-7)], &Options::default()),
+            Ok(Value::Integer(7))
+        );
+        assert_eq!(
+            abs(vec![Value::Integer(i64::MIN)], &Options::default()),
+            Ok(Value::BigInteger(-BigInt::from(i64::MIN)))
+        );
+    }
+
+    #[test]
+    fn it_promotes_mixed_arguments_to_floats() {
+        assert_eq!(
+            max(
+                vec![Value::Integer(3), Value::Float(3.5)],
+                &Options::default()
+            ),
+            Ok(Value::Float(3.5))
+        );
+        assert_eq!(
+            pow(
+                vec![Value::Integer(2), Value::Integer(10)],
+                &Options::default()
+            ),
+            Ok(Value::Float(1024.0))
+        );
+    }
+
+    #[test]
+    fn it_rounds_floats_to_integers() {
+        assert_eq!(
+            floor(vec![Value::Float(-1.5)], &Options::default()),
+            Ok(Value::Integer(-2))
+        );
+        assert_eq!(
+            ceil(vec![Value::Float(1.2)], &Options::default()),
+            Ok(Value::Integer(2))
+        );
+        assert_eq!(
+            round(vec![Value::Float(2.5)], &Options::default()),
+            Ok(Value::Integer(3))
+        );
+        assert_eq!(
+            round(vec![Value::Float(1e19)], &Options::default()),
+            Ok(Value::BigInteger(BigInt::from(
+                10_000_000_000_000_000_000u64
+            )))
+        );
+        assert!(round(vec![Value::Float(f64::NAN)], &Options::default()).is_err());
+    }
+
+    #[test]
+    fn it_fits_integer_results_to_the_overflow_mode() {
+        let with = |overflow| Options {
+            overflow,
+            ..Options::default()
+        };
+
+        assert!(matches!(
+            abs(vec![Value::Integer(i64::MIN)], &with(Overflow::Checked)),
+            Err(Error::IntegerOverflow(..))
+        ));
+        assert_eq!(
+            abs(vec![Value::Integer(i64::MIN)], &with(Overflow::Saturating)),
+            Ok(Value::Integer(i64::MAX))
+        );
+        assert_eq!(
+            round(vec![Value::Float(1e19)], &with(Overflow::Wrapping)),
+            Ok(Value::Integer(-8446744073709551616))
+        );
+    }
+
+    #[test]
+    fn it_refuses_the_square_root_of_negative_numbers() {
+        assert_eq!(
+            sqrt(vec![Value::Integer(9)], &Options::default()),
+            Ok(Value::Float(3.0))
+        );
+        assert!(sqrt(vec![Value::Integer(-1)], &Options::default()).is_err());
+    }
+
+    #[test]
+    fn it_computes_trigonometry_in_radians() {
+        assert_eq!(
+            cos(vec![Value::Integer(0)], &Options::default()),
+            Ok(Value::Float(1.0))
+        );
+        assert_eq!(
+            atan2(
+                vec![Value::Integer(1), Value::Integer(1)],
+                &Options::default()
+            ),
+            Ok(Value::Float(consts::FRAC_PI_4))
+        );
+    }
+
+    #[test]
+    fn it_exposes_pi_and_e() {
+        assert_eq!(constant("pi"), Some(Value::Float(consts::PI)));
+        assert_eq!(constant("e"), Some(Value::Float(consts::E)));
+        assert_eq!(constant("tau"), None);
+    }
+}
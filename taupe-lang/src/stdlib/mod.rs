@@ -0,0 +1,41 @@
+pub mod io;
+pub mod math;
+pub mod string;
+
+use crate::core::{
+    options::Options,
+    types::Type,
+    values::{Error, Value},
+};
+
+// Builtins get the options of the run, for its overflow mode and its input.
+pub type Builtin = fn(Vec<Value>, &Options) -> Result<Value, Error>;
+
+// The builtins reading input go without a module prefix.
+pub fn lookup(function: &str) -> Option<Builtin> {
+    match function.split_once('.') {
+        Some(("math", name)) => math::lookup(name),
+        Some(("string", name)) => string::lookup(name),
+        Some(_) => None,
+        None => io::lookup(function),
+    }
+}
+
+// The types of a builtin's parameters and of its result. A parameter that
+// takes either kind of number, and a result that depends on it or may be
+// None, are Unknown. Builtins taking a varying number of arguments have none.
+pub fn signature(function: &str) -> Option<(Vec<Type>, Type)> {
+    match function.split_once('.') {
+        Some(("math", name)) => math::signature(name),
+        Some(("string", name)) => string::signature(name),
+        Some(_) => None,
+        None => io::signature(function),
+    }
+}
+
+pub fn constant(name: &str) -> Option<Value> {
+    match name.split_once('.') {
+        Some(("math", name)) => math::constant(name),
+        _ => None,
+    }
+}
@@ -0,0 +1,296 @@
+use crate::core::{
+    limits,
+    options::Options,
+    types::Type,
+    values::{Error, Value},
+};
+
+use super::Builtin;
+
+pub fn lookup(name: &str) -> Option<Builtin> {
+    match name {
+        "len" => Some(len),
+        "upper" => Some(upper),
+        "lower" => Some(lower),
+        "trim" => Some(trim),
+        "split" => Some(split),
+        "join" => Some(join),
+        "replace" => Some(replace),
+        "contains" => Some(contains),
+        "starts_with" => Some(starts_with),
+        "find" => Some(find),
+        "repeat" => Some(repeat),
+        _ => None,
+    }
+}
+
+pub fn signature(name: &str) -> Option<(Vec<Type>, Type)> {
+    let strings = Type::List(Box::new(Type::Str));
+    let signature = match name {
+        "len" => (vec![Type::Str], Type::Int),
+        "upper" | "lower" | "trim" => (vec![Type::Str], Type::Str),
+        "split" => (vec![Type::Str, Type::Str], strings),
+        "join" => (vec![strings, Type::Str], Type::Str),
+        "replace" => (vec![Type::Str, Type::Str, Type::Str], Type::Str),
+        "contains" | "starts_with" => (vec![Type::Str, Type::Str], Type::Bool),
+        "find" => (vec![Type::Str, Type::Str], Type::Unknown),
+        "repeat" => (vec![Type::Str, Type::Int], Type::Str),
+        _ => return None,
+    };
+    Some(signature)
+}
+
+fn invalid(name: &str, arguments: Vec<Value>) -> Result<Value, Error> {
+    Err(Error::InvalidValues(format!("string.{}", name), arguments))
+}
+
+// Lengths and positions count characters, not bytes.
+fn len(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value)] => Ok(Value::Integer(value.chars().count() as i64)),
+        _ => invalid("len", arguments),
+    }
+}
+
+fn upper(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value)] => Ok(Value::String(value.to_uppercase())),
+        _ => invalid("upper", arguments),
+    }
+}
+
+fn lower(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value)] => Ok(Value::String(value.to_lowercase())),
+        _ => invalid("lower", arguments),
+    }
+}
+
+fn trim(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value)] => Ok(Value::String(value.trim().to_string())),
+        _ => invalid("trim", arguments),
+    }
+}
+
+// An empty separator splits the string into its characters.
+fn split(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value), Value::String(separator)] if separator.is_empty() => {
+            Ok(Value::List(
+                value
+                    .chars()
+                    .map(|c| Value::String(c.to_string()))
+                    .collect(),
+            ))
+        }
+        [Value::String(value), Value::String(separator)] => Ok(Value::List(
+            value
+                .split(separator.as_str())
+                .map(|part| Value::String(part.to_string()))
+                .collect(),
+        )),
+        _ => invalid("split", arguments),
+    }
+}
+
+fn join(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::List(values), Value::String(separator)] => {
+            let mut parts = Vec::new();
+            for value in values {
+                match value {
+                    Value::String(value) => parts.push(value.as_str()),
+                    _ => return invalid("join", arguments.clone()),
+                }
+            }
+            Ok(Value::String(parts.join(separator)))
+        }
+        _ => invalid("join", arguments),
+    }
+}
+
+fn replace(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value), Value::String(from), Value::String(to)] if !from.is_empty() => {
+            let matches = value.matches(from.as_str()).count();
+            limits::reserve(
+                (value.len() - matches * from.len())
+                    .saturating_add(matches.saturating_mul(to.len())),
+                options,
+            )?;
+            Ok(Value::String(value.replace(from.as_str(), to)))
+        }
+        _ => invalid("replace", arguments),
+    }
+}
+
+fn contains(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value), Value::String(pattern)] => {
+            Ok(Value::Boolean(value.contains(pattern.as_str())))
+        }
+        _ => invalid("contains", arguments),
+    }
+}
+
+fn starts_with(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value), Value::String(prefix)] => {
+            Ok(Value::Boolean(value.starts_with(prefix.as_str())))
+        }
+        _ => invalid("starts_with", arguments),
+    }
+}
+
+// Yields the character index of the first match, or None.
+fn find(arguments: Vec<Value>, _: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value), Value::String(pattern)] => match value.find(pattern.as_str()) {
+            Some(byte_index) => Ok(Value::Integer(value[..byte_index].chars().count() as i64)),
+            None => Ok(Value::None),
+        },
+        _ => invalid("find", arguments),
+    }
+}
+
+fn repeat(arguments: Vec<Value>, options: &Options) -> Result<Value, Error> {
+    match arguments.as_slice() {
+        [Value::String(value), Value::Integer(count)] if *count >= 0 => {
+            // A string longer than any allocation fails rather than aborts.
+            match value.len().checked_mul(*count as usize) {
+                Some(length) if length <= isize::MAX as usize => {
+                    limits::reserve(length, options)?;
+                    Ok(Value::String(value.repeat(*count as usize)))
+                }
+                _ => invalid("repeat", arguments),
+            }
+        }
+        _ => invalid("repeat", arguments),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    #[test]
+    fn it_counts_characters_instead_of_bytes() {
+        assert_eq!(
+            len(vec![string("héllo")], &Options::default()),
+            Ok(Value::Integer(5))
+        );
+        assert_eq!(
+            len(vec![string("日本語")], &Options::default()),
+            Ok(Value::Integer(3))
+        );
+    }
+
+    #[test]
+    fn it_changes_case_of_non_ascii_letters() {
+        assert_eq!(
+            upper(vec![string("straße")], &Options::default()),
+            Ok(string("STRASSE"))
+        );
+        assert_eq!(
+            lower(vec![string("ÉCOLE")], &Options::default()),
+            Ok(string("école"))
+        );
+    }
+
+    #[test]
+    fn it_trims_unicode_whitespace() {
+        assert_eq!(
+            trim(vec![string("\u{3000} taupe \t")], &Options::default()),
+            Ok(string("taupe"))
+        );
+    }
+
+    #[test]
+    fn it_splits_and_joins_back() {
+        let parts = split(vec![string("a,é,c"), string(",")], &Options::default()).unwrap();
+
+        assert_eq!(
+            parts,
+            Value::List(vec![string("a"), string("é"), string("c")])
+        );
+        assert_eq!(
+            join(vec![parts, string("-")], &Options::default()),
+            Ok(string("a-é-c"))
+        );
+    }
+
+    #[test]
+    fn it_splits_into_characters_with_an_empty_separator() {
+        assert_eq!(
+            split(vec![string("né"), string("")], &Options::default()),
+            Ok(Value::List(vec![string("n"), string("é")]))
+        );
+    }
+
+    #[test]
+    fn it_replaces_every_occurrence() {
+        assert_eq!(
+            replace(
+                vec![string("à la la"), string("la"), string("lo")],
+                &Options::default()
+            ),
+            Ok(string("à lo lo"))
+        );
+    }
+
+    #[test]
+    fn it_finds_the_character_index() {
+        assert_eq!(
+            find(vec![string("ça va"), string("va")], &Options::default()),
+            Ok(Value::Integer(3))
+        );
+        assert_eq!(
+            find(vec![string("ça va"), string("non")], &Options::default()),
+            Ok(Value::None)
+        );
+    }
+
+    #[test]
+    fn it_checks_for_substrings_and_prefixes() {
+        assert_eq!(
+            contains(vec![string("taupe"), string("au")], &Options::default()),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            starts_with(vec![string("taupe"), string("au")], &Options::default()),
+            Ok(Value::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn it_refuses_to_repeat_a_negative_number_of_times() {
+        assert_eq!(
+            repeat(vec![string("ab"), Value::Integer(2)], &Options::default()),
+            Ok(string("abab"))
+        );
+        assert!(repeat(vec![string("ab"), Value::Integer(-1)], &Options::default()).is_err());
+    }
+
+    #[test]
+    fn it_refuses_to_repeat_past_the_largest_string() {
+        assert!(matches!(
+            repeat(
+                vec![string("ab"), Value::Integer(i64::MAX)],
+                &Options::default()
+            ),
+            Err(Error::InvalidValues(..))
+        ));
+        assert_eq!(
+            repeat(
+                vec![string(""), Value::Integer(i64::MAX)],
+                &Options::default()
+            ),
+            Ok(string(""))
+        );
+    }
+}
@@ -1,13 +1,20 @@
-use std::rc::Rc;
+use std::{iter::Peekable, rc::Rc, vec::IntoIter};
 
 use crate::{
     core::{
-        expression::{Expression, ExpressionRef, Operand},
+        expression::{
+            Constructor, Declaration, Expression, ExpressionRef, Function, Operand, Pattern,
+            Variable,
+        },
         primitives::Primitive,
+        stack,
+        types::Type,
     },
     parser::token::{Token, TokenType},
 };
 
+type Tokens = Peekable<IntoIter<Token>>;
+
 pub struct Translator {}
 
 impl Translator {
@@ -18,245 +25,652 @@ impl Translator {
     }
 
     pub fn tree(tokens: Vec<Token>) -> Expression {
-        let mut it = tokens.clone().into_iter();
+        let mut it = tokens.into_iter().peekable();
 
-        let token = match it.next() {
-            Some(val) => val,
-            None => {
-                return Expression::Primitive(Primitive::ToReplace);
+        Translator::statements(&mut it)
+    }
+
+    // A sequence of statements up to the end of the current block, each
+    // wrapped in the line it starts on. A `let`, `fn`, `struct` or `enum`
+    // scopes over every statement that follows it. A `let` may bind several comma-separated
+    // patterns: `let a = 1, (b, c) = pair`.
+    fn statements(it: &mut Tokens) -> Expression {
+        stack::grow(|| Translator::statement(it))
+    }
+
+    fn statement(it: &mut Tokens) -> Expression {
+        Translator::skip_lines(it);
+
+        let (token_type, line) = match it.peek() {
+            Some(token) if token.token_type != TokenType::RightBrace => {
+                (token.token_type.clone(), token.line)
             }
+            _ => return Expression::None {},
         };
 
-        let expression = match token.token_type {
-            TokenType::Plus => {
-                let right = it.next().unwrap();
-                let left = it.next().unwrap();
-                Expression::Sum {
-                    left: Rc::new(Translator::tree(vec![left])),
-                    right: Rc::new(Translator::tree(vec![right])),
-                }
-            }
-            TokenType::Star => {
-                let right = it.next().unwrap();
-                let left = it.next().unwrap();
-                Expression::Product {
-                    left: Rc::new(Translator::tree(vec![left])),
-                    right: Rc::new(Translator::tree(vec![right])),
-                }
-            }
-            TokenType::Minus => {
-                let right = it.next().unwrap();
-                let left = it.next().unwrap();
-                Expression::Subtract {
-                    left: Rc::new(Translator::tree(vec![left])),
-                    right: Rc::new(Translator::tree(vec![right])),
-                }
-            }
-            TokenType::Slash => {
-                let left = it.next().unwrap();
-                let right = it.next().unwrap();
-                Expression::Divide {
-                    left: Rc::new(Translator::tree(vec![left])),
-                    right: Rc::new(Translator::tree(vec![right])),
-                }
-            }
-            TokenType::Print => {
-                let mut expression_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    if token.token_type == TokenType::EOL {
-                        break;
-                    }
-                    expression_tokens.push(token);
+        if token_type == TokenType::Let {
+            it.next();
+            let mut variables = Vec::new();
+            loop {
+                let pattern = Translator::pattern(it);
+                Translator::expect(it, TokenType::Equal);
+                variables.push((pattern, Translator::operand(it)));
+                if !Translator::expect(it, TokenType::Comma) {
+                    break;
                 }
-                Expression::Print {
-                    expression: Rc::new(Translator::tree(expression_tokens)),
-                }
-            }
-            TokenType::String => {
-                let right = token;
-                Expression::Primitive(Primitive::String(right.lexeme.clone()))
             }
-            TokenType::Number => {
-                let right = token;
-                Expression::Primitive(Primitive::Integer(right.lexeme.parse::<i32>().unwrap()))
-            }
-            TokenType::True => Expression::Primitive(Primitive::Boolean(true)),
-            TokenType::False => Expression::Primitive(Primitive::Boolean(false)),
+
+            let scope = Rc::new(Translator::statements(it));
+            return Translator::line(line, Expression::Let { variables, scope });
+        }
+
+        if token_type == TokenType::Fn {
+            it.next();
+            let function = Translator::function(it, line);
+
+            let scope = Rc::new(Translator::statements(it));
+            return Translator::line(
+                line,
+                Expression::Function {
+                    function: Rc::new(function),
+                    scope,
+                },
+            );
+        }
+
+        if token_type == TokenType::Struct || token_type == TokenType::Enum {
+            it.next();
+            let declaration = Translator::declaration(it, token_type == TokenType::Enum);
+
+            let scope = Rc::new(Translator::statements(it));
+            return Translator::line(
+                line,
+                Expression::Declare {
+                    declaration: Rc::new(declaration),
+                    scope,
+                },
+            );
+        }
+
+        let statement = Translator::line(line, Translator::expression(it));
+
+        Translator::skip_lines(it);
+        match it.peek() {
+            Some(token) if token.token_type != TokenType::RightBrace => Expression::Chain {
+                left: Rc::new(statement),
+                right: Rc::new(Translator::statements(it)),
+            },
+            _ => statement,
+        }
+    }
+
+    fn line(line: usize, statement: Expression) -> Expression {
+        Expression::Line {
+            line,
+            statement: Rc::new(statement),
+        }
+    }
+
+    fn expression(it: &mut Tokens) -> Expression {
+        stack::grow(|| Translator::term(it))
+    }
+
+    // Operators take their operands in the order they are written, so
+    // `- 5 3` is 2 and `+ "a" "b"` is "ab", the same way `/` and `<` read.
+    fn term(it: &mut Tokens) -> Expression {
+        let token = match it.next() {
+            Some(val) => val,
+            None => return Expression::None {},
+        };
+
+        if let Some(literal) = Translator::literal(&token) {
+            return Expression::Primitive(literal);
+        }
+
+        match token.token_type {
+            TokenType::Plus => Expression::Sum {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::Star => Expression::Product {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::Minus => Expression::Subtract {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::Slash => Expression::Divide {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::SlashSlash => Expression::IntegerDivide {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::Percent => Expression::Modulo {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::StarStar => Expression::Power {
+                left: Translator::operand(it),
+                right: Translator::operand(it),
+            },
+            TokenType::Greater => Expression::Compare {
+                left: Translator::operand(it),
+                operand: Operand::GreaterThan,
+                right: Translator::operand(it),
+            },
+            TokenType::Less => Expression::Compare {
+                left: Translator::operand(it),
+                operand: Operand::LessThan,
+                right: Translator::operand(it),
+            },
+            TokenType::Ampersand => Translator::bitwise(it, Operand::BitAnd),
+            TokenType::Pipe => Translator::bitwise(it, Operand::BitOr),
+            TokenType::Caret => Translator::bitwise(it, Operand::BitXor),
+            TokenType::LessLess => Translator::bitwise(it, Operand::ShiftLeft),
+            TokenType::GreaterGreater => Translator::bitwise(it, Operand::ShiftRight),
+            TokenType::Bang => Expression::Unary {
+                operand: Operand::Not,
+                right: Translator::operand(it),
+            },
+            TokenType::Tilde => Expression::Unary {
+                operand: Operand::Complement,
+                right: Translator::operand(it),
+            },
+            TokenType::Print => Expression::Print {
+                expression: Translator::operand(it),
+            },
+            // `( e )` only groups; a comma makes a tuple: `(e,)`, `(a, b)`.
             TokenType::LeftParen => {
-                let mut expression_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    expression_tokens.push(token.clone());
-                    if token.token_type == TokenType::RightParen {
-                        break;
+                let first = Translator::expression(it);
+                let expression = if Translator::expect(it, TokenType::Comma) {
+                    let mut elements = vec![Rc::new(first)];
+                    while !Translator::expect(it, TokenType::RightParen) && it.peek().is_some() {
+                        elements.push(Translator::operand(it));
+                        Translator::expect(it, TokenType::Comma);
                     }
-                }
-                return Translator::tree(expression_tokens);
+                    Expression::Tuple { elements }
+                } else {
+                    Translator::expect(it, TokenType::RightParen);
+                    first
+                };
+                Translator::indexes(it, expression)
             }
             TokenType::LeftBrace => {
-                let mut expression_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    expression_tokens.push(token.clone());
-                    if token.token_type == TokenType::RightBrace {
-                        break;
-                    }
+                let body = Translator::statements(it);
+                Translator::expect(it, TokenType::RightBrace);
+                Expression::Block {
+                    body: Rc::new(body),
                 }
-                return Translator::tree(expression_tokens);
             }
-            TokenType::Bang => Expression::Unary {
-                operand: Operand::Not,
-                right: Rc::new(Translator::tree(vec![it.next().unwrap()])),
-            },
             TokenType::If => {
-                let mut condition_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    condition_tokens.push(token.clone());
-                    if token.token_type == TokenType::RightParen {
-                        break;
-                    }
-                }
-                let mut then_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    if token.token_type == TokenType::Else {
-                        break;
-                    }
-                    then_tokens.push(token.clone());
-                }
-                let mut else_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    else_tokens.push(token.clone());
-                    if token.token_type == TokenType::RightBrace {
-                        break;
-                    }
-                }
+                // if <condition> { <then> } else { <else> }
+                let condition = Translator::operand(it);
+                let then_branch = Translator::body(it);
+
+                Translator::skip_lines(it);
+                let else_branch = if Translator::expect(it, TokenType::Else) {
+                    Translator::body(it)
+                } else {
+                    Rc::new(Expression::None {})
+                };
 
                 Expression::If {
-                    condition: Rc::new(Translator::tree(condition_tokens)),
-                    then_branch: Rc::new(Translator::tree(then_tokens)),
-                    else_branch: Rc::new(Translator::tree(else_tokens)),
+                    condition,
+                    then_branch,
+                    else_branch,
                 }
             }
             TokenType::For => {
                 // for <var> in [<from>..<to>] { <body> }
+                let variable = Variable::new(Translator::name(it));
 
-                let variable_name = it.next().unwrap().lexeme;
+                Translator::expect(it, TokenType::In);
+                Translator::expect(it, TokenType::LeftBracket);
 
-                it.next().unwrap(); // skip 'in'
-                it.next().unwrap(); // skip '['
+                let from = Translator::operand(it);
+                let inclusive = Translator::expect(it, TokenType::DotDotEqual);
+                if !inclusive {
+                    Translator::expect(it, TokenType::DotDot);
+                }
+                let mut to = Translator::operand(it);
+                if !inclusive {
+                    to = Rc::new(Expression::Subtract {
+                        left: to,
+                        right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
+                    });
+                }
 
-                let mut from_tokens = Vec::new();
-                let mut include_from = true;
+                Translator::expect(it, TokenType::RightBracket);
 
-                while let Some(token) = it.next() {
-                    if token.clone().token_type == TokenType::DotDot {
-                        include_from = false;
-                        break;
-                    }
-                    if token.clone().token_type == TokenType::DotDotEqual {
+                Expression::For {
+                    variable,
+                    from,
+                    to,
+                    body: Translator::body(it),
+                }
+            }
+            TokenType::Match => {
+                // match <value> { <pattern> => <body> ... }
+                let value = Translator::operand(it);
+                Translator::skip_lines(it);
+                Translator::expect(it, TokenType::LeftBrace);
+
+                let mut arms = Vec::new();
+                loop {
+                    Translator::skip_lines(it);
+                    if Translator::expect(it, TokenType::RightBrace) || it.peek().is_none() {
                         break;
                     }
-                    from_tokens.push(token.clone());
+                    let pattern = Translator::pattern(it);
+                    Translator::expect(it, TokenType::FatArrow);
+                    arms.push((pattern, Translator::body(it)));
+                    Translator::expect(it, TokenType::Comma);
                 }
 
-                let mut to_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    if token.clone().token_type == TokenType::RightBracket {
-                        break;
+                Expression::Match { value, arms }
+            }
+            TokenType::Identifier => {
+                let mut path = token.lexeme;
+                while Translator::expect(it, TokenType::Dot) {
+                    if it
+                        .peek()
+                        .is_some_and(|token| token.token_type == TokenType::Number)
+                    {
+                        let tuple = Expression::Use {
+                            variable: Variable::new(path),
+                        };
+                        let indexed = Translator::index(it, tuple);
+                        return Translator::indexes(it, indexed);
                     }
-                    to_tokens.push(token.clone());
+                    path.push('.');
+                    path.push_str(&Translator::name(it));
                 }
 
-                let mut body_tokens = Vec::new();
-                while let Some(token) = it.next() {
-                    body_tokens.push(token.clone());
-                    if token.clone().token_type == TokenType::RightBrace {
-                        break;
+                let token_type = it.peek().map(|token| token.token_type.clone());
+                match token_type {
+                    Some(TokenType::LeftParen) => {
+                        it.next();
+                        Expression::Call {
+                            function: path,
+                            definition: None,
+                            arguments: Translator::arguments(it),
+                        }
                     }
+                    Some(TokenType::Equal) => {
+                        it.next();
+                        Expression::Assign {
+                            variable: Variable::new(path),
+                            value: Translator::operand(it),
+                        }
+                    }
+                    Some(TokenType::PlusEqual)
+                    | Some(TokenType::MinusEqual)
+                    | Some(TokenType::StarEqual)
+                    | Some(TokenType::SlashEqual) => {
+                        it.next();
+                        Translator::compound_assign(
+                            path,
+                            token_type.unwrap(),
+                            Translator::operand(it),
+                        )
+                    }
+                    _ => Translator::indexes(
+                        it,
+                        Expression::Use {
+                            variable: Variable::new(path),
+                        },
+                    ),
                 }
+            }
+            _ => Expression::None {},
+        }
+    }
 
-                let to = if include_from {
-                    Rc::new(Translator::tree(to_tokens))
-                } else {
-                    Rc::new(Expression::Subtract {
-                        left: Rc::new(Translator::tree(from_tokens.clone())),
-                        right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
-                    })
-                };
+    // `x += y` is sugar for `x = + x y`, and likewise for the other operators.
+    fn compound_assign(
+        variable: String,
+        token_type: TokenType,
+        right: ExpressionRef,
+    ) -> Expression {
+        let variable = Variable::new(variable);
+        let left = Rc::new(Expression::Use {
+            variable: variable.clone(),
+        });
+        let value = match token_type {
+            TokenType::PlusEqual => Expression::Sum { left, right },
+            TokenType::MinusEqual => Expression::Subtract { left, right },
+            TokenType::StarEqual => Expression::Product { left, right },
+            _ => Expression::Divide { left, right },
+        };
 
-                Expression::For {
-                    variable: variable_name,
-                    from: Rc::new(Translator::tree(from_tokens.clone())),
-                    to,
-                    body: Rc::new(Translator::tree(body_tokens.clone())),
-                }
+        Expression::Assign {
+            variable,
+            value: Rc::new(value),
+        }
+    }
+
+    // Tuple indexes following an expression: `pair.0`.
+    fn indexes(it: &mut Tokens, mut expression: Expression) -> Expression {
+        while Translator::expect(it, TokenType::Dot) {
+            expression = Translator::index(it, expression);
+        }
+        expression
+    }
+
+    // The scanner reads `t.0.1` as `t`, `.`, `0.1`, so the number after a dot
+    // may hold several indexes.
+    fn index(it: &mut Tokens, mut expression: Expression) -> Expression {
+        for index in Translator::name(it).split('.') {
+            expression = Expression::Index {
+                tuple: Rc::new(expression),
+                index: index.parse().unwrap_or_default(),
+            };
+        }
+        expression
+    }
+
+    fn bitwise(it: &mut Tokens, operand: Operand) -> Expression {
+        Expression::Bitwise {
+            left: Translator::operand(it),
+            operand,
+            right: Translator::operand(it),
+        }
+    }
+
+    fn operand(it: &mut Tokens) -> ExpressionRef {
+        Rc::new(Translator::expression(it))
+    }
+
+    // The braces of an `if` or `for` body need no Block of their own: the
+    // evaluator already gives those bodies a scope.
+    fn body(it: &mut Tokens) -> ExpressionRef {
+        if !Translator::expect(it, TokenType::LeftBrace) {
+            return Translator::operand(it);
+        }
+        let body = Translator::statements(it);
+        Translator::expect(it, TokenType::RightBrace);
+        Rc::new(body)
+    }
+
+    // The arguments of a call, once its opening parenthesis is consumed.
+    fn arguments(it: &mut Tokens) -> Vec<ExpressionRef> {
+        let mut arguments = Vec::new();
+
+        while !Translator::expect(it, TokenType::RightParen) {
+            if it.peek().is_none() {
+                break;
             }
-            TokenType::Greater => {
-                let left = it.next().unwrap();
-                let right = it.next().unwrap();
-                Expression::Compare {
-                    left: Rc::new(Translator::tree(vec![left])),
-                    operand: Operand::GreaterThan,
-                    right: Rc::new(Translator::tree(vec![right])),
-                }
+            arguments.push(Translator::operand(it));
+            Translator::expect(it, TokenType::Comma);
+        }
+
+        arguments
+    }
+
+    // (a, b), [head, ..rest], Some(x), a literal such as 1 or "one", or a
+    // variable, optionally `mut`.
+    fn pattern(it: &mut Tokens) -> Pattern {
+        if Translator::expect(it, TokenType::LeftParen) {
+            let mut elements = Vec::new();
+            while !Translator::expect(it, TokenType::RightParen) && it.peek().is_some() {
+                elements.push(Translator::pattern(it));
+                Translator::expect(it, TokenType::Comma);
             }
-            TokenType::Less => {
-                let left = it.next().unwrap();
-                let right = it.next().unwrap();
-                Expression::Compare {
-                    left: Rc::new(Translator::tree(vec![left])),
-                    operand: Operand::LessThan,
-                    right: Rc::new(Translator::tree(vec![right])),
+            return Pattern::Tuple(elements);
+        }
+
+        if Translator::expect(it, TokenType::LeftBracket) {
+            let mut elements = Vec::new();
+            let mut rest = None;
+            while !Translator::expect(it, TokenType::RightBracket) && it.peek().is_some() {
+                if Translator::expect(it, TokenType::DotDot) {
+                    rest = Some(Translator::variable(it));
+                } else {
+                    elements.push(Translator::pattern(it));
                 }
+                Translator::expect(it, TokenType::Comma);
             }
-            TokenType::Let => {
-                let mut variable_tokens = Vec::new();
+            return Pattern::List { elements, rest };
+        }
 
-                let name = it.next().unwrap();
-                let _ = it.next();
+        if let Some(literal) = it.peek().and_then(Translator::literal) {
+            it.next();
+            return Pattern::Literal(literal);
+        }
 
-                while let Some(token) = it.next() {
-                    variable_tokens.push(token.clone());
-                    if token.token_type == TokenType::EOL {
-                        break;
-                    }
+        if it
+            .peek()
+            .is_some_and(|token| token.token_type == TokenType::Identifier)
+        {
+            let name = Translator::name(it);
+            if Translator::expect(it, TokenType::LeftParen) {
+                let mut elements = Vec::new();
+                while !Translator::expect(it, TokenType::RightParen) && it.peek().is_some() {
+                    elements.push(Translator::pattern(it));
+                    Translator::expect(it, TokenType::Comma);
                 }
+                return Pattern::Constructor { name, elements };
+            }
+            return Pattern::Variable(Translator::annotated(it, Variable::new(name)));
+        }
+
+        Pattern::Variable(Translator::variable(it))
+    }
+
+    // The value a string, number, `true` or `false` token stands for.
+    fn literal(token: &Token) -> Option<Primitive> {
+        let literal = match token.token_type {
+            TokenType::String => Primitive::String(token.lexeme.clone()),
+            TokenType::Number if token.lexeme.contains('.') => {
+                Primitive::Float(token.lexeme.parse::<f64>().unwrap())
+            }
+            TokenType::Number => match token.lexeme.parse::<i64>() {
+                Ok(value) => Primitive::Integer(value),
+                Err(_) => Primitive::BigInteger(token.lexeme.parse().unwrap()),
+            },
+            TokenType::True => Primitive::Boolean(true),
+            TokenType::False => Primitive::Boolean(false),
+            _ => return None,
+        };
+        Some(literal)
+    }
+
+    // A declared variable, optionally `mut` and annotated: `mut total: int`.
+    fn variable(it: &mut Tokens) -> Variable {
+        let variable = if Translator::expect(it, TokenType::Mut) {
+            Variable::mutable(Translator::name(it))
+        } else {
+            Variable::new(Translator::name(it))
+        };
+        Translator::annotated(it, variable)
+    }
 
-                let value = Rc::new(Translator::tree(variable_tokens.clone()));
+    fn annotated(it: &mut Tokens, mut variable: Variable) -> Variable {
+        if Translator::expect(it, TokenType::Colon) {
+            variable.annotation = Some(Translator::annotation(it));
+        }
+        variable
+    }
 
-                let mut variables = Vec::new();
+    // int, float, str, bool, [T], (A, B), () or a name with type arguments:
+    // Option<int>.
+    fn annotation(it: &mut Tokens) -> Type {
+        Translator::annotation_closing(it, &mut false)
+    }
 
-                variables.push((name.lexeme.clone(), value));
+    // The scanner reads the `>>` ending `Option<Option<int>>` as one token;
+    // `closed` tells whether it also closed the enclosing type arguments.
+    fn annotation_closing(it: &mut Tokens, closed: &mut bool) -> Type {
+        if Translator::expect(it, TokenType::LeftBracket) {
+            let element = Translator::annotation(it);
+            Translator::expect(it, TokenType::RightBracket);
+            return Type::List(Box::new(element));
+        }
 
-                return Expression::Let {
-                    variables,
-                    scope: Rc::new(Translator::tree(it.collect())),
-                };
+        if Translator::expect(it, TokenType::LeftParen) {
+            let mut elements = Vec::new();
+            let mut tuple = false;
+            while !Translator::expect(it, TokenType::RightParen) && it.peek().is_some() {
+                elements.push(Translator::annotation(it));
+                tuple |= Translator::expect(it, TokenType::Comma);
             }
-            TokenType::Identifier => {
-                let right = token;
-                Expression::Use {
-                    variable: right.lexeme.clone(),
+            return match elements.len() {
+                0 => Type::Unit,
+                1 if !tuple => elements.pop().unwrap(),
+                _ => Type::Tuple(elements),
+            };
+        }
+
+        match Translator::name(it).as_str() {
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "str" => Type::Str,
+            "bool" => Type::Bool,
+            name => {
+                let mut arguments = Vec::new();
+                if Translator::expect(it, TokenType::Less) {
+                    while it.peek().is_some() {
+                        let mut inner = false;
+                        arguments.push(Translator::annotation_closing(it, &mut inner));
+                        if inner || Translator::expect(it, TokenType::Greater) {
+                            break;
+                        }
+                        if Translator::expect(it, TokenType::GreaterGreater) {
+                            *closed = true;
+                            break;
+                        }
+                        Translator::expect(it, TokenType::Comma);
+                    }
                 }
+                Type::Named(name.to_string(), arguments)
             }
-            TokenType::EOL => {
-                return Expression::Expression(Rc::new(Translator::tree(it.collect())))
+        }
+    }
+
+    // <T, U>, or nothing.
+    fn generics(it: &mut Tokens) -> Vec<String> {
+        let mut generics = Vec::new();
+        if Translator::expect(it, TokenType::Less) {
+            while !Translator::expect(it, TokenType::Greater) && it.peek().is_some() {
+                generics.push(Translator::name(it));
+                Translator::expect(it, TokenType::Comma);
             }
-            TokenType::Else => {
-                return Expression::Expression(Rc::new(Translator::tree(it.collect())))
+        }
+        generics
+    }
+
+    // struct <Name><T> { <field>: <type>, ... }
+    // enum <Name><T> { <Constructor>(<type>, ...), ... }
+    fn declaration(it: &mut Tokens, enumeration: bool) -> Declaration {
+        let name = Translator::name(it);
+        let parameters = Translator::generics(it);
+        Translator::skip_lines(it);
+        Translator::expect(it, TokenType::LeftBrace);
+
+        let mut constructors = Vec::new();
+        let mut fields = Vec::new();
+        loop {
+            Translator::skip_lines(it);
+            if Translator::expect(it, TokenType::RightBrace) || it.peek().is_none() {
+                break;
             }
-            _ => Expression::None {},
+            let field = Translator::name(it);
+            if enumeration {
+                let mut types = Vec::new();
+                if Translator::expect(it, TokenType::LeftParen) {
+                    while !Translator::expect(it, TokenType::RightParen) && it.peek().is_some() {
+                        types.push((types.len().to_string(), Translator::annotation(it)));
+                        Translator::expect(it, TokenType::Comma);
+                    }
+                }
+                constructors.push(Constructor {
+                    name: field,
+                    fields: types,
+                });
+            } else {
+                Translator::expect(it, TokenType::Colon);
+                fields.push((field, Translator::annotation(it)));
+            }
+            Translator::expect(it, TokenType::Comma);
+        }
+        if !enumeration {
+            constructors.push(Constructor {
+                name: name.clone(),
+                fields,
+            });
+        }
+
+        Declaration {
+            name,
+            parameters,
+            enumeration,
+            constructors,
+        }
+    }
+
+    // fn <name><T>(<parameters>) -> <type> { <body> }
+    fn function(it: &mut Tokens, line: usize) -> Function {
+        let name = Translator::name(it);
+        let generics = Translator::generics(it);
+
+        let mut parameters = Vec::new();
+        Translator::expect(it, TokenType::LeftParen);
+        while !Translator::expect(it, TokenType::RightParen) && it.peek().is_some() {
+            parameters.push(Translator::variable(it));
+            Translator::expect(it, TokenType::Comma);
+        }
+
+        let returns = if Translator::expect(it, TokenType::Arrow) {
+            Some(Translator::annotation(it))
+        } else {
+            None
         };
 
-        let tokes = it.collect();
+        Function {
+            name,
+            line,
+            definition: None,
+            generics,
+            parameters,
+            returns,
+            body: Translator::body(it),
+        }
+    }
 
-        let expression = expression.replace_with(Translator::tree(tokes));
+    fn name(it: &mut Tokens) -> String {
+        match it.next() {
+            Some(token) => token.lexeme,
+            None => String::new(),
+        }
+    }
 
-        expression
+    // Consumes the next token only if it has the expected type.
+    fn expect(it: &mut Tokens, token_type: TokenType) -> bool {
+        match it.peek() {
+            Some(token) if token.token_type == token_type => {
+                it.next();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn skip_lines(it: &mut Tokens) {
+        while Translator::expect(it, TokenType::EOL) {}
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::Parser;
+
+    fn line(line: usize, statement: Expression) -> ExpressionRef {
+        Rc::new(Translator::line(line, statement))
+    }
 
     #[test]
     fn it_prints() {
@@ -273,9 +687,14 @@ mod tests {
 
         assert_eq!(
             expr,
-            Rc::new(Expression::Print {
-                expression: Rc::new(Expression::Primitive(Primitive::String("john".to_string())))
-            })
+            line(
+                1,
+                Expression::Print {
+                    expression: Rc::new(Expression::Primitive(Primitive::String(
+                        "john".to_string()
+                    )))
+                }
+            )
         );
     }
 
@@ -298,12 +717,15 @@ mod tests {
 
         assert_eq!(
             expr,
-            Rc::new(Expression::Print {
-                expression: Rc::new(Expression::Sum {
-                    right: Rc::new(Expression::Primitive(Primitive::Integer(2))),
-                    left: Rc::new(Expression::Primitive(Primitive::Integer(1))),
-                })
-            })
+            line(
+                1,
+                Expression::Print {
+                    expression: Rc::new(Expression::Sum {
+                        left: Rc::new(Expression::Primitive(Primitive::Integer(2))),
+                        right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
+                    })
+                }
+            )
         );
     }
 
@@ -317,10 +739,13 @@ mod tests {
 
         assert_eq!(
             expr,
-            Rc::new(Expression::Unary {
-                operand: Operand::Not,
-                right: Rc::new(Expression::Primitive(Primitive::Integer(2))),
-            })
+            line(
+                1,
+                Expression::Unary {
+                    operand: Operand::Not,
+                    right: Rc::new(Expression::Primitive(Primitive::Integer(2))),
+                }
+            )
         );
     }
 
@@ -337,13 +762,16 @@ mod tests {
 
         assert_eq!(
             expr,
-            Rc::new(Expression::Unary {
-                operand: Operand::Not,
-                right: Rc::new(Expression::Unary {
+            line(
+                1,
+                Expression::Unary {
                     operand: Operand::Not,
-                    right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
-                }),
-            })
+                    right: Rc::new(Expression::Unary {
+                        operand: Operand::Not,
+                        right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
+                    }),
+                }
+            )
         );
     }
 
@@ -369,16 +797,54 @@ mod tests {
         let expr = Translator::from(tokens);
         assert_eq!(
             expr,
-            Rc::new(Expression::If {
-                condition: Rc::new(Expression::Unary {
-                    operand: Operand::Not,
-                    right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
-                }),
-                then_branch: Rc::new(Expression::Print {
-                    expression: Rc::new(Expression::Primitive(Primitive::Integer(1)))
-                }),
-                else_branch: Rc::new(Expression::None {}),
-            })
+            line(
+                1,
+                Expression::If {
+                    condition: Rc::new(Expression::Unary {
+                        operand: Operand::Not,
+                        right: Rc::new(Expression::Primitive(Primitive::Integer(1))),
+                    }),
+                    then_branch: line(
+                        2,
+                        Expression::Print {
+                            expression: Rc::new(Expression::Primitive(Primitive::Integer(1)))
+                        }
+                    ),
+                    else_branch: Rc::new(Expression::None {}),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn it_can_make_mutable_let_statements() {
+        let tokens = vec![
+            Token::new(TokenType::Let, "let".to_string(), "let".to_string(), 1),
+            Token::new(TokenType::Mut, "mut".to_string(), "mut".to_string(), 1),
+            Token::new(
+                TokenType::Identifier,
+                "count".to_string(),
+                "count".to_string(),
+                1,
+            ),
+            Token::new(TokenType::Equal, "=".to_string(), "=".to_string(), 1),
+            Token::new(TokenType::Number, "0".to_string(), "0".to_string(), 1),
+        ];
+
+        let expr = Translator::from(tokens);
+
+        assert_eq!(
+            expr,
+            line(
+                1,
+                Expression::Let {
+                    variables: vec![(
+                        Pattern::Variable(Variable::mutable("count".to_string())),
+                        Rc::new(Expression::Primitive(Primitive::Integer(0)))
+                    ),],
+                    scope: Rc::new(Expression::None {}),
+                }
+            )
         );
     }
 
@@ -400,13 +866,16 @@ mod tests {
 
         assert_eq!(
             expr,
-            Rc::new(Expression::Let {
-                variables: vec![(
-                    "xanax".to_string(),
-                    Rc::new(Expression::Primitive(Primitive::Integer(1)))
-                ),],
-                scope: Rc::new(Expression::None {}),
-            })
+            line(
+                1,
+                Expression::Let {
+                    variables: vec![(
+                        Pattern::Variable(Variable::new("xanax".to_string())),
+                        Rc::new(Expression::Primitive(Primitive::Integer(1)))
+                    ),],
+                    scope: Rc::new(Expression::None {}),
+                }
+            )
         );
     }
 
@@ -423,9 +892,155 @@ mod tests {
 
         assert_eq!(
             expr,
-            Rc::new(Expression::Use {
-                variable: "xanax".to_string(),
+            line(
+                1,
+                Expression::Use {
+                    variable: Variable::new("xanax".to_string()),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn it_can_call_functions_inside_operators() {
+        let tokens = vec![
+            Token::new(TokenType::Plus, "+".to_string(), "+".to_string(), 1),
+            Token::new(
+                TokenType::Identifier,
+                "string".to_string(),
+                "string".to_string(),
+                1,
+            ),
+            Token::new(TokenType::Dot, ".".to_string(), ".".to_string(), 1),
+            Token::new(
+                TokenType::Identifier,
+                "trim".to_string(),
+                "trim".to_string(),
+                1,
+            ),
+            Token::new(TokenType::LeftParen, "(".to_string(), "(".to_string(), 1),
+            Token::new(
+                TokenType::Identifier,
+                "name".to_string(),
+                "name".to_string(),
+                1,
+            ),
+            Token::new(TokenType::RightParen, ")".to_string(), ")".to_string(), 1),
+            Token::new(TokenType::String, "!".to_string(), "!".to_string(), 1),
+        ];
+
+        let expr = Translator::from(tokens);
+
+        assert_eq!(
+            expr,
+            line(
+                1,
+                Expression::Sum {
+                    left: Rc::new(Expression::Call {
+                        function: "string.trim".to_string(),
+                        definition: None,
+                        arguments: vec![Rc::new(Expression::Use {
+                            variable: Variable::new("name".to_string()),
+                        })],
+                    }),
+                    right: Rc::new(Expression::Primitive(Primitive::String("!".to_string()))),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn it_chains_statements_on_separate_lines() {
+        let tokens = vec![
+            Token::new(
+                TokenType::Print,
+                "print".to_string(),
+                "print".to_string(),
+                1,
+            ),
+            Token::new(TokenType::Number, "1".to_string(), "1".to_string(), 1),
+            Token::new(TokenType::EOL, "\n".to_string(), "\n".to_string(), 2),
+            Token::new(
+                TokenType::Print,
+                "print".to_string(),
+                "print".to_string(),
+                2,
+            ),
+            Token::new(TokenType::Number, "2".to_string(), "2".to_string(), 2),
+        ];
+
+        let expr = Translator::from(tokens);
+
+        assert_eq!(
+            expr,
+            Rc::new(Expression::Chain {
+                left: line(
+                    1,
+                    Expression::Print {
+                        expression: Rc::new(Expression::Primitive(Primitive::Integer(1)))
+                    }
+                ),
+                right: line(
+                    2,
+                    Expression::Print {
+                        expression: Rc::new(Expression::Primitive(Primitive::Integer(2)))
+                    }
+                ),
             })
         );
     }
+
+    #[test]
+    fn it_tells_tuples_from_grouping() {
+        let one = || Expression::Primitive(Primitive::Integer(1));
+
+        assert_eq!(
+            Translator::from(Parser::new().parse("( 1 )")),
+            line(1, one())
+        );
+        assert_eq!(
+            Translator::from(Parser::new().parse("(1,).0")),
+            line(
+                1,
+                Expression::Index {
+                    tuple: Rc::new(Expression::Tuple {
+                        elements: vec![Rc::new(one())]
+                    }),
+                    index: 0,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn it_expands_compound_assignments() {
+        let tokens = vec![
+            Token::new(
+                TokenType::Identifier,
+                "total".to_string(),
+                "total".to_string(),
+                1,
+            ),
+            Token::new(TokenType::StarEqual, "*=".to_string(), "*=".to_string(), 1),
+            Token::new(TokenType::Number, "2".to_string(), "2".to_string(), 1),
+        ];
+
+        let expr = Translator::from(tokens);
+
+        assert_eq!(
+            expr,
+            line(
+                1,
+                Expression::Assign {
+                    variable: Variable::new("total".to_string()),
+                    value: Rc::new(Expression::Product {
+                        left: Rc::new(Expression::Use {
+                            variable: Variable::new("total".to_string()),
+                        }),
+                        right: Rc::new(Expression::Primitive(Primitive::Integer(2))),
+                    }),
+                }
+            )
+        );
+    }
 }
@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod arithmetic {
+    use num_bigint::BigInt;
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            options::{Options, Overflow},
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_promotes_to_big_integers_by_default() {
+        let result = given_this_source_with("* 9223372036854775807 4", Overflow::default());
+
+        assert_eq!(result, Ok(Value::BigInteger(BigInt::from(i64::MAX) * 4)));
+    }
+
+    #[test]
+    fn it_comes_back_to_small_integers_when_the_result_fits() {
+        let result =
+            given_this_source_with("- ( + 9223372036854775807 10 ) 20", Overflow::default());
+
+        assert_eq!(result, Ok(Value::Integer(i64::MAX - 10)));
+    }
+
+    #[test]
+    fn it_compares_big_integers_with_every_number() {
+        let source = "> 100000000000000000000 ( * 2 9223372036854775807 )";
+
+        assert_eq!(
+            given_this_source_with(source, Overflow::default()),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            given_this_source_with("< 100000000000000000000 1.5", Overflow::default()),
+            Ok(Value::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn it_reports_overflow_when_checked() {
+        let result = given_this_source_with("+ 9223372036854775807 1", Overflow::Checked);
+
+        assert!(matches!(result, Err(Error::IntegerOverflow(_, _))));
+    }
+
+    #[test]
+    fn it_wraps_when_asked_to() {
+        let result = given_this_source_with("* 4611686018427387904 2", Overflow::Wrapping);
+
+        assert_eq!(result, Ok(Value::Integer(i64::MIN)));
+    }
+
+    #[test]
+    fn it_saturates_when_asked_to() {
+        let result = given_this_source_with(
+            "- 0 ( - 9223372036854775807 ( - 0 5 ) )",
+            Overflow::Saturating,
+        );
+
+        assert_eq!(result, Ok(Value::Integer(-i64::MAX)));
+    }
+
+    #[test]
+    fn it_rounds_integer_division_towards_negative_infinity() {
+        assert_eq!(given_this_source("// 7 2"), Ok(Value::Integer(3)));
+        assert_eq!(given_this_source("// ( - 0 7 ) 2"), Ok(Value::Integer(-4)));
+        assert_eq!(given_this_source("// 7.5 2"), Ok(Value::Float(3.0)));
+    }
+
+    #[test]
+    fn it_gives_the_remainder_the_sign_of_the_divisor() {
+        assert_eq!(given_this_source("% 7 3"), Ok(Value::Integer(1)));
+        assert_eq!(given_this_source("% ( - 0 7 ) 3"), Ok(Value::Integer(2)));
+        assert_eq!(given_this_source("% 7 ( - 0 3 )"), Ok(Value::Integer(-2)));
+        assert_eq!(given_this_source("% 5.5 2"), Ok(Value::Float(1.5)));
+    }
+
+    #[test]
+    fn it_reports_division_by_zero() {
+        assert!(matches!(
+            given_this_source("// 1 0"),
+            Err(Error::DivisionByZero(_, _))
+        ));
+        assert!(matches!(
+            given_this_source("% 1 0"),
+            Err(Error::DivisionByZero(_, _))
+        ));
+        assert!(matches!(
+            given_this_source("/ 1 0.0"),
+            Err(Error::DivisionByZero(_, _))
+        ));
+    }
+
+    #[test]
+    fn it_takes_operands_in_the_order_they_are_written() {
+        assert_eq!(given_this_source("- 5 3"), Ok(Value::Integer(2)));
+        assert_eq!(given_this_source("/ 6 3"), Ok(Value::Float(2.0)));
+        assert_eq!(
+            given_this_source("+ \"tau\" \"pe\""),
+            Ok(Value::String("taupe".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_raises_to_a_power() {
+        assert_eq!(given_this_source("** 2 10"), Ok(Value::Integer(1024)));
+        assert_eq!(given_this_source("** 2 ( - 0 1 )"), Ok(Value::Float(0.5)));
+        assert_eq!(
+            given_this_source("** 2 100"),
+            Ok(Value::BigInteger(BigInt::from(2).pow(100)))
+        );
+    }
+
+    #[test]
+    fn it_reports_overflow_of_a_power_when_checked() {
+        let result = given_this_source_with("** 2 64", Overflow::Checked);
+
+        assert!(matches!(result, Err(Error::IntegerOverflow(_, _))));
+    }
+
+    #[test]
+    fn it_applies_the_overflow_mode_to_a_power_without_computing_it() {
+        let huge = "** 3 4000000001";
+
+        assert!(matches!(
+            given_this_source_with(huge, Overflow::Checked),
+            Err(Error::IntegerOverflow(_, _))
+        ));
+        assert_eq!(
+            given_this_source_with(huge, Overflow::Wrapping),
+            Ok(Value::Integer(-4389742388245536765))
+        );
+        assert_eq!(
+            given_this_source_with("** ( - 0 3 ) 4000000001", Overflow::Saturating),
+            Ok(Value::Integer(i64::MIN))
+        );
+        assert!(matches!(
+            given_this_source(huge),
+            Err(Error::IntegerOverflow(_, _))
+        ));
+        assert_eq!(
+            given_this_source_with("** ( - 0 1 ) 4000000001", Overflow::Checked),
+            Ok(Value::Integer(-1))
+        );
+    }
+
+    #[test]
+    fn it_combines_bits() {
+        assert_eq!(given_this_source("& 12 10"), Ok(Value::Integer(8)));
+        assert_eq!(given_this_source("| 12 10"), Ok(Value::Integer(14)));
+        assert_eq!(given_this_source("^ 12 10"), Ok(Value::Integer(6)));
+        assert_eq!(given_this_source("~ 5"), Ok(Value::Integer(-6)));
+    }
+
+    #[test]
+    fn it_shifts_bits() {
+        assert_eq!(given_this_source("<< 1 4"), Ok(Value::Integer(16)));
+        assert_eq!(given_this_source(">> ( - 0 16 ) 2"), Ok(Value::Integer(-4)));
+        assert_eq!(
+            given_this_source("<< 1 63"),
+            Ok(Value::BigInteger(BigInt::from(1) << 63))
+        );
+        assert_eq!(
+            given_this_source_with("<< 1 63", Overflow::Wrapping),
+            Ok(Value::Integer(i64::MIN))
+        );
+    }
+
+    #[test]
+    fn it_refuses_negative_or_too_large_shifts() {
+        assert!(matches!(
+            given_this_source("<< 1 ( - 0 1 )"),
+            Err(Error::InvalidShift(_, _))
+        ));
+        assert!(matches!(
+            given_this_source(">> 1 64"),
+            Err(Error::InvalidShift(_, _))
+        ));
+    }
+
+    #[test]
+    fn it_refuses_bitwise_operations_on_floats() {
+        assert!(matches!(
+            given_this_source("& 1.5 1"),
+            Err(Error::InvalidValues(_, _))
+        ));
+    }
+
+    fn given_this_source(source: &str) -> Result<Value, Error> {
+        given_this_source_with(source, Overflow::default())
+    }
+
+    fn given_this_source_with(source: &str, overflow: Overflow) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let options = Options {
+            overflow,
+            ..Options::default()
+        };
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        evaluate(&resolved.expression, &mut Environment::new(), &options)
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod assignment {
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            options::Options,
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_reassigns_a_variable() {
+        let result = given_this_source("let mut x = 1\nx = + x 41\nx");
+
+        assert_eq!(result, Ok(Value::Integer(42)));
+    }
+
+    #[test]
+    fn it_applies_compound_assignments() {
+        let result = given_this_source("let mut x = 10\nx += 5\nx -= 3\nx *= 2\nx /= 8\nx");
+
+        assert_eq!(result, Ok(Value::Float(3.0)));
+    }
+
+    #[test]
+    fn it_assigns_every_kind_of_value() {
+        assert_eq!(
+            given_this_source("let mut x = 1\nx = true\nx"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            given_this_source("let mut x = 1\nx = 2.5\nx"),
+            Ok(Value::Float(2.5))
+        );
+        assert_eq!(
+            given_this_source("let mut x = 1\nx = string.split(\"a b\", \" \")\nx"),
+            Ok(Value::List(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string())
+            ]))
+        );
+        assert_eq!(
+            given_this_source("let mut x = 1\nx = string.find(\"a\", \"b\")\nx"),
+            Ok(Value::None)
+        );
+    }
+
+    #[test]
+    fn it_refuses_to_assign_an_undeclared_variable() {
+        assert_eq!(
+            given_this_source("y = 1"),
+            Err(Error::UndefinedVariable("y".to_string()))
+        );
+        assert_eq!(
+            given_this_source("let x = 1\ny += 1"),
+            Err(Error::UndefinedVariable("y".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_refuses_to_assign_an_immutable_variable() {
+        assert_eq!(
+            given_this_source("let x = 1\nx = 2"),
+            Err(Error::ImmutableVariable("x".to_string()))
+        );
+        assert_eq!(
+            given_this_source("for i in [0..3] {\n  i += 1\n}"),
+            Err(Error::ImmutableVariable("i".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_refuses_immutable_assignments_before_running() {
+        let result = given_this_source("let x = 1\nif false {\n  x = 2\n}\nx");
+
+        assert_eq!(result, Err(Error::ImmutableVariable("x".to_string())));
+    }
+
+    #[test]
+    fn it_allows_a_shadowing_binding_to_be_mutable() {
+        let result = given_this_source("let x = 1\nlet mut x = x\nx += 1\nx");
+
+        assert_eq!(result, Ok(Value::Integer(2)));
+    }
+
+    fn given_this_source(source: &str) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        evaluate(
+            &resolved.expression,
+            &mut Environment::new(),
+            &Options::default(),
+        )
+    }
+}
@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod bytecode {
+    use std::{fs, path::Path, process::Command, rc::Rc};
+
+    use taupe::{
+        core::{
+            compilation::{compile, Chunk, Instruction, Program},
+            encoding::{decode, encode, MAGIC, VERSION},
+            environment::Environment,
+            execution::execute,
+            expression::{Pattern, Variable},
+            options::{Options, Overflow},
+            resolution::Resolver,
+            values::Error,
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_decodes_what_it_encodes() {
+        let sources = [
+            "let mut total = 0\nfor i in [1..10] {\n  total += i\n}\ntotal",
+            "print + ** 2 64 -3\nprint / 1.5 0.25\nprint \"taupe\"\nprint < 1 2",
+            "fn fact(n: int) -> int {\n  if < n 2 { 1 } else { * n fact(- n 1) }\n}\nfact(25)",
+            "let (a, [b, ..rest]) = (1, string.split(\"a b c\", \" \"))\nprint rest",
+            "enum Option<T> {\n  Some(T),\n  Nothing,\n}\n\
+             match Some(2) {\n  Some(x) => x,\n  Nothing => 0,\n}",
+            "match (2, \"two\") {\n  (1, _) => 1.5,\n  (_, \"two\") => 2.5,\n  _ => 0.0,\n}",
+            "struct Point {\n  x: int,\n  y: int,\n}\nlet p = Point(1, 2)\n- p.x p.y",
+            "let rate = 2\nfn f(x: int) -> int {\n  * x rate\n}\nf(21)",
+        ];
+
+        for source in sources {
+            let program = given_this_source(source);
+            assert_eq!(
+                decode(&encode(&program, Overflow::Wrapping)),
+                Ok((program, Overflow::Wrapping)),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn it_rejects_other_files() {
+        assert!(matches!(
+            decode(b"print 1\n"),
+            Err(Error::InvalidBytecode(..))
+        ));
+
+        let mut truncated = encode(&given_this_source("print + 1 2"), Overflow::Promote);
+        truncated.pop();
+        assert!(matches!(
+            decode(&truncated),
+            Err(Error::InvalidBytecode(..))
+        ));
+    }
+
+    #[test]
+    fn it_rejects_other_versions() {
+        let mut bytes = encode(&given_this_source("print 1"), Overflow::Promote);
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(VERSION + 1).to_le_bytes());
+
+        assert_eq!(
+            decode(&bytes),
+            Err(Error::IncompatibleBytecode(VERSION + 1, VERSION))
+        );
+    }
+
+    #[test]
+    fn it_runs_built_files_like_their_sources() {
+        let directory = Path::new(env!("CARGO_TARGET_TMPDIR"));
+        for asset in ["for.tau", "function.tau", "generics.tau", "tuple.tau"] {
+            let source = Path::new("tests/assets").join(asset);
+            let built = directory.join(asset).with_extension("taub");
+            let build = given_these_arguments(&[
+                "build",
+                source.to_str().unwrap(),
+                "-o",
+                built.to_str().unwrap(),
+            ]);
+            assert!(build.status.success(), "{}", asset);
+
+            let run = given_these_arguments(&["run", built.to_str().unwrap()]).stdout;
+            let interpreted = given_these_arguments(&[source.to_str().unwrap()]).stdout;
+            assert!(!run.is_empty(), "{}", asset);
+            assert_eq!(run, interpreted, "{}", asset);
+        }
+    }
+
+    #[test]
+    fn it_refuses_to_run_code_that_takes_what_it_never_pushed() {
+        let pattern = Pattern::Variable(Variable::resolved("x".to_string(), 3));
+        let programs = [
+            vec![Instruction::Pop, Instruction::Return],
+            vec![Instruction::Sum, Instruction::Return],
+            vec![Instruction::Tuple(2), Instruction::Return],
+            vec![
+                Instruction::ExitScope,
+                Instruction::Nil,
+                Instruction::Return,
+            ],
+            vec![
+                Instruction::Nil,
+                Instruction::Bind(0),
+                Instruction::Nil,
+                Instruction::Return,
+            ],
+        ];
+
+        for code in programs {
+            let chunk = Chunk {
+                code: code.clone(),
+                patterns: vec![pattern.clone()],
+                ..Chunk::default()
+            };
+            let (program, _) = decode(&encode(
+                &Program {
+                    main: Rc::new(chunk),
+                    functions: Vec::new(),
+                },
+                Overflow::Promote,
+            ))
+            .unwrap();
+
+            assert!(
+                matches!(
+                    execute(&program, &mut Environment::new(), &Options::default()),
+                    Err(Error::InvalidBytecode(..))
+                ),
+                "{:?}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn it_runs_with_the_overflow_mode_it_was_built_with() {
+        let directory = Path::new(env!("CARGO_TARGET_TMPDIR"));
+        let source = directory.join("overflow.tau");
+        let built = directory.join("overflow.taub");
+        fs::write(
+            &source,
+            "print * 9223372036854775807 2\nfn f(a: int) -> int {\n  * a 2\n}\nprint f(9223372036854775807)\n",
+        )
+        .unwrap();
+        let build = given_these_arguments(&[
+            "--overflow=wrapping",
+            "build",
+            source.to_str().unwrap(),
+            "-o",
+            built.to_str().unwrap(),
+        ]);
+        assert!(build.status.success());
+
+        let run = given_these_arguments(&["run", built.to_str().unwrap()]);
+        assert_eq!(String::from_utf8(run.stdout).unwrap(), "-2\n-2\n");
+
+        let checked =
+            given_these_arguments(&["--overflow=checked", "run", built.to_str().unwrap()]);
+        assert!(!checked.status.success());
+        assert!(String::from_utf8(checked.stderr)
+            .unwrap()
+            .contains("built with --overflow=wrapping"));
+    }
+
+    #[test]
+    fn it_diagnoses_on_stderr() {
+        let directory = Path::new(env!("CARGO_TARGET_TMPDIR"));
+        let source = directory.join("unused.tau");
+        fs::write(&source, "let unused = 1\n").unwrap();
+
+        let build = given_these_arguments(&[
+            "build",
+            source.to_str().unwrap(),
+            "-o",
+            directory.join("unused.taub").to_str().unwrap(),
+        ]);
+
+        assert!(build.status.success());
+        assert!(!String::from_utf8(build.stdout)
+            .unwrap()
+            .contains("UnusedVariable"));
+        assert!(String::from_utf8(build.stderr)
+            .unwrap()
+            .contains("UnusedVariable(\"unused\")"));
+    }
+
+    #[test]
+    fn it_explains_why_it_will_not_run_an_old_file() {
+        let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("old.taub");
+        let mut bytes = encode(&given_this_source("print 1"), Overflow::Promote);
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&0u16.to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+
+        let output = given_these_arguments(&["run", path.to_str().unwrap()]);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr).unwrap().contains(&format!(
+            "built for bytecode version 0, but this taupe runs version {}",
+            VERSION
+        )));
+    }
+
+    fn given_this_source(source: &str) -> Program {
+        let tokens = Parser::new().parse(source);
+        compile(&Resolver::new().resolve(&Translator::from(tokens)).unwrap())
+    }
+
+    fn given_these_arguments(arguments: &[&str]) -> std::process::Output {
+        Command::new(env!("CARGO_BIN_EXE_taupe"))
+            .args(arguments)
+            .output()
+            .unwrap()
+    }
+}
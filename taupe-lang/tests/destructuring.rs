@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod destructuring {
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            expression::{Pattern, Variable},
+            options::Options,
+            primitives::Primitive,
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_binds_several_variables_in_one_let() {
+        let result = given_this_source("let a = 1, b = + a 1, mut c = 3\nc += b\n+ a c");
+
+        assert_eq!(result, Ok(Value::Integer(6)));
+    }
+
+    #[test]
+    fn it_destructures_a_pair() {
+        let result = given_this_source("let (x, y) = (\"4\", \"2\")\n+ y x");
+
+        assert_eq!(result, Ok(Value::String("24".to_string())));
+    }
+
+    #[test]
+    fn it_binds_the_rest_of_a_list() {
+        let source = "let [head, ..rest] = string.split(\"a b c\", \" \")\nstring.join(rest, head)";
+
+        assert_eq!(
+            given_this_source(source),
+            Ok(Value::String("bac".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_binds_an_empty_rest() {
+        let result = given_this_source("let [_head, ..rest] = string.split(\"a\", \" \")\nrest");
+
+        assert_eq!(result, Ok(Value::List(vec![])));
+    }
+
+    #[test]
+    fn it_refuses_a_value_of_the_wrong_shape() {
+        let result = given_this_source("let (x, y) = (\"a\", \"b\", \"c\")\n+ x y");
+
+        assert_eq!(
+            result,
+            Err(Error::PatternMismatch(
+                Box::new(Pattern::Tuple(vec![
+                    Pattern::Variable(Variable {
+                        global: true,
+                        ..Variable::resolved("x".to_string(), 0)
+                    }),
+                    Pattern::Variable(Variable {
+                        global: true,
+                        ..Variable::resolved("y".to_string(), 1)
+                    }),
+                ])),
+                Value::Tuple(vec![
+                    Value::String("a".to_string()),
+                    Value::String("b".to_string()),
+                    Value::String("c".to_string()),
+                ])
+            ))
+        );
+        assert!(matches!(
+            given_this_source("let [x, ..rest] = 1\n+ x rest"),
+            Err(Error::PatternMismatch(_, Value::Integer(1)))
+        ));
+        assert!(matches!(
+            given_this_source("let (x, y) = string.split(\"a b\", \" \")\n+ x y"),
+            Err(Error::PatternMismatch(_, Value::List(_)))
+        ));
+        assert!(matches!(
+            given_this_source("let [x, y] = string.split(\"a\", \" \")\n+ x y"),
+            Err(Error::PatternMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn it_matches_literal_patterns() {
+        let source = "match 3 {\n  1 => \"one\",\n  3 => \"three\",\n  _ => \"other\",\n}";
+        assert_eq!(
+            given_this_source(source),
+            Ok(Value::String("three".to_string()))
+        );
+
+        let source = "match (\"b\", true) {\n  (\"a\", _) => 1,\n  (\"b\", false) => 2,\n  (\"b\", true) => 3,\n}";
+        assert_eq!(given_this_source(source), Ok(Value::Integer(3)));
+    }
+
+    #[test]
+    fn it_refuses_a_literal_it_is_not_given() {
+        assert_eq!(
+            given_this_source("let 5 = 3"),
+            Err(Error::PatternMismatch(
+                Box::new(Pattern::Literal(Primitive::Integer(5))),
+                Value::Integer(3)
+            ))
+        );
+        assert_eq!(given_this_source("let 5 = 5\n1"), Ok(Value::Integer(1)));
+    }
+
+    fn given_this_source(source: &str) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        evaluate(
+            &resolved.expression,
+            &mut Environment::new(),
+            &Options::default(),
+        )
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod embedding {
+    use std::{cell::RefCell, path::Path, rc::Rc};
+
+    use taupe::{
+        core::{
+            options::{Backend, Options},
+            output::{Buffer, OutputRef},
+            values::{Error, Value},
+        },
+        Engine,
+    };
+
+    #[test]
+    fn it_returns_the_value_of_the_last_statement() {
+        let mut engine = given_an_engine();
+
+        assert_eq!(engine.eval("let x = 2\n* x 21"), Ok(Value::Integer(42)));
+    }
+
+    #[test]
+    fn it_keeps_state_between_calls() {
+        for backend in [Backend::Tree, Backend::Bytecode] {
+            let mut engine = given_an_engine_with(backend);
+
+            engine
+                .eval("let mut total = 1\nfn double(n) { * n 2 }")
+                .unwrap();
+            engine.eval("total = double(total)").unwrap();
+
+            assert_eq!(
+                engine.eval("double(total)"),
+                Ok(Value::Integer(4)),
+                "{:?}",
+                backend
+            );
+        }
+    }
+
+    #[test]
+    fn it_returns_errors() {
+        let mut engine = given_an_engine();
+
+        assert!(matches!(
+            engine.eval("// 1 0"),
+            Err(Error::DivisionByZero(..))
+        ));
+        assert_eq!(
+            engine.eval("missing"),
+            Err(Error::UndefinedVariable("missing".to_string()))
+        );
+        assert!(matches!(engine.eval("\"open"), Err(Error::ParseError(..))));
+        assert_eq!(engine.eval("+ 1 2"), Ok(Value::Integer(3)));
+    }
+
+    #[test]
+    fn it_forgets_globals_of_a_failed_call() {
+        let mut engine = given_an_engine();
+
+        assert!(engine
+            .eval("let before = 1\n// 1 0\nlet after = 2")
+            .is_err());
+
+        assert_eq!(engine.get_global("before"), Some(Value::Integer(1)));
+        assert_eq!(engine.get_global("after"), None);
+        assert_eq!(engine.eval("let after = 3\nafter"), Ok(Value::Integer(3)));
+    }
+
+    #[test]
+    fn it_shares_globals_with_the_host() {
+        for backend in [Backend::Tree, Backend::Bytecode] {
+            let mut engine = given_an_engine_with(backend);
+
+            engine.set_global("port", Value::Integer(8080));
+            engine.eval("let name = \"taupe\"").unwrap();
+            engine.set_global("port", Value::String("8081".to_string()));
+
+            assert_eq!(
+                engine.eval("string.len(port)"),
+                Ok(Value::Integer(4)),
+                "{:?}",
+                backend
+            );
+            assert_eq!(
+                engine.get_global("name"),
+                Some(Value::String("taupe".to_string()))
+            );
+            assert_eq!(engine.get_global("missing"), None);
+        }
+    }
+
+    #[test]
+    fn it_keeps_globals_of_the_host_read_only() {
+        let mut engine = given_an_engine();
+        engine.set_global("port", Value::Integer(8080));
+
+        assert_eq!(
+            engine.eval("port = 1"),
+            Err(Error::ImmutableVariable("port".to_string()))
+        );
+        assert_eq!(engine.get_global("port"), Some(Value::Integer(8080)));
+    }
+
+    #[test]
+    fn it_lets_functions_read_globals_of_the_host() {
+        let mut engine = given_an_engine();
+        engine.set_global("port", Value::Integer(8080));
+
+        assert_eq!(
+            engine.eval("fn next() -> int {\n  + port 1\n}\nnext()"),
+            Ok(Value::Integer(8081))
+        );
+        engine.set_global("port", Value::Integer(9090));
+        assert_eq!(engine.eval("next()"), Ok(Value::Integer(9091)));
+    }
+
+    #[test]
+    fn it_evaluates_files() {
+        let mut engine = given_an_engine();
+
+        engine.eval_file(Path::new("tests/assets/let.tau")).unwrap();
+
+        assert_eq!(engine.get_global("n"), Some(Value::Integer(3)));
+        assert_eq!(engine.eval("+ a n"), Ok(Value::Integer(4)));
+        assert!(matches!(
+            engine.eval_file("tests/assets/missing.tau"),
+            Err(Error::UnreadableFile(..))
+        ));
+    }
+
+    #[test]
+    fn it_leaves_the_source_out_of_the_diagnostics() {
+        let buffer = Rc::new(RefCell::new(Buffer::default()));
+        let mut engine = Engine::with_options(Options {
+            output: OutputRef::from(buffer.clone()),
+            ..Options::default()
+        });
+
+        engine.eval("print 1").unwrap();
+        assert_eq!(buffer.borrow().printed, "1\n");
+        assert_eq!(buffer.borrow().diagnostics, "");
+
+        assert!(engine.eval("print \"1").is_err());
+        assert!(!buffer.borrow().diagnostics.contains("print"));
+        assert!(buffer.borrow().diagnostics.contains("Error"));
+    }
+
+    fn given_an_engine() -> Engine {
+        given_an_engine_with(Backend::Tree)
+    }
+
+    fn given_an_engine_with(backend: Backend) -> Engine {
+        let buffer = Rc::new(RefCell::new(Buffer::default()));
+        Engine::with_options(Options {
+            engine: backend,
+            output: OutputRef::from(buffer),
+            ..Options::default()
+        })
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod engines {
+    use std::{fs, path::Path, process::Command};
+
+    use taupe::{
+        core::{
+            compilation::compile,
+            environment::Environment,
+            evaluation::evaluate,
+            execution::execute,
+            options::Options,
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_prints_and_diagnoses_the_same_for_every_asset() {
+        let mut assets: Vec<_> = fs::read_dir("tests/assets")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "tau"))
+            .collect();
+        assets.sort();
+
+        for asset in assets {
+            assert_eq!(
+                given_this_file_with(&asset, "tree"),
+                given_this_file_with(&asset, "bytecode"),
+                "{}",
+                asset.display()
+            );
+        }
+    }
+
+    #[test]
+    fn it_evaluates_to_the_same_values() {
+        let sources = [
+            "let mut total = 0\nfor i in [1..10] {\n  total += i\n}\ntotal",
+            "for i in [3..1] {\n  print i\n}",
+            "fn fact(n: int) -> int {\n  if < n 2 { 1 } else { * n fact(- n 1) }\n}\nfact(25)",
+            "let (a, b) = (1, (2, 3))\n+ a b.1",
+            "enum Shape {\n  Circle(float),\n  Square(float),\n}\n\
+             match Square(2.0) {\n  Circle(r) => * r r,\n  Square(s) => * s s,\n}",
+            "struct Point {\n  x: int,\n  y: int,\n}\nlet p = Point(1, 2)\n- p.x p.y",
+            "string.upper(\"taupe\")",
+            "let rate = 2\nfn f(x: int) -> int {\n  * x rate\n}\nf(21)",
+        ];
+
+        for source in sources {
+            assert_eq!(
+                given_this_source_with(source, false),
+                given_this_source_with(source, true),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn it_fails_with_the_same_errors() {
+        let sources = [
+            "// 1 0",
+            "let mut x = 1\nfor i in [1..\"3\"] {\n  x = i\n}",
+            "enum Bit {\n  One,\n  Zero,\n}\nmatch Zero {\n  One => 1,\n}",
+            "fn f(a) {\n  / a 0\n}\nf(1)",
+            "if true {\n  let inner = 1\n  print f()\n}\nlet late = 2\nfn f() {\n  late\n}",
+        ];
+
+        for source in sources {
+            let tree = given_this_source_with(source, false);
+            assert!(tree.is_err(), "{}", source);
+            assert_eq!(tree, given_this_source_with(source, true), "{}", source);
+        }
+    }
+
+    #[test]
+    fn it_releases_the_slots_of_a_failed_run() {
+        let tokens = Parser::new().parse("let a = 1\nif true {\n  let b = 2\n  / b 0\n}");
+        let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+        let mut environment = Environment::new();
+
+        let result = execute(&compile(&resolved), &mut environment, &Options::default());
+
+        assert!(matches!(result, Err(Error::DivisionByZero(..))));
+        assert_eq!(environment.len(), 1);
+    }
+
+    // What a run prints, and what it diagnoses after the echo of its source,
+    // which is the same whatever the engine.
+    fn given_this_file_with(asset: &Path, engine: &str) -> (String, String) {
+        let output = Command::new(env!("CARGO_BIN_EXE_taupe"))
+            .arg(format!("--engine={}", engine))
+            .arg(asset)
+            .output()
+            .unwrap();
+        let echo = format!(
+            "Parsing source: \n {}\n",
+            fs::read_to_string(asset).unwrap()
+        );
+        let diagnostics = String::from_utf8(output.stderr).unwrap();
+        (
+            String::from_utf8(output.stdout).unwrap(),
+            diagnostics
+                .strip_prefix(&echo)
+                .unwrap_or(&diagnostics)
+                .to_string(),
+        )
+    }
+
+    fn given_this_source_with(source: &str, compiled: bool) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        let mut environment = Environment::new();
+        for function in &resolved.functions {
+            environment.define(function.clone());
+        }
+        let options = Options::default();
+        if compiled {
+            execute(&compile(&resolved), &mut environment, &options)
+        } else {
+            evaluate(&resolved.expression, &mut environment, &options)
+        }
+    }
+}
@@ -6,7 +6,7 @@ mod parser {
 
     #[test]
     pub fn verify_that_parser_loads() {
-        let args = vec!["tests/assets/invalid.tau".to_string()];
+        let args = ["tests/assets/invalid.tau".to_string()];
         let file = File::open(&args[0]).unwrap();
         let mut parser = Parser::new_from(file);
 
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod functions {
+    use taupe::{
+        core::{
+            checking::Checker,
+            environment::Environment,
+            evaluation::evaluate,
+            options::Options,
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_calls_a_function() {
+        let result = given_this_source("fn double(x) {\n  * x 2\n}\ndouble(21)");
+
+        assert_eq!(result, Ok(Value::Integer(42)));
+    }
+
+    #[test]
+    fn it_calls_a_function_recursively() {
+        let source =
+            "fn fact(n: int) -> int {\n  if < n 2 { 1 } else { * n fact(- n 1) }\n}\nfact(20)";
+
+        assert_eq!(
+            given_this_source(source),
+            Ok(Value::Integer(2432902008176640000))
+        );
+    }
+
+    #[test]
+    fn it_calls_a_function_declared_further_down() {
+        let source = "fn even(n) {\n  if < n 1 { true } else { odd(- n 1) }\n}\nfn odd(n) {\n  if < n 1 { false } else { even(- n 1) }\n}\neven(10)";
+
+        assert_eq!(given_this_source(source), Ok(Value::Boolean(true)));
+    }
+
+    #[test]
+    fn it_returns_tuples() {
+        let source = "fn divide(a: int, b: int) -> (int, int) {\n  (// a b, % a b)\n}\nlet (q, r) = divide(17, 5)\n+ * q 10 r";
+
+        assert_eq!(given_this_source(source), Ok(Value::Integer(32)));
+    }
+
+    #[test]
+    fn it_keeps_the_variables_of_the_caller_apart() {
+        let source = "let mut x = 1\nfn f(x) {\n  let y = + x 1\n  y\n}\nlet z = f(10)\n+ x z";
+
+        assert_eq!(given_this_source(source), Ok(Value::Integer(12)));
+    }
+
+    #[test]
+    fn it_reads_the_immutable_globals_of_the_enclosing_scope() {
+        assert_eq!(
+            given_this_source("let outer = 1\nfn f() {\n  outer\n}\nf()"),
+            Ok(Value::Integer(1))
+        );
+        assert_eq!(
+            given_this_source("let rate = 2\nfn f(x: int) -> int {\n  * x rate\n}\nf(21)"),
+            Ok(Value::Integer(42))
+        );
+    }
+
+    #[test]
+    fn it_refuses_other_variables_of_the_enclosing_scope() {
+        assert_eq!(
+            given_this_source("let mut count = 0\nfn f() {\n  count\n}\nf()"),
+            Err(Error::UndefinedVariable("count".to_string()))
+        );
+        assert_eq!(
+            given_this_source("let outer = 1\nfn f() {\n  outer = 2\n}\nf()"),
+            Err(Error::UndefinedVariable("outer".to_string()))
+        );
+        assert_eq!(
+            given_this_source("if true {\n  let inner = 1\n  fn f() {\n    inner\n  }\n  f()\n}"),
+            Err(Error::UndefinedVariable("inner".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_refuses_a_global_read_before_its_declaration() {
+        assert_eq!(
+            given_this_source("print f()\nlet late = 1\nfn f() {\n  late\n}"),
+            Err(Error::UndefinedVariable("late".to_string()))
+        );
+        assert_eq!(
+            given_this_source(
+                "if true {\n  let inner = 1\n  print f()\n}\nlet late = 2\nfn f() {\n  late\n}"
+            ),
+            Err(Error::UndefinedVariable("late".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_reports_undefined_functions_before_running() {
+        assert_eq!(
+            given_this_source("if false { missing(1) }"),
+            Err(Error::UndefinedFunction("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_lets_a_function_shadow_the_stdlib() {
+        let source = "fn len(s) {\n  0\n}\n+ len(\"abc\") string.len(\"abc\")";
+
+        assert_eq!(given_this_source(source), Ok(Value::Integer(3)));
+    }
+
+    fn given_this_source(source: &str) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        Checker::new().check(&resolved)?;
+        let mut environment = Environment::new();
+        for function in resolved.functions {
+            environment.define(function);
+        }
+        evaluate(&resolved.expression, &mut environment, &Options::default())
+    }
+}
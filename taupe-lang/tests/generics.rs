@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod generics {
+    use taupe::{
+        core::{checking::Checker, resolution::Resolver, types::Type, values::Error},
+        parser::Parser,
+        translator::Translator,
+    };
+
+    const DECLARATIONS: &str = "enum Option<T> {\n  Some(T),\n  None,\n}\n\
+                                struct Pair<A, B> {\n  left: A,\n  right: B,\n}\n\
+                                fn unwrap_or<T>(option: Option<T>, fallback: T) -> T {\n  \
+                                match option {\n    Some(value) => value,\n    None => fallback,\n  }\n}\n";
+
+    #[test]
+    fn it_instantiates_generic_functions_at_each_call() {
+        assert_eq!(given_this_source("unwrap_or(Some(1), 2)"), Ok(Type::Int));
+        assert_eq!(
+            given_this_source("unwrap_or(None, \"none\")"),
+            Ok(Type::Str)
+        );
+        assert_eq!(
+            given_this_source("unwrap_or(Some(1), \"one\")"),
+            Err(Error::TypeMismatch(
+                "unwrap_or".to_string(),
+                vec![
+                    Type::Tuple(vec![
+                        Type::Named(
+                            "Option".to_string(),
+                            vec![Type::Named("T".to_string(), vec![])]
+                        ),
+                        Type::Named("T".to_string(), vec![])
+                    ]),
+                    Type::Tuple(vec![
+                        Type::Named("Option".to_string(), vec![Type::Int]),
+                        Type::Str
+                    ])
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn it_types_the_fields_of_generic_structs() {
+        assert_eq!(
+            given_this_source("let pair = Pair(1, \"one\")\npair.right"),
+            Ok(Type::Str)
+        );
+        assert_eq!(
+            given_this_source("let pair: Pair<int, str> = Pair(\"one\", 1)"),
+            Err(Error::TypeMismatch(
+                "pair".to_string(),
+                vec![
+                    Type::Named("Pair".to_string(), vec![Type::Int, Type::Str]),
+                    Type::Named("Pair".to_string(), vec![Type::Str, Type::Int])
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn it_keeps_type_parameters_opaque() {
+        assert_eq!(
+            given_this_source("fn increment<T>(x: T) -> T {\n  + x 1\n}"),
+            Err(Error::TypeMismatch(
+                "Sum".to_string(),
+                vec![Type::Named("T".to_string(), vec![]), Type::Int]
+            ))
+        );
+    }
+
+    #[test]
+    fn it_reports_a_type_given_the_wrong_number_of_arguments() {
+        assert_eq!(
+            given_this_source("let none: Option<int, int> = None"),
+            Err(Error::UndefinedType("Option".to_string()))
+        );
+    }
+
+    fn given_this_source(source: &str) -> Result<Type, Error> {
+        let tokens = Parser::new().parse(&format!("{}{}", DECLARATIONS, source));
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        Checker::new().check(&resolved)
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod inference {
+    use taupe::{
+        core::{inference::Inferencer, resolution::Resolver, types::Type, values::Error},
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_infers_unannotated_bindings() {
+        assert_eq!(
+            given_this_source("let x = 1\nlet (a, b) = (\"s\", / x 2)\nprint (a, b)"),
+            Ok(vec![
+                (1, "let x: int".to_string()),
+                (2, "let a: str".to_string()),
+                (2, "let b: float".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_infers_polymorphic_functions() {
+        assert_eq!(
+            given_this_source(
+                "fn swap(p) {\n  let (a, b) = p\n  (b, a)\n}\nlet s = swap((1, \"one\"))\nprint swap(s)"
+            ),
+            Ok(vec![
+                (1, "fn swap<T, U>(p: (T, U)) -> (U, T)".to_string()),
+                (2, "let a: T".to_string()),
+                (2, "let b: U".to_string()),
+                (5, "let s: (str, int)".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_restricts_variables_to_what_operators_take() {
+        assert_eq!(
+            given_this_source("fn add(a, b) {\n  + a b\n}\nprint add(\"a\", \"b\")"),
+            Ok(vec![(1, "fn add<T: num | str>(a: T, b: T) -> T".to_string())])
+        );
+        assert_eq!(
+            given_this_source("fn add(a, b) {\n  + a b\n}\nprint add(true, false)"),
+            Err(Error::TypeConflict(
+                Box::new((Type::Named("num | str".to_string(), vec![]), 2)),
+                Box::new((Type::Bool, 4))
+            ))
+        );
+    }
+
+    #[test]
+    fn it_infers_through_generic_types() {
+        let source = "enum Option<T> {\n  Some(T),\n  None,\n}\n\
+                      fn or<T>(option: Option<T>, fallback: T) -> T {\n  \
+                      match option {\n    Some(value) => value,\n    None => fallback,\n  }\n}\n\
+                      let x = or(Some(1.5), 2.0)";
+
+        assert_eq!(
+            given_this_source(source),
+            Ok(vec![
+                (5, "fn or<T>(option: Option<T>, fallback: T) -> T".to_string()),
+                (6, "let value: T".to_string()),
+                (11, "let x: float".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_infers_mutually_recursive_functions() {
+        let source = "fn even(n) {\n  if < n 1 { true } else { odd(- n 1) }\n}\n\
+                      fn odd(n) {\n  if < n 1 { false } else { even(- n 1) }\n}";
+
+        assert_eq!(
+            given_this_source(source),
+            Ok(vec![
+                (1, "fn even(n: int) -> bool".to_string()),
+                (4, "fn odd(n: int) -> bool".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_points_at_both_conflicting_sites() {
+        assert_eq!(
+            given_this_source("let mut x = 1\nprint x\nx = \"one\""),
+            Err(Error::TypeConflict(
+                Box::new((Type::Int, 1)),
+                Box::new((Type::Str, 3))
+            ))
+        );
+        assert_eq!(
+            given_this_source("fn f(a) {\n  - a 1\n}\nprint f(\"x\")"),
+            Err(Error::TypeConflict(
+                Box::new((Type::Int, 2)),
+                Box::new((Type::Str, 4))
+            ))
+        );
+    }
+
+    #[test]
+    fn it_types_calls_to_the_stdlib() {
+        assert_eq!(
+            given_this_source("let words = string.split(\"a b\", \" \")\nprint words"),
+            Ok(vec![(1, "let words: [str]".to_string())])
+        );
+        assert_eq!(
+            given_this_source("let n = string.len(\"abc\")\nprint + n \"x\""),
+            Err(Error::TypeConflict(
+                Box::new((Type::Int, 1)),
+                Box::new((Type::Str, 2))
+            ))
+        );
+    }
+
+    #[test]
+    fn it_checks_an_index_once_the_tuple_is_known() {
+        assert_eq!(
+            given_this_source("fn swap(p) {\n  (p.1, p.0)\n}\nprint swap((1, \"a\"))"),
+            Ok(vec![(1, "fn swap<T, U, V>(p: T) -> (U, V)".to_string())])
+        );
+        assert_eq!(
+            given_this_source(
+                "fn first(p) {\n  p.0\n}\nprint + first((1, \"a\")) first((\"b\", 2))"
+            ),
+            Err(Error::TypeConflict(
+                Box::new((Type::Int, 4)),
+                Box::new((Type::Str, 4))
+            ))
+        );
+        assert_eq!(
+            given_this_source("fn first(p) {\n  p.0\n}\nprint first(5)"),
+            Err(Error::TypeConflict(
+                Box::new((Type::Tuple(vec![Type::Unknown]), 2)),
+                Box::new((Type::Int, 4))
+            ))
+        );
+    }
+
+    fn given_this_source(source: &str) -> Result<Vec<(usize, String)>, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        Inferencer::new().infer(&resolved)
+    }
+}
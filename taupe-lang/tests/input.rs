@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod input {
+    use std::{
+        cell::RefCell,
+        fs,
+        io::{Cursor, Write},
+        path::Path,
+        process::{Command, Stdio},
+        rc::Rc,
+    };
+
+    use taupe::{
+        core::{
+            checking::Checker,
+            environment::Environment,
+            input::{InputRef, Reader},
+            interpretation::interpret_with_options,
+            options::{Backend, Options},
+            output::{Buffer, OutputRef},
+            resolution::Resolver,
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_reads_lines_from_the_input_of_the_options() {
+        let source = "let name = input(\"name? \")\nprint string.upper(name)\nprint input()";
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let buffer = given_this_source_and_input_with(source, "taupe\nlang\n", engine);
+
+            assert_eq!(buffer.printed, "name? TAUPE\nlang\n", "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn it_reads_none_at_the_end_of_the_input() {
+        let source = "print input()\nprint input()\nprint input()";
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let buffer = given_this_source_and_input_with(source, "last", engine);
+
+            assert_eq!(buffer.printed, "last\nNone\nNone\n", "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn it_reads_all_that_is_left() {
+        let source = "let first = input()\nlet rest = read_all()\nprint rest\nprint first";
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let buffer = given_this_source_and_input_with(source, "a\nb\nc", engine);
+
+            assert_eq!(buffer.printed, "b\nc\na\n", "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn it_reads_piped_stdin() {
+        let script = Path::new(env!("CARGO_TARGET_TMPDIR")).join("read_all.tau");
+        fs::write(&script, "print string.upper(read_all())").unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_taupe"))
+            .arg(&script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"piped").unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "PIPED\n");
+    }
+
+    fn given_this_source_and_input_with(source: &str, input: &str, engine: Backend) -> Buffer {
+        let buffer = Rc::new(RefCell::new(Buffer::default()));
+        let output = OutputRef::from(buffer.clone());
+        let tokens = Parser::new().with_output(output.clone()).parse(source);
+        let options = Options {
+            engine,
+            output,
+            input: InputRef::new(Reader::new(Cursor::new(input.to_string()))),
+            ..Options::default()
+        };
+
+        interpret_with_options(
+            Translator::from(tokens),
+            &mut Resolver::new(),
+            &mut Checker::new(),
+            &mut Environment::new(),
+            &options,
+        );
+
+        buffer.take()
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod interrupt {
+    use std::{thread, time::Duration};
+
+    use taupe::{
+        core::{
+            compilation::compile,
+            environment::Environment,
+            evaluation::evaluate,
+            execution::execute,
+            options::{Backend, Options},
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    const ENDLESS: &str = "let a = 1\nlet mut total = 0\nfor i in [0..=2000000000] {\n  \
+                           total += i\n}\ntotal";
+
+    #[test]
+    fn it_stops_when_interrupted() {
+        let engines = [
+            Backend::Tree,
+            Backend::Bytecode,
+            #[cfg(feature = "jit")]
+            Backend::Jit,
+        ];
+
+        for engine in engines {
+            let options = Options {
+                engine,
+                ..Options::default()
+            };
+            let interrupt = options.interrupt.clone();
+            let raising = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                interrupt.raise();
+            });
+            let tokens = Parser::new().parse(ENDLESS);
+            let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+            let mut environment = Environment::new();
+
+            let result = match engine {
+                Backend::Bytecode => execute(&compile(&resolved), &mut environment, &options),
+                _ => evaluate(&resolved.expression, &mut environment, &options),
+            };
+            raising.join().unwrap();
+
+            assert_eq!(result, Err(Error::Interrupted), "{:?}", engine);
+            assert_eq!(environment.get(0), Some(&Value::Integer(1)), "{:?}", engine);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_returns_to_the_prompt_on_ctrl_c() {
+        use std::{
+            io::Write,
+            process::{Command, Stdio},
+        };
+
+        let mut repl = Command::new(env!("CARGO_BIN_EXE_taupe"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = repl.stdin.take().unwrap();
+        writeln!(stdin, "let a = 41").unwrap();
+        writeln!(stdin, "let mut total = 0").unwrap();
+        writeln!(stdin, "for i in [0..=2000000000] {{ total += i }}").unwrap();
+        thread::sleep(Duration::from_millis(500));
+        // SAFETY: sends a signal to a child this test owns.
+        unsafe {
+            libc::kill(repl.id() as libc::pid_t, libc::SIGINT);
+        }
+        writeln!(stdin, "print + a 1").unwrap();
+        drop(stdin);
+
+        let output = repl.wait_with_output().unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+
+        assert!(output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Interrupted"));
+        assert!(stdout.trim_end().ends_with("42\n>"), "{}", stdout);
+    }
+}
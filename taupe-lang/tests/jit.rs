@@ -0,0 +1,118 @@
+#[cfg(all(test, feature = "jit"))]
+mod jit {
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            options::{Backend, Options, Overflow},
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    const SOURCES: [&str; 10] = [
+        "let mut total = 0\nfor i in [1..10000] {\n  total += * i i\n}\ntotal",
+        "fn fib(n: int) -> int {\n  if < n 2 { n } else { + fib(- n 1) fib(- n 2) }\n}\nfib(20)",
+        "fn collatz(n) {\n  let mut steps = 0\n  let mut m = n\n  for i in [1..1000] {\n    \
+         if > m 1 {\n      if < 0 % m 2 { m = + * 3 m 1 } else { m = // m 2 }\n      \
+         steps += 1\n    }\n  }\n  steps\n}\ncollatz(27)",
+        "fn even(n: int) -> bool {\n  if < n 1 { true } else { !even(- n 1) }\n}\neven(10)",
+        "let mut x = 0\nfor i in [0..10] {\n  x = + x ^ // - i 5 3 % - i 5 - 0 4\n}\nx",
+        "let mut product = 1\nfor i in [1..30] {\n  product = * product i\n}\nproduct",
+        "fn f(n) {\n  * n 4611686018427387904\n}\nf(4)",
+        "let mut x = 1\nfor i in [1..3] {\n  print i\n  x += i\n}\nx",
+        "let name = \"taupe\"\nlet mut count = 0\nfor i in [1..5] {\n  count += i\n}\n(name, count)",
+        "fn g(n) {\n  - 0 n\n}\ng(- - 0 9223372036854775807 1)",
+    ];
+
+    #[test]
+    fn it_evaluates_to_the_same_values_as_the_interpreter() {
+        for overflow in [
+            Overflow::Promote,
+            Overflow::Checked,
+            Overflow::Wrapping,
+            Overflow::Saturating,
+        ] {
+            for source in SOURCES {
+                assert_eq!(
+                    given_this_source_with(source, overflow, Backend::Jit),
+                    given_this_source_with(source, overflow, Backend::Tree),
+                    "{} with {:?}",
+                    source,
+                    overflow
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_leaves_failures_to_the_interpreter() {
+        let sources = [
+            "fn f(n) {\n  // 10 n\n}\nf(0)",
+            "let mut total = 0\nfor i in [1..4] {\n  total += % 5 - 2 i\n}\ntotal",
+        ];
+
+        for source in sources {
+            let jit = given_this_source_with(source, Overflow::Promote, Backend::Jit);
+            assert!(matches!(jit, Err(Error::DivisionByZero(..))), "{}", source);
+            assert_eq!(
+                jit,
+                given_this_source_with(source, Overflow::Promote, Backend::Tree)
+            );
+        }
+    }
+
+    #[test]
+    fn it_writes_back_the_variables_of_a_loop() {
+        let source = "let mut total = 0\nlet mut odd = false\nfor i in [1..=99] {\n  \
+                      total += i\n  odd = !odd\n}\n(total, odd)";
+
+        assert_eq!(
+            given_this_source_with(source, Overflow::Promote, Backend::Jit),
+            Ok(Value::Tuple(vec![
+                Value::Integer(4950),
+                Value::Boolean(true)
+            ]))
+        );
+    }
+
+    #[test]
+    fn it_leaves_deep_recursion_to_the_interpreter() {
+        let source = "fn depth(n: int) -> int {\n  \
+                      if < n 1 { 0 } else { + 1 depth(- n 1) }\n}\ndepth(";
+
+        assert_eq!(
+            given_this_source_with(&format!("{}5000)", source), Overflow::Promote, Backend::Jit),
+            Ok(Value::Integer(5000))
+        );
+        assert_eq!(
+            given_this_source_with(
+                &format!("{}10000)", source),
+                Overflow::Promote,
+                Backend::Jit
+            ),
+            Err(Error::StackOverflow(10000))
+        );
+    }
+
+    fn given_this_source_with(
+        source: &str,
+        overflow: Overflow,
+        engine: Backend,
+    ) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        let mut environment = Environment::new();
+        for function in &resolved.functions {
+            environment.define(function.clone());
+        }
+        let options = Options {
+            overflow,
+            engine,
+            ..Options::default()
+        };
+        evaluate(&resolved.expression, &mut environment, &options)
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod limits {
+    use std::time::Duration;
+
+    use taupe::{
+        core::{
+            compilation::compile,
+            environment::Environment,
+            evaluation::evaluate,
+            execution::execute,
+            options::{Backend, Options},
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    const ENDLESS: &str = "let mut total = 0\nfor i in [0..=2000000000] {\n  total += i\n}\ntotal";
+    const DOUBLING: &str = "let mut s = \"x\"\nfor i in [0..=100] {\n  s = + s s\n}\ns";
+
+    #[test]
+    fn it_stops_after_the_maximum_steps() {
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let options = Options {
+                engine,
+                max_steps: Some(10_000),
+                ..Options::default()
+            };
+
+            assert_eq!(
+                given_this_source_with(ENDLESS, &options),
+                Err(Error::StepLimit(10_000)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_stops_once_the_values_take_the_maximum_bytes() {
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let options = Options {
+                engine,
+                max_bytes: Some(1 << 20),
+                ..Options::default()
+            };
+
+            assert_eq!(
+                given_this_source_with(DOUBLING, &options),
+                Err(Error::MemoryLimit(1 << 20)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_refuses_to_build_a_value_past_the_maximum_bytes() {
+        for source in [
+            "string.repeat(\"ab\", 100000000000)",
+            "string.replace(string.repeat(\"a\", 1000), \"a\", string.repeat(\"b\", 1000))",
+            "** 3 2000000000",
+        ] {
+            for engine in [Backend::Tree, Backend::Bytecode] {
+                let options = Options {
+                    engine,
+                    max_bytes: Some(1 << 16),
+                    ..Options::default()
+                };
+
+                assert_eq!(
+                    given_this_source_with(source, &options),
+                    Err(Error::MemoryLimit(1 << 16)),
+                    "{} {:?}",
+                    source,
+                    engine
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_times_out() {
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let options = Options {
+                engine,
+                timeout: Some(Duration::from_millis(50)),
+                ..Options::default()
+            };
+
+            assert_eq!(
+                given_this_source_with(ENDLESS, &options),
+                Err(Error::Timeout(Duration::from_millis(50))),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_times_out_before_building_a_value_it_could_not_in_time() {
+        for source in [
+            "let b = 3\n** b 100000000",
+            "string.repeat(\"ab\", 100000000)",
+        ] {
+            for engine in [Backend::Tree, Backend::Bytecode] {
+                let options = Options {
+                    engine,
+                    timeout: Some(Duration::from_millis(100)),
+                    ..Options::default()
+                };
+
+                assert_eq!(
+                    given_this_source_with(source, &options),
+                    Err(Error::Timeout(Duration::from_millis(100))),
+                    "{} {:?}",
+                    source,
+                    engine
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_runs_within_its_limits() {
+        let source = "let mut total = 0\nfor i in [1..=100] {\n  total += i\n}\ntotal";
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let options = Options {
+                engine,
+                max_steps: Some(10_000),
+                max_bytes: Some(1024),
+                timeout: Some(Duration::from_secs(60)),
+                ..Options::default()
+            };
+
+            assert_eq!(
+                given_this_source_with(source, &options),
+                Ok(Value::Integer(5050)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn it_keeps_native_code_within_the_limits() {
+        let options = Options {
+            engine: Backend::Jit,
+            max_steps: Some(10_000),
+            ..Options::default()
+        };
+
+        assert_eq!(
+            given_this_source_with(ENDLESS, &options),
+            Err(Error::StepLimit(10_000))
+        );
+    }
+
+    fn given_this_source_with(source: &str, options: &Options) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        let mut environment = Environment::new();
+        match options.engine {
+            Backend::Bytecode => execute(&compile(&resolved), &mut environment, options),
+            _ => evaluate(&resolved.expression, &mut environment, options),
+        }
+    }
+}
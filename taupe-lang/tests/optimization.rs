@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod optimization {
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            optimization::optimize,
+            options::{Options, Overflow},
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_evaluates_to_the_same_value_once_optimized() {
+        let sources = [
+            "+ 4 4",
+            "let mut total = 0\nfor i in [1..10] {\n  total += * 2 3\n}\ntotal",
+            "if > 2 1 { \"yes\" } else { \"no\" }",
+            "fn half(n) {\n  / n 2\n}\nhalf(+ 1 2)",
+            "** 2 64",
+        ];
+
+        for source in sources {
+            let options = Options::default();
+            assert_eq!(
+                given_this_source(source, &options, true),
+                given_this_source(source, &options, false)
+            );
+        }
+    }
+
+    #[test]
+    fn it_still_reports_errors_of_constant_operators() {
+        let options = Options {
+            overflow: Overflow::Checked,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            given_this_source("* 9223372036854775807 2", &options, true),
+            Err(Error::IntegerOverflow(
+                "Product".to_string(),
+                vec![Value::Integer(i64::MAX), Value::Integer(2)]
+            ))
+        );
+        assert!(matches!(
+            given_this_source("// 1 0", &options, true),
+            Err(Error::DivisionByZero(..))
+        ));
+    }
+
+    fn given_this_source(source: &str, options: &Options, optimized: bool) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let mut resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        if optimized {
+            resolved = optimize(resolved, options);
+        }
+        let mut environment = Environment::new();
+        for function in resolved.functions {
+            environment.define(function);
+        }
+        evaluate(&resolved.expression, &mut environment, options)
+    }
+}
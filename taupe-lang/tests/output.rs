@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod output {
+    use std::{cell::RefCell, rc::Rc};
+
+    use taupe::{
+        core::{
+            checking::Checker,
+            environment::Environment,
+            interpretation::interpret_with_options,
+            options::{Backend, Options},
+            output::{Buffer, OutputRef},
+            resolution::Resolver,
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_prints_to_the_output_of_the_options() {
+        let source = "print \"taupe\"\nprint + 1 2\nprint (1, \"a\")";
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let buffer = given_this_source_with(source, engine);
+
+            assert_eq!(buffer.printed, "taupe\n3\n(1, \"a\")\n", "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn it_reports_errors_and_warnings_apart() {
+        let source = "let unused = 1\nprint \"before\"\n// 1 0";
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            let buffer = given_this_source_with(source, engine);
+
+            assert_eq!(buffer.printed, "before\n", "{:?}", engine);
+            let diagnostics: Vec<_> = buffer.diagnostics.lines().rev().take(2).collect();
+            assert!(diagnostics[0].starts_with("DivisionByZero"), "{:?}", engine);
+            assert_eq!(diagnostics[1], "UnusedVariable(\"unused\")", "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn it_reports_what_it_parses() {
+        let buffer = Rc::new(RefCell::new(Buffer::default()));
+
+        Parser::new()
+            .with_output(OutputRef::from(buffer.clone()))
+            .parse("print 1");
+
+        assert_eq!(buffer.borrow().printed, "");
+        assert!(buffer.borrow().diagnostics.contains("print 1"));
+    }
+
+    fn given_this_source_with(source: &str, engine: Backend) -> Buffer {
+        let buffer = Rc::new(RefCell::new(Buffer::default()));
+        let output = OutputRef::from(buffer.clone());
+        let tokens = Parser::new().with_output(output.clone()).parse(source);
+        let options = Options {
+            engine,
+            output,
+            ..Options::default()
+        };
+
+        interpret_with_options(
+            Translator::from(tokens),
+            &mut Resolver::new(),
+            &mut Checker::new(),
+            &mut Environment::new(),
+            &options,
+        );
+
+        buffer.take()
+    }
+}
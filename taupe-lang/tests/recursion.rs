@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod recursion {
+    use taupe::{
+        core::{
+            compilation::compile,
+            environment::Environment,
+            evaluation::evaluate,
+            execution::execute,
+            options::{Backend, Options},
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    const COUNT: &str = "fn count(n, total) {\n  \
+                         if < n 1 { total } else { count(- n 1, + total n) }\n}\n";
+    const DEPTH: &str = "fn depth(n) {\n  if < n 1 { 0 } else { + 1 depth(- n 1) }\n}\n";
+
+    #[test]
+    fn it_runs_a_script_of_many_lines() {
+        let mut source = "let mut total = 0\n".to_string();
+        for i in 0..10_000 {
+            source.push_str(&format!("let x{} = {}\ntotal += x{}\n", i, i, i));
+        }
+        source.push_str("total");
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            assert_eq!(
+                given_this_source_with(&source, engine, 10_000),
+                Ok(Value::Integer(49_995_000)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_makes_tail_calls_past_the_maximum_depth() {
+        let source = format!("{}count(100000, 0)", COUNT);
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            assert_eq!(
+                given_this_source_with(&source, engine, 10_000),
+                Ok(Value::Integer(5_000_050_000)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_recurses_up_to_the_maximum_depth() {
+        let source = format!("{}depth(5000)", DEPTH);
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            assert_eq!(
+                given_this_source_with(&source, engine, 5001),
+                Ok(Value::Integer(5000)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_overflows_past_the_maximum_depth() {
+        let source = format!("{}depth(5000)", DEPTH);
+
+        for engine in [Backend::Tree, Backend::Bytecode] {
+            assert_eq!(
+                given_this_source_with(&source, engine, 5000),
+                Err(Error::StackOverflow(5000)),
+                "{:?}",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn it_releases_every_frame_of_an_overflow() {
+        let tokens = Parser::new().parse(&format!("let a = 1\n{}depth(200)", DEPTH));
+        let resolved = Resolver::new().resolve(&Translator::from(tokens)).unwrap();
+        let options = Options {
+            max_depth: 100,
+            ..Options::default()
+        };
+
+        for compiled in [false, true] {
+            let mut environment = Environment::new();
+            for function in &resolved.functions {
+                environment.define(function.clone());
+            }
+            let result = if compiled {
+                execute(&compile(&resolved), &mut environment, &options)
+            } else {
+                evaluate(&resolved.expression, &mut environment, &options)
+            };
+
+            assert_eq!(result, Err(Error::StackOverflow(100)));
+            assert_eq!(environment.depth(), 0);
+            assert_eq!(environment.len(), 1);
+        }
+    }
+
+    fn given_this_source_with(
+        source: &str,
+        engine: Backend,
+        max_depth: usize,
+    ) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        let mut environment = Environment::new();
+        for function in &resolved.functions {
+            environment.define(function.clone());
+        }
+        let options = Options {
+            engine,
+            max_depth,
+            ..Options::default()
+        };
+        match engine {
+            Backend::Bytecode => execute(&compile(&resolved), &mut environment, &options),
+            _ => evaluate(&resolved.expression, &mut environment, &options),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod scoping {
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            options::Options,
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_keeps_assignments_made_inside_a_loop() {
+        let result =
+            given_this_source("let mut total = 1\nfor i in [1..=20] {\n  total *= i\n}\ntotal");
+
+        assert_eq!(result, Ok(Value::Integer(2432902008176640000)));
+    }
+
+    #[test]
+    fn it_evaluates_a_binding_once() {
+        let result =
+            given_this_source("let mut calls = 0\nlet x = { calls += 1\n 5 }\n+ x x\ncalls");
+
+        assert_eq!(result, Ok(Value::Integer(1)));
+    }
+
+    #[test]
+    fn it_shadows_a_variable_until_the_block_ends() {
+        assert_eq!(
+            given_this_source("let x = 1\nif true {\n  let mut x = 2\n  x = 3\n}\nx"),
+            Ok(Value::Integer(1))
+        );
+        assert_eq!(
+            given_this_source("let x = 1\n{\n  let x = 2\n}\nx"),
+            Ok(Value::Integer(1))
+        );
+    }
+
+    #[test]
+    fn it_forgets_declarations_once_the_block_ends() {
+        assert_eq!(
+            given_this_source("for i in [0..1] {\n  let y = i\n}\ny"),
+            Err(Error::UndefinedVariable("y".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_keeps_globals_between_evaluations() {
+        let mut resolver = Resolver::persistent();
+        let mut environment = Environment::new();
+        let options = Options::default();
+
+        let first = resolver
+            .resolve(&Translator::from(Parser::new().parse("let x = 41")))
+            .unwrap();
+        let second = resolver
+            .resolve(&Translator::from(Parser::new().parse("+ x 1")))
+            .unwrap();
+        evaluate(&first.expression, &mut environment, &options).unwrap();
+
+        assert_eq!(
+            evaluate(&second.expression, &mut environment, &options),
+            Ok(Value::Integer(42))
+        );
+    }
+
+    fn given_this_source(source: &str) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        evaluate(
+            &resolved.expression,
+            &mut Environment::new(),
+            &Options::default(),
+        )
+    }
+}
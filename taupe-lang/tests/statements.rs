@@ -2,7 +2,7 @@
 mod statements {
     use std::fs::File;
 
-    use taupe::{parser::Parser, core::interpretation::interpret, translator::Translator};
+    use taupe::{core::interpretation::interpret, parser::Parser, translator::Translator};
 
     #[test]
     fn it_parses_an_if_statement() {
@@ -14,13 +14,43 @@ mod statements {
         given_this_file_should_not_panic("tests/assets/let.tau");
     }
 
+    #[test]
+    fn it_destructures_let_bindings() {
+        given_this_file_should_not_panic("tests/assets/destructuring.tau");
+    }
+
     #[test]
     fn it_parses_a_for_statement() {
         given_this_file_should_not_panic("tests/assets/for.tau");
     }
 
+    #[test]
+    fn it_makes_tuples() {
+        given_this_file_should_not_panic("tests/assets/tuple.tau");
+    }
+
+    #[test]
+    fn it_declares_functions() {
+        given_this_file_should_not_panic("tests/assets/function.tau");
+    }
+
+    #[test]
+    fn it_calls_string_functions() {
+        given_this_file_should_not_panic("tests/assets/string.tau");
+    }
+
+    #[test]
+    fn it_calls_math_functions() {
+        given_this_file_should_not_panic("tests/assets/math.tau");
+    }
+
+    #[test]
+    fn it_declares_generic_types() {
+        given_this_file_should_not_panic("tests/assets/generics.tau");
+    }
+
     fn given_this_file_should_not_panic(file: &str) {
-        let args = vec![file.to_string()];
+        let args = [file.to_string()];
         let file = File::open(&args[0]).unwrap();
         let mut parser = Parser::new_from(file);
         let tokens = parser.parse_source().unwrap();
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tuples {
+    use taupe::{
+        core::{
+            environment::Environment,
+            evaluation::evaluate,
+            options::Options,
+            resolution::Resolver,
+            values::{Error, Value},
+        },
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_makes_tuples_of_mixed_values() {
+        assert_eq!(
+            given_this_source("(1, \"a\", true)"),
+            Ok(Value::Tuple(vec![
+                Value::Integer(1),
+                Value::String("a".to_string()),
+                Value::Boolean(true)
+            ]))
+        );
+        assert_eq!(
+            given_this_source("(+ 1 2,)"),
+            Ok(Value::Tuple(vec![Value::Integer(3)]))
+        );
+    }
+
+    #[test]
+    fn it_still_groups_without_a_comma() {
+        assert_eq!(given_this_source("( + 1 2 )"), Ok(Value::Integer(3)));
+    }
+
+    #[test]
+    fn it_indexes_tuples() {
+        assert_eq!(
+            given_this_source("let t = (1, (2, 3))\n+ t.0 t.1.1"),
+            Ok(Value::Integer(4))
+        );
+        assert_eq!(given_this_source("(7, 8).1"), Ok(Value::Integer(8)));
+    }
+
+    #[test]
+    fn it_refuses_an_index_out_of_range() {
+        assert_eq!(
+            given_this_source("let t = (1, 2)\nt.2"),
+            Err(Error::InvalidValues(
+                "Index".to_string(),
+                vec![
+                    Value::Tuple(vec![Value::Integer(1), Value::Integer(2)]),
+                    Value::Integer(2)
+                ]
+            ))
+        );
+    }
+
+    fn given_this_source(source: &str) -> Result<Value, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        evaluate(
+            &resolved.expression,
+            &mut Environment::new(),
+            &Options::default(),
+        )
+    }
+}
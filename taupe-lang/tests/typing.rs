@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod typing {
+    use taupe::{
+        core::{checking::Checker, resolution::Resolver, types::Type, values::Error},
+        parser::Parser,
+        translator::Translator,
+    };
+
+    #[test]
+    fn it_types_annotated_and_inferred_bindings() {
+        assert_eq!(given_this_source("let x: int = 3\n+ x 1"), Ok(Type::Int));
+        assert_eq!(given_this_source("let x = 1.5\n* x 2"), Ok(Type::Float));
+        assert_eq!(
+            given_this_source("let (a, b) = (\"s\", true)\n(b, a)"),
+            Ok(Type::Tuple(vec![Type::Bool, Type::Str]))
+        );
+    }
+
+    #[test]
+    fn it_reports_adding_a_string_to_an_integer() {
+        assert_eq!(
+            given_this_source("let name = \"taupe\"\nif false {\n  print + name 1\n}"),
+            Err(Error::TypeMismatch(
+                "Sum".to_string(),
+                vec![Type::Str, Type::Int]
+            ))
+        );
+    }
+
+    #[test]
+    fn it_reports_a_value_that_does_not_fit_its_annotation() {
+        assert_eq!(
+            given_this_source("let x: float = 3"),
+            Err(Error::TypeMismatch(
+                "x".to_string(),
+                vec![Type::Float, Type::Int]
+            ))
+        );
+        assert_eq!(
+            given_this_source("let mut total: int = 0\ntotal /= 2"),
+            Err(Error::TypeMismatch(
+                "total".to_string(),
+                vec![Type::Int, Type::Float]
+            ))
+        );
+    }
+
+    #[test]
+    fn it_checks_function_signatures() {
+        assert_eq!(
+            given_this_source("fn f(a: str) -> int {\n  string.len(a)\n}\nf(1)"),
+            Err(Error::TypeMismatch(
+                "f".to_string(),
+                vec![Type::Tuple(vec![Type::Str]), Type::Tuple(vec![Type::Int])]
+            ))
+        );
+        assert_eq!(
+            given_this_source("fn f(a: str) -> int {\n  a\n}\nf(\"x\")"),
+            Err(Error::TypeMismatch(
+                "f".to_string(),
+                vec![Type::Int, Type::Str]
+            ))
+        );
+        assert_eq!(
+            given_this_source("fn f(a: str) -> int {\n  string.len(a)\n}\n+ f(\"x\") 1"),
+            Ok(Type::Int)
+        );
+    }
+
+    #[test]
+    fn it_checks_calls_to_the_stdlib() {
+        assert_eq!(
+            given_this_source("string.len(1)"),
+            Err(Error::TypeMismatch(
+                "string.len".to_string(),
+                vec![Type::Tuple(vec![Type::Str]), Type::Tuple(vec![Type::Int])]
+            ))
+        );
+        assert_eq!(
+            given_this_source("let n = string.len(\"abc\")\n+ n \"x\""),
+            Err(Error::TypeMismatch(
+                "Sum".to_string(),
+                vec![Type::Int, Type::Str]
+            ))
+        );
+    }
+
+    #[test]
+    fn it_lets_unannotated_code_through() {
+        assert_eq!(
+            given_this_source("let mut x = 1\nx = \"one\"\nx"),
+            Ok(Type::Unknown)
+        );
+        assert_eq!(
+            given_this_source("fn f(a) {\n  + a 1\n}\nf(\"one\")"),
+            Ok(Type::Unknown)
+        );
+    }
+
+    #[test]
+    fn it_reports_a_literal_pattern_of_another_type() {
+        assert_eq!(
+            given_this_source("match 3 {\n  \"three\" => 1,\n  _ => 2,\n}"),
+            Err(Error::TypeMismatch("Let".to_string(), vec![Type::Int]))
+        );
+    }
+
+    #[test]
+    fn it_reports_unknown_type_names() {
+        assert_eq!(
+            given_this_source("let x: integer = 1"),
+            Err(Error::UndefinedType("integer".to_string()))
+        );
+    }
+
+    fn given_this_source(source: &str) -> Result<Type, Error> {
+        let tokens = Parser::new().parse(source);
+        let resolved = Resolver::new().resolve(&Translator::from(tokens))?;
+        Checker::new().check(&resolved)
+    }
+}